@@ -1,7 +1,14 @@
 use driver::Byakugan;
-use miette::Result;
 use std::process::ExitCode;
 
-fn main() -> Result<ExitCode> {
-    Byakugan::run()
+fn main() -> ExitCode {
+    match Byakugan::run() {
+        Ok(code) => code,
+        Err(report) => {
+            // Printed as is (i.e. without the `Error: ` prefix of a returned
+            // error), such that JSON and SARIF reports remain parseable
+            eprintln!("{report:?}");
+            ExitCode::FAILURE
+        }
+    }
 }
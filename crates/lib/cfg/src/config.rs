@@ -0,0 +1,324 @@
+use diagnostics::errors::ConfigError;
+use getset::{
+    CopyGetters,
+    Getters,
+};
+use serde::{
+    Deserialize,
+    Deserializer,
+};
+use std::{
+    collections::BTreeMap,
+    fs,
+    path::{
+        Path,
+        PathBuf,
+    },
+    time::Duration,
+};
+
+use crate::settings::{
+    Charset,
+    Color,
+    LinkStyle,
+    LogLevel,
+    ThemePreset,
+    Topic,
+};
+
+/// The name of the **configuration file** of a project.
+pub const CONFIG_FILE: &str = "byakugan.toml";
+
+/// The **configuration** of byakugan, read from the `byakugan.toml` of the
+/// project layered over the configuration file of the user.
+///
+/// ```toml
+/// [log.levels]
+/// watch = "debug"
+/// query = "trace"
+///
+/// [theme]
+/// preset = "emoji"
+/// context_lines = 2
+///
+/// [theme.colors]
+/// error = "bright red"
+/// highlights = ["#f657f8", "#1ec9d4"]
+///
+/// [watch]
+/// poll_interval = "2s"
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize, Getters)]
+#[serde(default, deny_unknown_fields)]
+#[getset(get = "pub")]
+pub struct Config {
+    /// The paths the configuration was loaded from, by precedence.
+    #[serde(skip)]
+    paths: Vec<PathBuf>,
+    /// The `[log]` settings.
+    log:   LogConfig,
+    /// The `[theme]` settings.
+    theme: ThemeConfig,
+    /// The `[watch]` settings.
+    watch: WatchConfig,
+}
+
+/// The `[log]` settings of the [`Config`].
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize, Getters)]
+#[serde(default, deny_unknown_fields)]
+#[getset(get = "pub")]
+pub struct LogConfig {
+    /// The level of individual topics (e.g. `watch = "debug"`), overriding
+    /// the `--verbosity` for the logs of that topic.
+    levels: BTreeMap<Topic, LogLevel>,
+}
+
+/// The `[theme]` settings of the [`Config`], i.e. how diagnostics (and the
+/// progress of builds) are drawn. Unset settings are those of the `preset`.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize, Getters, CopyGetters)]
+#[serde(default, deny_unknown_fields)]
+pub struct ThemeConfig {
+    /// The preset the theme is based on.
+    preset:        Option<ThemePreset>,
+    /// The characters diagnostics are drawn with.
+    #[getset(get_copy = "pub")]
+    characters:    Option<Charset>,
+    /// How links to the documentation of errors are rendered.
+    #[getset(get_copy = "pub")]
+    links:         Option<LinkStyle>,
+    /// The number of lines of source code shown around each label.
+    #[getset(get_copy = "pub")]
+    context_lines: Option<usize>,
+    /// The width diagnostics are wrapped at.
+    #[getset(get_copy = "pub")]
+    width:         Option<usize>,
+    /// The colors diagnostics are painted with.
+    #[getset(get = "pub")]
+    colors:        ThemeColors,
+}
+
+/// The `[theme.colors]` settings of the [`ThemeConfig`].
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize, Getters, CopyGetters)]
+#[serde(default, deny_unknown_fields)]
+pub struct ThemeColors {
+    /// The color of errors.
+    #[getset(get_copy = "pub")]
+    error:      Option<Color>,
+    /// The color of warnings.
+    #[getset(get_copy = "pub")]
+    warning:    Option<Color>,
+    /// The color of advice.
+    #[getset(get_copy = "pub")]
+    advice:     Option<Color>,
+    /// The color of help messages.
+    #[getset(get_copy = "pub")]
+    help:       Option<Color>,
+    /// The color of links and file names.
+    #[getset(get_copy = "pub")]
+    link:       Option<Color>,
+    /// The color of line numbers.
+    #[getset(get_copy = "pub")]
+    linum:      Option<Color>,
+    /// The colors cycled through to highlight labels.
+    #[getset(get = "pub")]
+    highlights: Option<Vec<Color>>,
+    /// The gradient the progress of builds is painted along (e.g. as tasks
+    /// keep running, or by the ratio of cache hits), from best to worst.
+    #[getset(get_copy = "pub")]
+    gradient:   Option<[Color; 5]>,
+}
+
+/// The `[watch]` settings of the [`Config`], i.e. how the files of the
+/// workspace are watched for changes.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct WatchConfig {
+    /// How often the directories that cannot be watched (e.g. past the limit
+    /// of inotify watches) are polled for changes instead (e.g. `"500ms"`).
+    #[serde(deserialize_with = "duration")]
+    poll_interval: Option<Duration>,
+}
+
+impl LogConfig {
+    fn merge(mut self, base: Self) -> Self {
+        let mut levels = base.levels;
+        levels.append(&mut self.levels);
+        Self { levels }
+    }
+}
+
+impl ThemeConfig {
+    /// The preset the theme is based on.
+    pub fn preset(&self) -> ThemePreset {
+        self.preset.unwrap_or_default()
+    }
+
+    fn merge(self, base: Self) -> Self {
+        Self {
+            preset:        self.preset.or(base.preset),
+            characters:    self.characters.or(base.characters),
+            links:         self.links.or(base.links),
+            context_lines: self.context_lines.or(base.context_lines),
+            width:         self.width.or(base.width),
+            colors:        self.colors.merge(base.colors),
+        }
+    }
+}
+
+impl ThemeColors {
+    fn merge(self, base: Self) -> Self {
+        Self {
+            error:      self.error.or(base.error),
+            warning:    self.warning.or(base.warning),
+            advice:     self.advice.or(base.advice),
+            help:       self.help.or(base.help),
+            link:       self.link.or(base.link),
+            linum:      self.linum.or(base.linum),
+            highlights: self.highlights.or(base.highlights),
+            gradient:   self.gradient.or(base.gradient),
+        }
+    }
+}
+
+impl WatchConfig {
+    /// How often directories are polled for changes, unless configured.
+    pub const DEFAULT_POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+    /// How often the directories that cannot be watched are polled for
+    /// changes instead.
+    ///
+    /// ```rust
+    /// use cfg::config::Config;
+    /// use std::{
+    ///     path::Path,
+    ///     time::Duration,
+    /// };
+    ///
+    /// let config = Config::parse(Path::new("byakugan.toml"), "[watch]\npoll_interval = \"500ms\"");
+    /// assert_eq!(config.unwrap().watch().poll_interval(), Duration::from_millis(500));
+    /// assert_eq!(Config::default().watch().poll_interval(), Duration::from_secs(1));
+    ///
+    /// assert!(Config::parse(Path::new("byakugan.toml"), "[watch]\npoll_interval = \"0s\"").is_err());
+    /// ```
+    pub fn poll_interval(&self) -> Duration {
+        self.poll_interval.unwrap_or(Self::DEFAULT_POLL_INTERVAL)
+    }
+
+    fn merge(self, base: Self) -> Self {
+        Self { poll_interval: self.poll_interval.or(base.poll_interval) }
+    }
+}
+
+/// Deserialize a (non-zero) duration written for humans, e.g. `"1s"` or
+/// `"250ms"`.
+fn duration<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<Duration>, D::Error> {
+    let duration = String::deserialize(deserializer)?;
+    match humantime::parse_duration(&duration) {
+        Ok(duration) if duration.is_zero() => {
+            Err(serde::de::Error::custom("invalid duration `0s`, expected a non-zero duration"))
+        }
+        Ok(duration) => Ok(Some(duration)),
+        Err(e) => Err(serde::de::Error::custom(format!(
+            "invalid duration `{duration}` ({e}), expected e.g. `1s` or `250ms`"
+        ))),
+    }
+}
+
+impl Config {
+    /// Load the configuration of the project containing `dir`, i.e. the
+    /// closest `byakugan.toml` within `dir` or its ancestors, merged over the
+    /// configuration of the user (i.e. `byakugan/config.toml` within their
+    /// config directory) and otherwise the defaults.
+    pub fn discover(dir: &Path) -> Result<Self, ConfigError> {
+        Self::files(dir)
+            .iter()
+            .rev()
+            .try_fold(Self::default(), |base, path| Ok(Self::load(path)?.merge(base)))
+    }
+
+    /// The configuration files found for the project containing `dir`, by
+    /// precedence: its closest `byakugan.toml`, then the configuration of the
+    /// user.
+    pub fn files(dir: &Path) -> Vec<PathBuf> {
+        let project = dir.ancestors().map(|dir| dir.join(CONFIG_FILE)).find(|path| path.is_file());
+        let user = dirs_next::config_dir()
+            .map(|dir| dir.join("byakugan").join("config.toml"))
+            .filter(|path| path.is_file());
+
+        project.into_iter().chain(user).collect()
+    }
+
+    /// Load the configuration file at `path`.
+    pub fn load(path: &Path) -> Result<Self, ConfigError> {
+        let src = fs::read_to_string(path).map_err(|e| ConfigError::Read {
+            path:   path.display().to_string().into(),
+            reason: e.to_string().into(),
+        })?;
+
+        Self::parse(path, &src)
+    }
+
+    /// Layer the configuration over `base` (e.g. that of the user), its
+    /// settings taking precedence over those of `base`.
+    ///
+    /// ```rust
+    /// use cfg::{
+    ///     config::Config,
+    ///     settings::{
+    ///         LogLevel,
+    ///         Topic,
+    ///     },
+    /// };
+    /// use std::path::Path;
+    ///
+    /// let user = "[log.levels]\nwatch = \"debug\"\nquery = \"trace\"\n\n[theme]\nwidth = 80\n";
+    /// let user = Config::parse(Path::new("config.toml"), user).unwrap();
+    /// let project = Config::parse(Path::new("byakugan.toml"), "[log.levels]\nwatch = \"warn\"");
+    ///
+    /// let config = project.unwrap().merge(user);
+    /// assert_eq!(config.log().levels().get(&Topic::Watch), Some(&LogLevel::Warn));
+    /// assert_eq!(config.log().levels().get(&Topic::Query), Some(&LogLevel::Trace));
+    /// assert_eq!(config.theme().width(), Some(80));
+    /// assert_eq!(config.paths().len(), 2);
+    /// ```
+    pub fn merge(mut self, mut base: Self) -> Self {
+        self.paths.append(&mut base.paths);
+        Self {
+            paths: self.paths,
+            log:   self.log.merge(base.log),
+            theme: self.theme.merge(base.theme),
+            watch: self.watch.merge(base.watch),
+        }
+    }
+
+    /// Parse the contents `src` of the configuration file at `path`.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use cfg::{
+    ///     config::Config,
+    ///     settings::{
+    ///         LogLevel,
+    ///         Topic,
+    ///     },
+    /// };
+    /// use std::path::Path;
+    ///
+    /// let src = "[log.levels]\nwatch = \"debug\"\n";
+    /// let config = Config::parse(Path::new("byakugan.toml"), src).unwrap();
+    /// assert_eq!(config.log().levels().get(&Topic::Watch), Some(&LogLevel::Debug));
+    ///
+    /// assert!(Config::parse(Path::new("byakugan.toml"), "[log.levels]\nlexer = \"debug\"").is_err());
+    /// ```
+    pub fn parse(path: &Path, src: &str) -> Result<Self, ConfigError> {
+        let config = toml::from_str::<Self>(src).map_err(|e| ConfigError::Invalid {
+            path:   path.display().to_string().into(),
+            src:    src.to_string(),
+            reason: e.message().trim().into(),
+            span:   e.span().map(Into::into),
+        })?;
+
+        Ok(Self { paths: vec![path.to_path_buf()], ..config })
+    }
+}
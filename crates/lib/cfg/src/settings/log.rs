@@ -1,5 +1,6 @@
 use clap::ValueEnum;
 use derive_more::IsVariant;
+use serde::Deserialize;
 // use strum::EnumIter;
 use strum_macros::Display;
 
@@ -31,7 +32,21 @@ use strum_macros::Display;
 /// assert!(LogLevel::Warn < LogLevel::Error);
 /// assert!(LogLevel::Error < LogLevel::Fatal);
 /// ```
-#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Ord, PartialOrd, ValueEnum, Hash, Display)]
+#[derive(
+    Debug,
+    Default,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    Ord,
+    PartialOrd,
+    ValueEnum,
+    Hash,
+    Display,
+    Deserialize,
+)]
+#[serde(rename_all = "lowercase")]
 pub enum LogLevel {
     /// The most fine-grained level for logging.
     //
@@ -103,32 +118,76 @@ impl From<LogLevel> for log::Level {
     }
 }
 
-/// The **category** of a log message (e.g. `Lexer`, `Parser`, etc.). This
-/// is used to **filter** log messages by their **category** (e.g.
-/// only display `Lexer` messages).
+/// The **renderer** used for the output of byakugan (i.e. logs, build
+/// progress and diagnostics).
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, ValueEnum, Hash, Display, IsVariant)]
+#[strum(serialize_all = "lowercase")]
+pub enum Renderer {
+    /// Detect the renderer from the environment, falling back to
+    /// [`Renderer::Plain`] when not attached to a terminal, when `CI` is set or
+    /// when `TERM=dumb`.
+    #[default]
+    Auto,
+    /// Live progress and colors, redrawn in place, for interactive terminals.
+    Tty,
+    /// Plain-text lines, with periodic progress lines instead of redraws (e.g.
+    /// for CI logs).
+    Plain,
+    /// Newline-delimited JSON, one object per log event.
+    Json,
+}
+
+/// The **format** of the diagnostics reported by byakugan, i.e. its own errors
+/// and (for machine-readable formats) the compiler diagnostics of failed
+/// builds.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, ValueEnum, Hash, Display, IsVariant)]
+#[strum(serialize_all = "lowercase")]
+pub enum ErrorFormat {
+    /// Graphical reports, rendered according to the [`Renderer`].
+    #[default]
+    Human,
+    /// A single line of JSON per report.
+    Json,
+    /// A SARIF 2.1.0 log per report, for code-scanning tools.
+    Sarif,
+}
+
+/// The **topic** of a log message, i.e. the subsystem of byakugan emitting it
+/// (e.g. `Watch`, `Build`, etc.). Subsystems attribute their logs to a topic
+/// by emitting them within a `tracing` span named after it, which is used to
+/// **filter** log messages by their topic (e.g. `--log-topic watch,build`) or
+/// to set the level of a single topic in the configuration file.
 #[derive(
     Debug,
-    // Display,
+    Display,
     Clone,
     Copy,
     PartialEq,
     Eq,
     Ord,
     PartialOrd,
-    // ValueEnum,
+    ValueEnum,
     Hash,
     IsVariant,
-    /* EnumIter, */
+    Deserialize,
 )]
+#[strum(serialize_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
 pub enum Topic {
-    /// A log message that is associated with the **`Lexer`**.
-    Lexer,
-    /// A log message that is associated with the **`Parser`**.
-    Parser,
-    /// A log message that is associated with the **`TypeChecker`**.
-    TypeCheck,
-    /// A log message that is associated with **`CodeGen`**.
-    CodeGen,
-    /// A log message that is associated performance **metrics**.
-    Performance,
+    /// File system events received while watching.
+    Watch,
+    /// The batching of file system events into changes triggering a run.
+    Debounce,
+    /// Queries of the build graph (e.g. targets affected by a change).
+    Query,
+    /// Builds spawned by the build tool.
+    Build,
+    /// Tests spawned by the build tool.
+    Test,
+    /// The process supervised by `bkg run`.
+    Run,
+    /// Cached state persisted between runs (e.g. installed build tools).
+    Cache,
+    /// The loading of the configuration file.
+    Config,
 }
@@ -1,7 +1,9 @@
 mod log;
 mod meta;
+mod theme;
 
 pub use self::{
     log::*,
     meta::*,
+    theme::*,
 };
@@ -0,0 +1,114 @@
+use derive_more::IsVariant;
+use owo_colors::{
+    AnsiColors,
+    DynColors,
+    Rgb,
+};
+use serde::Deserialize;
+use strum_macros::Display;
+
+/// The **preset** a theme is based on, i.e. the characters diagnostics are
+/// drawn with and the colors they are painted with.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash, Display, Deserialize, IsVariant)]
+#[strum(serialize_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
+pub enum ThemePreset {
+    /// Unicode characters for the TTY renderer, ASCII characters otherwise,
+    /// with ANSI colors (when enabled).
+    #[default]
+    Auto,
+    /// Unicode characters, with ANSI colors.
+    Unicode,
+    /// Unicode characters, with RGB colors.
+    Rgb,
+    /// Emoji and unicode characters, with ANSI colors.
+    Emoji,
+    /// ASCII characters, with ANSI colors.
+    Ascii,
+    /// ASCII characters, without colors.
+    None,
+}
+
+/// The set of **characters** diagnostics are drawn with.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Display, Deserialize, IsVariant)]
+#[strum(serialize_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
+pub enum Charset {
+    Unicode,
+    Emoji,
+    Ascii,
+}
+
+/// How **links** (e.g. to the documentation of an error) are rendered.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Display, Deserialize, IsVariant)]
+#[strum(serialize_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
+pub enum LinkStyle {
+    /// A clickable (i.e. OSC 8) hyperlink.
+    Link,
+    /// The URL itself.
+    Text,
+    /// No link at all.
+    None,
+}
+
+/// A **color** of a theme: either one of the 16 ANSI colors by name (e.g.
+/// `"red"`, `"bright blue"`) or an RGB color (e.g. `"#ff8700"`).
+///
+/// ```rust
+/// use cfg::settings::Color;
+/// use owo_colors::Rgb;
+///
+/// assert_eq!(Color::try_from("#ff8700".to_string()).unwrap().rgb(), Rgb(255, 135, 0));
+/// assert_eq!(Color::try_from("bright red".to_string()).unwrap().rgb(), Rgb(255, 0, 0));
+/// assert!(Color::try_from("reddish".to_string()).is_err());
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(try_from = "String")]
+pub struct Color(DynColors);
+
+impl Color {
+    /// The color, to paint with `owo_colors`.
+    pub fn dyn_color(self) -> DynColors {
+        self.0
+    }
+
+    /// The RGB value of the color (i.e. that of common terminals for ANSI
+    /// colors), e.g. to interpolate between colors.
+    pub fn rgb(self) -> Rgb {
+        let (r, g, b) = match self.0 {
+            DynColors::Rgb(r, g, b) => (r, g, b),
+            DynColors::Ansi(AnsiColors::Black) => (0, 0, 0),
+            DynColors::Ansi(AnsiColors::Red) => (205, 0, 0),
+            DynColors::Ansi(AnsiColors::Green) => (0, 205, 0),
+            DynColors::Ansi(AnsiColors::Yellow) => (205, 205, 0),
+            DynColors::Ansi(AnsiColors::Blue) => (0, 0, 238),
+            DynColors::Ansi(AnsiColors::Magenta) => (205, 0, 205),
+            DynColors::Ansi(AnsiColors::Cyan) => (0, 205, 205),
+            DynColors::Ansi(AnsiColors::BrightBlack) => (127, 127, 127),
+            DynColors::Ansi(AnsiColors::BrightRed) => (255, 0, 0),
+            DynColors::Ansi(AnsiColors::BrightGreen) => (0, 255, 0),
+            DynColors::Ansi(AnsiColors::BrightYellow) => (255, 255, 0),
+            DynColors::Ansi(AnsiColors::BrightBlue) => (92, 92, 255),
+            DynColors::Ansi(AnsiColors::BrightMagenta) => (255, 0, 255),
+            DynColors::Ansi(AnsiColors::BrightCyan) => (0, 255, 255),
+            DynColors::Ansi(AnsiColors::BrightWhite) => (255, 255, 255),
+            _ => (229, 229, 229),
+        };
+
+        Rgb(r, g, b)
+    }
+}
+
+impl TryFrom<String> for Color {
+    type Error = String;
+
+    fn try_from(color: String) -> Result<Self, Self::Error> {
+        color.parse::<DynColors>().map(Self).map_err(|_| {
+            format!(
+                "invalid color `{color}`, expected an ANSI color (e.g. `red` or `bright blue`) or \
+                 an RGB color (e.g. `#ff8700`)"
+            )
+        })
+    }
+}
@@ -1,10 +1,16 @@
 mod flags;
 
-use std::fmt;
+use std::{
+    fmt,
+    path::PathBuf,
+};
 
 use cfg::settings::{
     byakugan,
+    ErrorFormat,
     LogLevel,
+    Renderer,
+    Topic,
     EXE_ABOUT,
     EXE_AUTHOR,
     EXE_NAME,
@@ -41,12 +47,36 @@ Byakugan is specifically designed to work with Starlark-based build systems like
 pub struct ByakuganCli {
     /// The subcommand to execute
     #[clap(subcommand)]
-    subcommand: Option<Command>,
+    subcommand: Option<Invocation>,
 
     /// The verbosity level to use for logging
     /// [default: info]
     #[clap(short = 'v', long, required = false, value_enum, default_value = "info")]
     pub verbosity: LogLevel,
+
+    /// How to render logs, build progress and diagnostics (`auto` detects
+    /// terminals, `NO_COLOR`, `CI` and `TERM=dumb`)
+    /// [default: auto]
+    #[clap(long, required = false, value_enum, default_value = "auto", global = true)]
+    pub renderer: Renderer,
+
+    /// The format of reported errors and, when machine-readable, of the
+    /// compiler diagnostics of failed builds (e.g. `sarif` for code-scanning
+    /// tools)
+    /// [default: human]
+    #[clap(long, required = false, value_enum, default_value = "human", global = true)]
+    pub error_format: ErrorFormat,
+
+    /// Only show the logs of the given topics (e.g. `--log-topic watch,build`),
+    /// along with warnings and errors of any topic
+    #[clap(long, value_enum, value_delimiter = ',', global = true)]
+    pub log_topic: Vec<Topic>,
+
+    /// Write the spans of the session (e.g. detecting the build system,
+    /// queries, builds and every rebuild cycle) to the given file as Chrome
+    /// trace events, viewable in Perfetto (https://ui.perfetto.dev)
+    #[clap(long, value_name = "PATH", global = true)]
+    pub trace_file: Option<PathBuf>,
 }
 
 impl fmt::Display for ByakuganCli {
@@ -77,6 +107,39 @@ pub enum Command {
     Test(Test),
 }
 
+/// A **subcommand** of byakugan: either a [`Command`] watching the workspace,
+/// or a tool around it (e.g. `bkg explain`), which does not watch anything.
+#[derive(Subcommand, Debug, Display, Clone, PartialEq, Eq, Hash)]
+pub enum Invocation {
+    #[command(flatten)]
+    Watch(Command),
+    #[clap(about = "Explain an error code (e.g. `bkg explain bkg::cli::target_not_found`), with \
+                    examples and fixes.\nAll error codes are listed when none is given.")]
+    Explain(Explain),
+    #[clap(about = "Check the environment byakugan runs in (e.g. the build tools, inotify \
+                    watches, configuration and cache),\nwith a hint to fix each problem found.")]
+    #[display(fmt = "doctor")]
+    Doctor,
+    #[clap(
+        about = "Inspect or clean the cache byakugan keeps between runs (e.g. the installs of \
+                 build tools).",
+        subcommand
+    )]
+    #[display(fmt = "cache {_0}")]
+    Cache(Cache),
+}
+
+/// The subcommands of `bkg cache`.
+#[derive(Subcommand, Debug, Display, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Cache {
+    #[clap(about = "Show the cache directory and the recorded installs of build tools.")]
+    #[display(fmt = "info")]
+    Info,
+    #[clap(about = "Remove every entry of the cache, such that build tools are probed again.")]
+    #[display(fmt = "clean")]
+    Clean,
+}
+
 pub fn str(cmd: &Command) -> String {
     match cmd {
         Command::Build(cmd) => cmd.to_string().into(),
@@ -108,6 +171,11 @@ pub struct Run {
     /// The target to run (e.g. `//backend/go/web-server:web-server`)
     #[arg(required = true)]
     pub target: String,
+
+    /// Show a full-screen TUI with panes for the build progress, the output
+    /// of the process and the latest diagnostics
+    #[arg(long)]
+    pub tui: bool,
 }
 
 #[derive(Args, Debug, Default, Display, Clone, PartialEq, Eq, Hash, Getters)]
@@ -120,6 +188,16 @@ pub struct Test {
     pub targets: Vec<String>,
 }
 
+#[derive(Args, Debug, Default, Display, Clone, PartialEq, Eq, Hash, Getters)]
+#[getset(get = "pub")]
+#[display(fmt = "explain{}", "code.as_ref().map(|code| format!(\" {code}\")).unwrap_or_default()")]
+pub struct Explain {
+    /// The error code to explain, as shown in the footer of reports (e.g.
+    /// `bkg::cli::target_not_found`), with or without its `bkg::` prefix
+    #[arg(required = false)]
+    pub code: Option<String>,
+}
+
 // /// Duration of debounce (in milliseconds) for file system events
 // /// (default: 1000)
 // #[arg(short, long, default_value_t = 1000)]
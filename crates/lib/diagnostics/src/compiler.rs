@@ -0,0 +1,194 @@
+use miette::{
+    Diagnostic,
+    LabeledSpan,
+    NamedSource,
+    Severity,
+    SourceCode,
+    SourceSpan,
+};
+use std::{
+    fmt,
+    fs,
+    path::{
+        Path,
+        PathBuf,
+    },
+};
+
+/// A `path:line:column` location within a source file.
+type Location = (PathBuf, usize, usize);
+
+/// A **diagnostic** emitted by a compiler (or the build tool) during a build,
+/// parsed from its output (e.g. `error[E0425]: cannot find value` followed by
+/// ` --> src/main.rs:2:5` from `rustc`, `foo.cc:1:2: error: ...` from `clang`
+/// or `Action failed: ...` from `buck2`).
+#[derive(Debug)]
+pub struct CompilerDiagnostic {
+    severity: Severity,
+    /// The code of the diagnostic (e.g. `E0425`), if any.
+    code:     Option<String>,
+    message:  String,
+    /// The source file the diagnostic points into, if it could be read.
+    src:      Option<NamedSource>,
+    span:     Option<SourceSpan>,
+}
+
+impl CompilerDiagnostic {
+    /// Parse the header of a diagnostic from a line of build output, locating
+    /// it within the source files under `root` when the line includes its
+    /// location.
+    ///
+    /// ```rust
+    /// use diagnostics::compiler::CompilerDiagnostic;
+    /// use miette::{
+    ///     Diagnostic,
+    ///     Severity,
+    /// };
+    /// use std::path::Path;
+    ///
+    /// let diagnostic =
+    ///     CompilerDiagnostic::parse("error[E0425]: cannot find value `x`", Path::new(".")).unwrap();
+    /// assert_eq!(diagnostic.to_string(), "cannot find value `x`");
+    /// assert_eq!(diagnostic.code().unwrap().to_string(), "E0425");
+    ///
+    /// let diagnostic =
+    ///     CompilerDiagnostic::parse("a.cc:1:2: warning: unused", Path::new(".")).unwrap();
+    /// assert_eq!(diagnostic.severity(), Some(Severity::Warning));
+    ///
+    /// assert!(CompilerDiagnostic::parse("Compiling foo v0.1.0", Path::new(".")).is_none());
+    /// ```
+    pub fn parse(line: &str, root: &Path) -> Option<Self> {
+        let (mut diagnostic, location) = Self::parse_header(line)?;
+        if let Some((path, line, column)) = location {
+            diagnostic.locate(&root.join(path), line, column);
+        }
+        Some(diagnostic)
+    }
+
+    /// Parse the header of a diagnostic, alongside the location it includes
+    /// (if any).
+    fn parse_header(line: &str) -> Option<(Self, Option<Location>)> {
+        let line = line.trim();
+
+        if let Some(message) = line.strip_prefix("Action failed:") {
+            return Some((Self::new(Severity::Error, None, message.trim()), None));
+        }
+
+        // `error[E0425]: message`, `warning: message` (e.g. `rustc`)
+        for (severity, level) in [("error", Severity::Error), ("warning", Severity::Warning)] {
+            let Some(rest) = line.strip_prefix(severity) else {
+                continue;
+            };
+            let (code, rest) = match rest.strip_prefix('[').and_then(|rest| rest.split_once(']')) {
+                Some((code, rest)) => (Some(code.to_string()), rest),
+                None => (None, rest),
+            };
+            if let Some(message) = rest.strip_prefix(':') {
+                return Some((Self::new(level, code, message.trim()), None));
+            }
+        }
+
+        // `path:line:column: error: message` (e.g. `clang`)
+        for (marker, level) in [
+            (": fatal error:", Severity::Error),
+            (": error:", Severity::Error),
+            (": warning:", Severity::Warning),
+        ] {
+            let Some((location, message)) = line.split_once(marker) else {
+                continue;
+            };
+            return Some((Self::new(level, None, message.trim()), parse_location(location)));
+        }
+
+        None
+    }
+
+    fn new(severity: Severity, code: Option<String>, message: &str) -> Self {
+        Self { severity, code, message: message.to_string(), src: None, span: None }
+    }
+
+    /// Locate the diagnostic from a line of build output following its header
+    /// (e.g. ` --> src/main.rs:2:5` from `rustc`), returning whether it did.
+    pub fn locate_from(&mut self, line: &str, root: &Path) -> bool {
+        let Some((path, line, column)) = line.trim().strip_prefix("--> ").and_then(parse_location)
+        else {
+            return false;
+        };
+
+        self.locate(&root.join(path), line, column);
+        self.is_located()
+    }
+
+    /// Whether the diagnostic points into a source file.
+    pub fn is_located(&self) -> bool {
+        self.span.is_some()
+    }
+
+    /// Point the diagnostic at the (1-indexed) `line` and `column` of the
+    /// source file at `path`, if it can be read.
+    fn locate(&mut self, path: &Path, line: usize, column: usize) {
+        let Ok(src) = fs::read_to_string(path) else {
+            return;
+        };
+        let Some(start) = src.split_inclusive('\n').nth(line.saturating_sub(1)).map(|text| {
+            let start = text.as_ptr() as usize - src.as_ptr() as usize;
+            start + text.len().min(column.saturating_sub(1))
+        }) else {
+            return;
+        };
+
+        let len = src[start..].chars().next().map_or(0, char::len_utf8);
+        self.span = Some((start, len).into());
+        self.src = Some(NamedSource::new(path.display().to_string(), src));
+    }
+}
+
+/// Whether a line of build output is the **header** of a diagnostic (i.e. one
+/// [`CompilerDiagnostic::parse`] parses), without reading any source file.
+///
+/// ```rust
+/// use diagnostics::compiler::is_diagnostic_header;
+///
+/// assert!(is_diagnostic_header("foo.cc:1:2: warning: unused"));
+/// assert!(!is_diagnostic_header("errors are fine"));
+/// ```
+pub fn is_diagnostic_header(line: &str) -> bool {
+    CompilerDiagnostic::parse_header(line).is_some()
+}
+
+/// Parse a `path:line:column` location (e.g. `src/main.rs:2:5`).
+fn parse_location(location: &str) -> Option<Location> {
+    let mut parts = location.trim().rsplitn(3, ':');
+    let column = parts.next()?.parse().ok()?;
+    let line = parts.next()?.parse().ok()?;
+    let path = parts.next().filter(|path| !path.is_empty())?;
+
+    Some((PathBuf::from(path), line, column))
+}
+
+impl fmt::Display for CompilerDiagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+impl std::error::Error for CompilerDiagnostic {}
+
+impl Diagnostic for CompilerDiagnostic {
+    fn code<'a>(&'a self) -> Option<Box<dyn fmt::Display + 'a>> {
+        self.code.as_ref().map(|code| Box::new(code) as Box<dyn fmt::Display>)
+    }
+
+    fn severity(&self) -> Option<Severity> {
+        Some(self.severity)
+    }
+
+    fn source_code(&self) -> Option<&dyn SourceCode> {
+        self.src.as_ref().map(|src| src as &dyn SourceCode)
+    }
+
+    fn labels(&self) -> Option<Box<dyn Iterator<Item = LabeledSpan> + '_>> {
+        let span = self.span?;
+        Some(Box::new(std::iter::once(LabeledSpan::new_with_span(None, span))))
+    }
+}
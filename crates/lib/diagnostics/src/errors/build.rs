@@ -0,0 +1,39 @@
+use miette::Diagnostic;
+use owo_colors::OwoColorize;
+use smol_str::SmolStr;
+use thiserror::Error;
+
+use crate::compiler::CompilerDiagnostic;
+
+/// Prefix appended to all `BuildError` messages.
+pub const BUILD_ERROR_PREFIX: &str = "Build Error";
+
+/// All possible errors reported for a **build** (or test run) of the watched
+/// targets.
+#[derive(Debug, Error, Diagnostic)]
+pub enum BuildError {
+    /// Returned when a run of the build tool **fails**, alongside the compiler
+    /// diagnostics found in its output.
+    #[error(
+        "{} {} {}{} {}",
+        BUILD_ERROR_PREFIX.blue(),
+        "-".black(),
+        "Build failed".red(),
+        ":".black(),
+        .command.yellow().italic()
+    )]
+    #[diagnostic(
+        code(bkg::build::failed),
+        url(docsrs),
+        help("`{command}` exited with {status}. Please fix the diagnostics of the build.")
+    )]
+    Failed {
+        /// The command that failed (e.g. `buck2 build //foo:bar`).
+        command:     SmolStr,
+        /// The exit status of the command.
+        status:      SmolStr,
+        /// The compiler diagnostics found in the output of the command.
+        #[related]
+        diagnostics: Vec<CompilerDiagnostic>,
+    },
+}
@@ -1,5 +1,9 @@
-use miette::Diagnostic;
+use miette::{
+    Diagnostic,
+    SourceSpan,
+};
 use owo_colors::OwoColorize;
+use smol_str::SmolStr;
 use thiserror::Error;
 
 /// Prefix appended to all `ToolchainError` diagnostics.
@@ -20,7 +24,7 @@ pub enum ToolchainError {
         "buck2 not found".red(),
     )]
     #[diagnostic(
-        code(byakugan::toolchain::buck2_not_found),
+        code(bkg::toolchain::buck2_not_found),
         url(docsrs),
         help(
             "Neither `buck2` nor `buck` was found. Please ensure that either `buck2` or `buck` is \
@@ -38,7 +42,7 @@ pub enum ToolchainError {
         "bazel not found".red(),
     )]
     #[diagnostic(
-        code(byakugan::toolchain::bazel_not_found),
+        code(bkg::toolchain::bazel_not_found),
         url(docsrs),
         help(
             "Neither `bazel` nor `bazelisk` was found. Please ensure that either `bazel` or \
@@ -55,7 +59,7 @@ pub enum ToolchainError {
         "cargo not found".red(),
     )]
     #[diagnostic(
-        code(byakugan::toolchain::cargo_not_found),
+        code(bkg::toolchain::cargo_not_found),
         url(docsrs),
         help(
             "The `cargo` build tool was not found. Please ensure that `cargo` is installed and \
@@ -63,4 +67,39 @@ pub enum ToolchainError {
         )
     )]
     CargoNotFound,
+
+    /// Error returned when the version of the build tool found in the `PATH`
+    /// differs from the version **pinned** by the workspace (e.g. in
+    /// `.bazelversion`, `.buckversion` or `rust-toolchain.toml`), which would
+    /// otherwise make builds fail in unexpected ways.
+    #[error(
+        "{} {} {}{} {} {} {}",
+        TOOLCHAIN_ERROR_PREFIX.blue(),
+        "-".black(),
+        "Version mismatch".red(),
+        ":".black(),
+        .installed.yellow().italic(),
+        "is installed, but the workspace pins".black(),
+        .pinned.yellow().italic()
+    )]
+    #[diagnostic(code(bkg::toolchain::version_mismatch), url(docsrs), help("{hint}"))]
+    VersionMismatch {
+        /// The build tool (e.g. `bazel`).
+        tool:      SmolStr,
+        /// The version pinned by the workspace (e.g. `6.3.2`).
+        pinned:    SmolStr,
+        /// The version reported by the installed build tool (e.g. `bazel
+        /// 7.0.0`).
+        installed: SmolStr,
+        /// How to install the pinned version.
+        hint:      SmolStr,
+        /// The file pinning the version (e.g. `.bazelversion`).
+        file:      SmolStr,
+        /// The contents of the file pinning the version.
+        #[source_code]
+        src:       String,
+        /// The span of the pinned version within the file.
+        #[label("{tool} pinned in {file}")]
+        span:      SourceSpan,
+    },
 }
@@ -0,0 +1,37 @@
+use miette::Diagnostic;
+use owo_colors::OwoColorize;
+use smol_str::SmolStr;
+use thiserror::Error;
+
+/// Prefix appended to all `CacheError` messages.
+pub const CACHE_ERROR_PREFIX: &str = "Cache Error";
+
+/// All possible errors that can occur while **updating** the cache byakugan
+/// keeps between runs (e.g. the installs of build tools).
+#[derive(Debug, Error, Diagnostic, Clone)]
+pub enum CacheError {
+    /// Returned when an entry of the cache could not be **written** (or
+    /// removed), e.g. due to insufficient permissions or a full disk.
+    #[error(
+        "{} {} {}{} {}",
+        CACHE_ERROR_PREFIX.blue(),
+        "-".black(),
+        "Unable to update the cache".red(),
+        ":".black(),
+        .path.yellow().italic()
+    )]
+    #[diagnostic(
+        code(bkg::cache::write),
+        url(docsrs),
+        help(
+            "The cache could not be updated ({reason}). Please check the permissions of the cache \
+             directory, or clean it with `bkg cache clean`."
+        )
+    )]
+    Write {
+        /// The path of the entry (or lock file) of the cache.
+        path:   SmolStr,
+        /// Why the entry could not be written.
+        reason: SmolStr,
+    },
+}
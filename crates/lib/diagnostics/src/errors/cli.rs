@@ -28,7 +28,7 @@ pub enum CliError {
         .target.yellow().italic()
     )]
     #[diagnostic(
-        code(byakugan::cli::target_not_found),
+        code(bkg::cli::target_not_found),
         url(docsrs),
         help(
             "The target you have specified does not exist. Please check that the target is \
@@ -62,7 +62,7 @@ pub enum CliError {
         .targets.iter().map(|target| format!("  - {}", target.yellow().italic())).collect::<Vec<_>>().join("\n")
     )]
     #[diagnostic(
-        code(byakugan::cli::targets_not_found),
+        code(bkg::cli::targets_not_found),
         url(docsrs),
         help(
             "The targets you have specified do not exist. Please check that the targets are \
@@ -99,7 +99,7 @@ pub enum CliError {
           only specifying a single target."
     )]
     #[diagnostic(
-        code(byakugan::cli::multiple_targets_not_supported),
+        code(bkg::cli::multiple_targets_not_supported),
         url(docsrs),
         help(
             "Multiple targets specified but only a single target is supported. Please check that \
@@ -131,7 +131,7 @@ pub enum CliError {
           analogous configuration files for other build systems)."
     )]
     #[diagnostic(
-        code(byakugan::cli::no_build_system_detected),
+        code(bkg::cli::no_build_system_detected),
         url(docsrs),
         help(
             "No build system detected. Please check that you are executing the command from \
@@ -145,6 +145,31 @@ pub enum CliError {
         #[source_code]
         command: String,
     },
+
+    /// Returned when the **error code** given to `bkg explain` is not the code
+    /// of any error of byakugan (e.g. `bkg explain bkg::cli::target_missing`).
+    /// To resolve this error, the user should copy the code from the footer of
+    /// the report they want explained.
+    #[error(
+        "{} {} {}{} {}",
+        CLI_ERROR_PREFIX.blue(),
+        "-".black(),
+        "Unknown error code".red(),
+        ":".black(),
+        .code.yellow().italic()
+    )]
+    #[diagnostic(
+        code(bkg::cli::unknown_error_code),
+        url(docsrs),
+        help(
+            "The error code you have specified does not exist. Please copy the code from the \
+             footer of the report, or run `bkg explain` to list all error codes."
+        )
+    )]
+    UnknownErrorCode {
+        /// The code that was not found.
+        code: SmolStr,
+    },
 }
 
 // impl Diagnostic for CliError {
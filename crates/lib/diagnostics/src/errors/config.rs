@@ -0,0 +1,68 @@
+use miette::{
+    Diagnostic,
+    SourceSpan,
+};
+use owo_colors::OwoColorize;
+use smol_str::SmolStr;
+use thiserror::Error;
+
+/// Prefix appended to all `ConfigError` messages.
+pub const CONFIG_ERROR_PREFIX: &str = "Config Error";
+
+/// All possible errors that can occur while **loading** the configuration file
+/// of byakugan (i.e. `byakugan.toml`).
+#[derive(Debug, Error, Diagnostic, Clone)]
+pub enum ConfigError {
+    /// Returned when the configuration file exists but could not be **read**
+    /// (e.g. due to insufficient permissions).
+    #[error(
+        "{} {} {}{} {}",
+        CONFIG_ERROR_PREFIX.blue(),
+        "-".black(),
+        "Unable to read the configuration file".red(),
+        ":".black(),
+        .path.yellow().italic()
+    )]
+    #[diagnostic(
+        code(bkg::config::read),
+        url(docsrs),
+        help("The configuration file could not be read ({reason}). Please check its permissions.")
+    )]
+    Read {
+        /// The path of the configuration file.
+        path:   SmolStr,
+        /// Why the configuration file could not be read.
+        reason: SmolStr,
+    },
+
+    /// Returned when the configuration file is not valid **TOML**, or contains
+    /// unknown or invalid settings (e.g. an unknown log topic).
+    #[error(
+        "{} {} {}{} {}",
+        CONFIG_ERROR_PREFIX.blue(),
+        "-".black(),
+        "Invalid configuration file".red(),
+        ":".black(),
+        .path.yellow().italic()
+    )]
+    #[diagnostic(
+        code(bkg::config::invalid),
+        url(docsrs),
+        help(
+            "The configuration file is invalid: {reason}. Please fix the setting (or remove it to \
+             use its default)."
+        )
+    )]
+    Invalid {
+        /// The path of the configuration file.
+        path:   SmolStr,
+        /// The contents of the configuration file.
+        #[source_code]
+        src:    String,
+        /// Why the configuration file is invalid.
+        reason: SmolStr,
+        /// The span of the invalid setting, if known.
+        #[label("{reason}")]
+        span:   Option<SourceSpan>,
+    },
+}
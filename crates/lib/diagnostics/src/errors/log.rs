@@ -18,7 +18,7 @@ pub enum LogError {
         .0.yellow().italic()
     )]
     #[diagnostic(
-        code(byakugan::log::log_file_initialization),
+        code(bkg::log::log_file_initialization),
         url(docsrs),
         help(
             "The log file could not be initialized. Please try again (and report this issue if it \
@@ -38,7 +38,7 @@ pub enum LogError {
         .0.yellow().italic()
     )]
     #[diagnostic(
-        code(byakugan::log::log_system_initialization),
+        code(bkg::log::log_system_initialization),
         url(docsrs),
         help(
             "The log system could not be initialized. Please try again (and report this issue if \
@@ -59,7 +59,7 @@ pub enum LogError {
         .0.yellow().italic()
     )]
     #[diagnostic(
-        code(byakugan::log::log_file),
+        code(bkg::log::log_file_open),
         url(docsrs),
         help(
             "The log file could not be opened. Please try again (and report this issue if it \
@@ -67,4 +67,24 @@ pub enum LogError {
         )
     )]
     LogFileOpen(SmolStr),
+
+    /// This error is returned when the **trace file** requested via
+    /// `--trace-file` could not be **created**.
+    #[error(
+        "{} {} {}{} {}",
+        LOG_ERROR_PREFIX.blue(),
+        "-".black(),
+        "Trace file could not be created".red(),
+        ":".black(),
+        .0.yellow().italic()
+    )]
+    #[diagnostic(
+        code(bkg::log::trace_file_creation),
+        url(docsrs),
+        help(
+            "The trace file could not be created. Please check that its directory exists and is \
+             writable."
+        )
+    )]
+    TraceFileCreation(SmolStr),
 }
@@ -1,11 +1,21 @@
+pub mod build;
 pub mod build_tools;
+pub mod cache;
 pub mod cli;
+pub mod config;
 pub mod log;
+pub mod storage;
 pub mod syntax;
+pub mod watch;
 
 pub use {
+    build::*,
     build_tools::*,
+    cache::*,
     cli::*,
+    config::*,
     log::*,
+    storage::*,
     syntax::*,
+    watch::*,
 };
@@ -0,0 +1,222 @@
+use miette::Diagnostic;
+use owo_colors::OwoColorize;
+use smol_str::SmolStr;
+use thiserror::Error;
+
+/// Prefix appended to all `StorageError` messages.
+pub const STORAGE_ERROR_PREFIX: &str = "Storage Error";
+
+/// All possible errors that can occur while **reading** or **writing** the
+/// files of the storage engine of byakugan (i.e. `stones`), e.g. its SSTables.
+#[derive(Debug, Error, Diagnostic, Clone, PartialEq, Eq)]
+pub enum StorageError {
+    /// Returned when a file of the storage engine could not be **read** or
+    /// **written** (e.g. due to insufficient permissions or a full disk).
+    #[error(
+        "{} {} {}{} {}",
+        STORAGE_ERROR_PREFIX.blue(),
+        "-".black(),
+        "Unable to access".red(),
+        ":".black(),
+        .path.yellow().italic()
+    )]
+    #[diagnostic(
+        code(bkg::storage::io),
+        url(docsrs),
+        help("The file could not be accessed ({reason}). Please check its permissions.")
+    )]
+    Io {
+        /// The path of the file.
+        path:   SmolStr,
+        /// Why the file could not be accessed.
+        reason: SmolStr,
+    },
+
+    /// Returned when a file does not start with the **magic** bytes of an
+    /// SSTable, i.e. it is not an SSTable (or was written by the version of
+    /// byakugan predating the versioned format).
+    #[error(
+        "{} {} {}{} {}",
+        STORAGE_ERROR_PREFIX.blue(),
+        "-".black(),
+        "Not an SSTable".red(),
+        ":".black(),
+        .path.yellow().italic()
+    )]
+    #[diagnostic(
+        code(bkg::storage::bad_magic),
+        url(docsrs),
+        help(
+            "The file does not start with the magic bytes of an SSTable. If it was written by an \
+             older version of byakugan, please remove it."
+        )
+    )]
+    BadMagic {
+        /// The path of the file.
+        path: SmolStr,
+    },
+
+    /// Returned when an SSTable was written in a **version** of the format
+    /// this version of byakugan cannot read (e.g. by a newer byakugan).
+    #[error(
+        "{} {} {}{} {}",
+        STORAGE_ERROR_PREFIX.blue(),
+        "-".black(),
+        "Unsupported SSTable version".red(),
+        ":".black(),
+        .path.yellow().italic()
+    )]
+    #[diagnostic(
+        code(bkg::storage::unsupported_version),
+        url(docsrs),
+        help(
+            "The SSTable was written in version {version} of the format, while only versions up \
+             to {supported} can be read. Please upgrade byakugan, or remove the file."
+        )
+    )]
+    UnsupportedVersion {
+        /// The path of the file.
+        path:      SmolStr,
+        /// The version of the format the file was written in.
+        version:   u32,
+        /// The latest version of the format supported.
+        supported: u32,
+    },
+
+    /// Returned when the **checksum** of a block of an SSTable does not match
+    /// its contents, i.e. the file was corrupted after being written.
+    #[error(
+        "{} {} {}{} {}",
+        STORAGE_ERROR_PREFIX.blue(),
+        "-".black(),
+        "Checksum mismatch".red(),
+        ":".black(),
+        format!("{} (block at offset {})", .path, .offset).yellow().italic()
+    )]
+    #[diagnostic(
+        code(bkg::storage::checksum_mismatch),
+        url(docsrs),
+        help(
+            "The block at offset {offset} has a checksum of {actual:#010x}, while \
+             {expected:#010x} was recorded when writing it. The file is corrupted (e.g. by a \
+             faulty disk), please remove it."
+        )
+    )]
+    ChecksumMismatch {
+        /// The path of the file.
+        path:     SmolStr,
+        /// The offset of the block within the file.
+        offset:   u64,
+        /// The checksum recorded for the block.
+        expected: u32,
+        /// The checksum of the contents of the block.
+        actual:   u32,
+    },
+
+    /// Returned when an SSTable is **malformed** (e.g. truncated, or its index
+    /// points past the end of the file), although its checksums match.
+    #[error(
+        "{} {} {}{} {}",
+        STORAGE_ERROR_PREFIX.blue(),
+        "-".black(),
+        "Corrupted SSTable".red(),
+        ":".black(),
+        format!("{} (at offset {})", .path, .offset).yellow().italic()
+    )]
+    #[diagnostic(
+        code(bkg::storage::corrupted),
+        url(docsrs),
+        help("The SSTable is malformed at offset {offset}: {reason}. Please remove the file.")
+    )]
+    Corrupted {
+        /// The path of the file.
+        path:   SmolStr,
+        /// The offset within the file the corruption was detected at.
+        offset: u64,
+        /// What is malformed.
+        reason: SmolStr,
+    },
+
+    /// Returned when the **manifest** of an LSM tree (i.e. the list of its
+    /// live SSTables) cannot be parsed.
+    #[error(
+        "{} {} {}{} {}",
+        STORAGE_ERROR_PREFIX.blue(),
+        "-".black(),
+        "Invalid manifest".red(),
+        ":".black(),
+        .path.yellow().italic()
+    )]
+    #[diagnostic(
+        code(bkg::storage::bad_manifest),
+        url(docsrs),
+        help(
+            "The manifest could not be parsed ({reason}). As it is replaced atomically, it was \
+             modified by hand or written by another version of byakugan. Please remove its \
+             directory."
+        )
+    )]
+    BadManifest {
+        /// The path of the manifest.
+        path:   SmolStr,
+        /// Why it could not be parsed.
+        reason: SmolStr,
+    },
+
+    /// Returned when the **write-ahead log** ends with a record that was not
+    /// fully written (e.g. the process crashed while appending it), which is
+    /// dropped when replaying the log, hence only a warning.
+    #[error(
+        "{} {} {}{} {}",
+        STORAGE_ERROR_PREFIX.blue(),
+        "-".black(),
+        "Dropped the torn tail of the write-ahead log".yellow(),
+        ":".black(),
+        format!("{} (at offset {})", .path, .offset).yellow().italic()
+    )]
+    #[diagnostic(
+        code(bkg::storage::torn_wal),
+        url(docsrs),
+        severity(Warning),
+        help(
+            "The last {dropped} bytes of the write-ahead log do not hold a valid record \
+             ({reason}), such that the writes they held (if any) were lost. The log was truncated \
+             to its valid records."
+        )
+    )]
+    TornWal {
+        /// The path of the log.
+        path:    SmolStr,
+        /// The offset the valid records of the log end at.
+        offset:  u64,
+        /// The number of bytes dropped.
+        dropped: u64,
+        /// Why the tail is not a valid record.
+        reason:  SmolStr,
+    },
+
+    /// Returned when the directory of an LSM tree is **locked** by another
+    /// process (or another tree of the same process) which opened it.
+    #[error(
+        "{} {} {}{} {}",
+        STORAGE_ERROR_PREFIX.blue(),
+        "-".black(),
+        "Already in use".red(),
+        ":".black(),
+        .path.yellow().italic()
+    )]
+    #[diagnostic(
+        code(bkg::storage::locked),
+        url(docsrs),
+        help(
+            "The storage engine is already open ({reason}). Please stop the other byakugan \
+             process using it, or use another directory."
+        )
+    )]
+    Locked {
+        /// The path of the lock file.
+        path:   SmolStr,
+        /// Why it could not be locked.
+        reason: SmolStr,
+    },
+}
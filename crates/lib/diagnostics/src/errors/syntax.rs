@@ -29,7 +29,7 @@ pub enum SyntaxError {
         .repo.yellow().italic()
     )]
     #[diagnostic(
-        code(byakugan::syntax::invalid_repo_name),
+        code(bkg::syntax::invalid_repo_name),
         url(docsrs),
         help(
             "The repo name you have specified is syntactically invalid. Please check that the \
@@ -66,7 +66,7 @@ pub enum SyntaxError {
         .pkg.yellow().italic()
     )]
     #[diagnostic(
-        code(byakugan::syntax::invalid_package_name),
+        code(bkg::syntax::invalid_package_name),
         url(docsrs),
         help(
             "The package name you have specified is syntactically invalid. Please check that the \
@@ -104,7 +104,7 @@ pub enum SyntaxError {
         .target.yellow().italic()
     )]
     #[diagnostic(
-        code(byakugan::syntax::invalid_target_name),
+        code(bkg::syntax::invalid_target_name),
         url(docsrs),
         help(
             "The target name you have specified is syntactically invalid. Please check that the \
@@ -139,7 +139,7 @@ pub enum SyntaxError {
         .label.yellow().italic()
     )]
     #[diagnostic(
-        code(byakugan::syntax::invalid_label),
+        code(bkg::syntax::invalid_label),
         url(docsrs),
         help(
             "The label you have specified is syntactically invalid. Please check that the label \
@@ -0,0 +1,72 @@
+use miette::Diagnostic;
+use owo_colors::OwoColorize;
+use smol_str::SmolStr;
+use thiserror::Error;
+
+/// Prefix appended to all `WatchError` messages.
+pub const WATCH_ERROR_PREFIX: &str = "Watch Error";
+
+/// All possible errors that can occur while **watching** the files of the
+/// workspace for changes.
+#[derive(Debug, Error, Diagnostic, Clone)]
+pub enum WatchError {
+    /// Returned when the per-user limit of **inotify watches** is reached
+    /// while watching the workspace, i.e. some of its directories could not
+    /// be watched. Those are **polled** instead, hence only a warning.
+    #[error(
+        "{} {} {} {}",
+        WATCH_ERROR_PREFIX.blue(),
+        "-".black(),
+        "Reached the limit of inotify watches, polling".yellow(),
+        format!("{} {}", .polled, if *.polled == 1 { "directory" } else { "directories" })
+            .yellow()
+            .italic()
+    )]
+    #[diagnostic(
+        code(bkg::watch::watch_limit),
+        url(docsrs),
+        severity(Warning),
+        help(
+            "{} allows {max} inotify watches, one per directory. Changes to the polled \
+             directories ({dirs}) are noticed every {interval} instead of immediately. Raise the \
+             limit with `sudo sysctl fs.inotify.max_user_watches={suggested}`, and persist it with \
+             `echo fs.inotify.max_user_watches={suggested} | sudo tee \
+             /etc/sysctl.d/90-byakugan.conf`.",
+            "fs.inotify.max_user_watches".italic()
+        )
+    )]
+    WatchLimit {
+        /// The number of directories polled instead of watched.
+        polled:    usize,
+        /// The polled directories (or the first of them).
+        dirs:      SmolStr,
+        /// The per-user limit of inotify watches (if known).
+        max:       SmolStr,
+        /// The suggested limit.
+        suggested: usize,
+        /// How often the polled directories are checked for changes.
+        interval:  SmolStr,
+    },
+
+    /// Returned when a directory of the workspace could not be **watched**
+    /// (nor polled), e.g. due to insufficient permissions.
+    #[error(
+        "{} {} {}{} {}",
+        WATCH_ERROR_PREFIX.blue(),
+        "-".black(),
+        "Unable to watch".red(),
+        ":".black(),
+        .path.yellow().italic()
+    )]
+    #[diagnostic(
+        code(bkg::watch::watch_failed),
+        url(docsrs),
+        help("The directory could not be watched for changes ({reason}).")
+    )]
+    WatchFailed {
+        /// The path of the directory.
+        path:   SmolStr,
+        /// Why the directory could not be watched.
+        reason: SmolStr,
+    },
+}
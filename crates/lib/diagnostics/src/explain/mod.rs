@@ -0,0 +1,94 @@
+//! The **error index**: long-form explanations of the error codes of byakugan
+//! (e.g. `bkg::cli::target_not_found`), with examples and fixes, as rendered
+//! by `bkg explain <code>`.
+
+/// The prefix shared by the codes of all errors of byakugan.
+pub const CODE_PREFIX: &str = "bkg::";
+
+/// The long-form **explanation** of an error code, written in markdown: a
+/// `# Title` heading, followed by a description of the error, an `## Example`
+/// triggering it and how to `## Fix` it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Explanation {
+    /// The code of the error (e.g. `bkg::cli::target_not_found`).
+    pub code: &'static str,
+    /// The explanation itself, in markdown.
+    pub text: &'static str,
+}
+
+impl Explanation {
+    /// The one line summary of the explanation, i.e. its title.
+    ///
+    /// ```rust
+    /// use diagnostics::explain;
+    ///
+    /// let explanation = explain::lookup("bkg::toolchain::cargo_not_found").unwrap();
+    /// assert_eq!(explanation.summary(), "cargo not found");
+    /// ```
+    pub fn summary(&self) -> &'static str {
+        self.text.lines().next().unwrap_or_default().trim_start_matches('#').trim()
+    }
+}
+
+macro_rules! explanations {
+    ($($code:literal => $file:literal),* $(,)?) => {
+        &[$(Explanation { code: concat!("bkg::", $code), text: include_str!($file) }),*]
+    };
+}
+
+/// The explanations of all error codes, grouped by subsystem.
+pub const EXPLANATIONS: &[Explanation] = explanations![
+    "cli::target_not_found" => "cli/target_not_found.md",
+    "cli::targets_not_found" => "cli/targets_not_found.md",
+    "cli::multiple_targets_not_supported" => "cli/multiple_targets_not_supported.md",
+    "cli::no_build_system_detected" => "cli/no_build_system_detected.md",
+    "cli::unknown_error_code" => "cli/unknown_error_code.md",
+    "syntax::invalid_repo_name" => "syntax/invalid_repo_name.md",
+    "syntax::invalid_package_name" => "syntax/invalid_package_name.md",
+    "syntax::invalid_target_name" => "syntax/invalid_target_name.md",
+    "syntax::invalid_label" => "syntax/invalid_label.md",
+    "toolchain::buck2_not_found" => "toolchain/buck2_not_found.md",
+    "toolchain::bazel_not_found" => "toolchain/bazel_not_found.md",
+    "toolchain::cargo_not_found" => "toolchain/cargo_not_found.md",
+    "toolchain::version_mismatch" => "toolchain/version_mismatch.md",
+    "log::log_file_initialization" => "log/log_file_initialization.md",
+    "log::log_system_initialization" => "log/log_system_initialization.md",
+    "log::log_file_open" => "log/log_file_open.md",
+    "log::trace_file_creation" => "log/trace_file_creation.md",
+    "cache::write" => "cache/write.md",
+    "watch::watch_limit" => "watch/watch_limit.md",
+    "watch::watch_failed" => "watch/watch_failed.md",
+    "storage::io" => "storage/io.md",
+    "storage::bad_magic" => "storage/bad_magic.md",
+    "storage::unsupported_version" => "storage/unsupported_version.md",
+    "storage::checksum_mismatch" => "storage/checksum_mismatch.md",
+    "storage::corrupted" => "storage/corrupted.md",
+    "storage::torn_wal" => "storage/torn_wal.md",
+    "storage::bad_manifest" => "storage/bad_manifest.md",
+    "storage::locked" => "storage/locked.md",
+    "config::read" => "config/read.md",
+    "config::invalid" => "config/invalid.md",
+    "build::failed" => "build/failed.md",
+];
+
+/// Look up the explanation of an error code, with or without its `bkg::`
+/// prefix.
+///
+/// ```rust
+/// use diagnostics::{
+///     errors::ToolchainError,
+///     explain,
+/// };
+/// use miette::Diagnostic;
+///
+/// let code = ToolchainError::Buck2NotFound.code().unwrap().to_string();
+/// assert_eq!(explain::lookup(&code).unwrap().code, "bkg::toolchain::buck2_not_found");
+/// assert_eq!(explain::lookup("toolchain::buck2_not_found").unwrap().code, code);
+/// assert!(explain::lookup("bkg::toolchain::make_not_found").is_none());
+/// ```
+pub fn lookup(code: &str) -> Option<&'static Explanation> {
+    let code = code.trim();
+    let code = code.strip_prefix(CODE_PREFIX).unwrap_or(code);
+
+    EXPLANATIONS.iter().find(|explanation| explanation.code.strip_prefix(CODE_PREFIX) == Some(code))
+}
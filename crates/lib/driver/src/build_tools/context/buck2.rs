@@ -13,6 +13,7 @@ pub mod query {
     /// # Returns
     ///
     /// A list of all build targets in the current workspace/cell.
+    #[tracing::instrument]
     pub fn all_targets() -> Result<Vec<String>> {
         // pub fn all_targets() -> Result<Vec<Target>> { // TODO:
         tracing::debug!("Querying all build targets in the current workspace/cell...");
@@ -0,0 +1,304 @@
+use diagnostics::errors::CacheError;
+use dirs_next::cache_dir;
+use getset::{
+    CopyGetters,
+    Getters,
+};
+use serde::{
+    Deserialize,
+    Serialize,
+};
+use std::{
+    env,
+    fs::{
+        self,
+        File,
+        OpenOptions,
+    },
+    io::{
+        self,
+        Write,
+    },
+    path::{
+        Path,
+        PathBuf,
+    },
+    process::Command,
+    time::SystemTime,
+};
+
+/// The extension of the entries of the [`InstallCache`] (e.g.
+/// `buck2.installed`).
+pub const INSTALL_EXTENSION: &str = "installed";
+
+/// The file locked while the [`InstallCache`] is written, such that concurrent
+/// runs of byakugan do not interleave their writes.
+pub const LOCK_FILE: &str = "cache.lock";
+
+/// The directory byakugan caches values in between runs (e.g. whether binaries
+/// are installed), i.e. `byakugan` within the cache directory of the user.
+pub fn byakugan_cache_dir() -> Option<PathBuf> {
+    cache_dir().map(|dir| dir.join("byakugan"))
+}
+
+/// Resolve a binary within the `PATH` (as spawning it would), i.e. the first
+/// executable file named `binary` in its directories.
+pub fn find_binary(binary: &str) -> Option<PathBuf> {
+    let path = env::var_os("PATH")?;
+    env::split_paths(&path).map(|dir| dir.join(binary)).find(|path| is_executable(path))
+}
+
+#[cfg(unix)]
+fn is_executable(path: &Path) -> bool {
+    use std::os::unix::fs::PermissionsExt;
+
+    fs::metadata(path)
+        .map_or(false, |metadata| metadata.is_file() && metadata.permissions().mode() & 0o111 != 0)
+}
+
+#[cfg(not(unix))]
+fn is_executable(path: &Path) -> bool {
+    path.is_file()
+}
+
+/// The **install** of a binary, as recorded by the [`InstallCache`]: where it
+/// was resolved to, the file it was resolved to (i.e. its modification time
+/// and size) and its version.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, Getters, CopyGetters)]
+pub struct Install {
+    /// The path the binary was resolved to within the `PATH`.
+    #[getset(get = "pub")]
+    path:       PathBuf,
+    /// The modification time of the binary.
+    #[getset(get_copy = "pub")]
+    modified:   SystemTime,
+    /// The size of the binary (in bytes).
+    #[getset(get_copy = "pub")]
+    size:       u64,
+    /// The version of the binary, i.e. the first line printed by `--version`.
+    #[getset(get = "pub")]
+    version:    String,
+    /// When the binary was last probed.
+    #[getset(get_copy = "pub")]
+    checked_at: SystemTime,
+}
+
+impl Install {
+    /// Probe the binary at `path` by running it with `--version`, returning its
+    /// install unless it could not be run.
+    pub fn probe(path: &Path) -> Option<Self> {
+        let metadata = fs::metadata(path).ok()?;
+        let output = Command::new(path).arg("--version").output().ok()?;
+        let version = [&output.stdout, &output.stderr]
+            .into_iter()
+            .find_map(|output| {
+                String::from_utf8_lossy(output)
+                    .lines()
+                    .map(str::trim)
+                    .find(|line| !line.is_empty())
+                    .map(str::to_string)
+            })
+            .unwrap_or_else(|| "unknown version".to_string());
+
+        Some(Self {
+            path: path.to_path_buf(),
+            modified: metadata.modified().ok()?,
+            size: metadata.len(),
+            version,
+            checked_at: SystemTime::now(),
+        })
+    }
+
+    /// Whether the install is still current, i.e. the binary still resolves
+    /// to the same, unmodified file. This only requires looking the binary up
+    /// in the `PATH`, not running it.
+    pub fn is_current(&self, path: &Path) -> bool {
+        self.path == path &&
+            fs::metadata(path).map_or(false, |metadata| {
+                metadata.len() == self.size && metadata.modified().ok() == Some(self.modified)
+            })
+    }
+}
+
+/// The **cache** of the installs of binaries (e.g. `buck2`), such that they are
+/// only probed (i.e. run) when they change. Each install is recorded as JSON
+/// in its own entry (e.g. `buck2.installed`), revalidated on each lookup.
+#[derive(Debug, Clone, PartialEq, Eq, Getters)]
+#[getset(get = "pub")]
+pub struct InstallCache {
+    /// The directory of the entries of the cache.
+    dir: PathBuf,
+}
+
+impl InstallCache {
+    /// The cache of the installs of binaries in the given directory.
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into() }
+    }
+
+    /// The cache of the installs of binaries of the user (i.e. within
+    /// [`byakugan_cache_dir`]), if they have a cache directory.
+    pub fn user() -> Option<Self> {
+        byakugan_cache_dir().map(Self::new)
+    }
+
+    /// The path of the entry of a binary.
+    pub fn entry(&self, binary: &str) -> PathBuf {
+        self.dir.join(format!("{binary}.{INSTALL_EXTENSION}"))
+    }
+
+    /// The recorded install of a binary, without revalidating it. Entries that
+    /// cannot be read (e.g. those of older versions of byakugan) are ignored.
+    pub fn get(&self, binary: &str) -> Option<Install> {
+        let entry = fs::read_to_string(self.entry(binary)).ok()?;
+        serde_json::from_str(&entry).ok()
+    }
+
+    /// All the entries of the cache by binary, sorted, with their recorded
+    /// install (if they can be read).
+    pub fn entries(&self) -> Vec<(String, Option<Install>)> {
+        let mut binaries = fs::read_dir(&self.dir)
+            .into_iter()
+            .flatten()
+            .filter_map(|entry| entry.ok())
+            .map(|entry| entry.path())
+            .filter(|path| {
+                path.extension().map_or(false, |extension| extension == INSTALL_EXTENSION)
+            })
+            .filter_map(|path| Some(path.file_stem()?.to_string_lossy().into_owned()))
+            .collect::<Vec<_>>();
+        binaries.sort();
+
+        binaries
+            .into_iter()
+            .map(|binary| {
+                let install = self.get(&binary);
+                (binary, install)
+            })
+            .collect()
+    }
+
+    /// The total size of the files of the cache (in bytes).
+    pub fn size(&self) -> u64 {
+        fs::read_dir(&self.dir)
+            .into_iter()
+            .flatten()
+            .filter_map(|entry| entry.ok()?.metadata().ok())
+            .filter(|metadata| metadata.is_file())
+            .map(|metadata| metadata.len())
+            .sum()
+    }
+
+    /// Resolve the install of a binary, revalidating its entry: the binary is
+    /// only probed (and its entry rewritten) when it resolves to another file
+    /// than the recorded one, or that file changed. The entry of a binary that
+    /// is not installed (anymore) is removed.
+    pub fn resolve(&self, binary: &str) -> Result<Option<Install>, CacheError> {
+        let Some(path) = find_binary(binary) else {
+            tracing::debug!("{binary} not found in the PATH");
+            self.remove(binary)?;
+            return Ok(None);
+        };
+
+        if let Some(install) = self.get(binary).filter(|install| install.is_current(&path)) {
+            tracing::debug!("Found cached {binary} install at {}", path.display());
+            return Ok(Some(install));
+        }
+
+        tracing::debug!("Probing {binary} install at {}", path.display());
+        match Install::probe(&path) {
+            Some(install) => {
+                self.store(binary, &install)?;
+                Ok(Some(install))
+            }
+            None => {
+                self.remove(binary)?;
+                Ok(None)
+            }
+        }
+    }
+
+    /// Record the install of a binary, atomically (i.e. by renaming a complete
+    /// temporary file over its entry) under the lock of the cache.
+    pub fn store(&self, binary: &str, install: &Install) -> Result<(), CacheError> {
+        let entry = self.entry(binary);
+        let tmp = self.dir.join(format!(".{binary}.{INSTALL_EXTENSION}.{}", std::process::id()));
+        let json = serde_json::to_vec_pretty(install).map_err(|e| write_error(&entry, e))?;
+
+        let _lock = self.lock()?;
+        File::create(&tmp)
+            .and_then(|mut file| file.write_all(&json).and_then(|_| file.sync_all()))
+            .and_then(|_| fs::rename(&tmp, &entry))
+            .map_err(|e| {
+                let _ = fs::remove_file(&tmp);
+                write_error(&entry, e)
+            })
+    }
+
+    /// Remove the entry of a binary, if any.
+    pub fn remove(&self, binary: &str) -> Result<(), CacheError> {
+        let entry = self.entry(binary);
+        if !entry.exists() {
+            return Ok(());
+        }
+
+        let _lock = self.lock()?;
+        match fs::remove_file(&entry) {
+            Err(e) if e.kind() != io::ErrorKind::NotFound => Err(write_error(&entry, e)),
+            _ => Ok(()),
+        }
+    }
+
+    /// Remove every file of the cache (but its lock), returning how many were
+    /// removed and their total size (in bytes).
+    pub fn clean(&self) -> Result<(usize, u64), CacheError> {
+        if !self.dir.is_dir() {
+            return Ok((0, 0));
+        }
+
+        let _lock = self.lock()?;
+        let mut removed = (0, 0);
+        for entry in fs::read_dir(&self.dir).map_err(|e| write_error(&self.dir, e))? {
+            let path = entry.map_err(|e| write_error(&self.dir, e))?.path();
+            if path.file_name().map_or(false, |name| name == LOCK_FILE) {
+                continue;
+            }
+
+            let size = fs::metadata(&path).map_or(0, |metadata| metadata.len());
+            let result =
+                if path.is_dir() { fs::remove_dir_all(&path) } else { fs::remove_file(&path) };
+            result.map_err(|e| write_error(&path, e))?;
+            removed = (removed.0 + 1, removed.1 + size);
+        }
+
+        Ok(removed)
+    }
+
+    /// Lock the cache (exclusively) until the returned file is dropped,
+    /// creating its directory if needed.
+    fn lock(&self) -> Result<File, CacheError> {
+        let path = self.dir.join(LOCK_FILE);
+        let file = fs::create_dir_all(&self.dir)
+            .and_then(|_| OpenOptions::new().create(true).truncate(false).write(true).open(&path))
+            .map_err(|e| write_error(&path, e))?;
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::io::AsRawFd;
+
+            // The lock is released once the file is closed
+            if unsafe { libc::flock(file.as_raw_fd(), libc::LOCK_EX) } != 0 {
+                return Err(write_error(&path, io::Error::last_os_error()));
+            }
+        }
+
+        Ok(file)
+    }
+}
+
+fn write_error(path: &Path, reason: impl ToString) -> CacheError {
+    CacheError::Write {
+        path:   path.display().to_string().into(),
+        reason: reason.to_string().into(),
+    }
+}
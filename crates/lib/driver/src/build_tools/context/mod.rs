@@ -1,26 +1,31 @@
 mod bazel;
 mod buck2;
 mod cargo;
+pub mod install;
+pub mod pin;
+
+pub use {
+    install::*,
+    pin::*,
+};
 
 use std::{
     collections::BinaryHeap,
     env,
-    fs,
     path::{
         Path,
         PathBuf,
     },
-    process::Command,
 };
 
 use super::label::Label;
+use cfg::settings::Topic;
 use derivative::Derivative;
 use derive_more::Display;
 use diagnostics::errors::{
     CliError::NoBuildSystemDetected,
     ToolchainError,
 };
-use dirs_next::cache_dir;
 use getset::{
     Getters,
     MutGetters,
@@ -38,6 +43,7 @@ use shrinkwraprs::Shrinkwrap;
 use smartstring::alias::String;
 use strsim::levenshtein;
 use typed_builder::TypedBuilder;
+use watch::BuildTool;
 
 pub fn buck2() -> String {
     "buck2".bright_yellow().bold().to_string().into()
@@ -64,6 +70,34 @@ pub enum BuildSystem {
     // ... more build systems in the future potentially (e.g. make, ninja, etc.)
 }
 
+impl BuildSystem {
+    /// The **binaries** of the build tool of the build system, any one of which
+    /// is enough to execute build commands (e.g. `buck` or `buck2`).
+    pub fn binaries(self) -> &'static [&'static str] {
+        match self {
+            BuildSystem::Buck => &["buck2", "buck"],
+            BuildSystem::Bazel => &["bazel", "bazelisk"],
+            BuildSystem::Cargo => &["cargo"],
+        }
+    }
+
+    /// The **binary** executing build commands: the first of its
+    /// [binaries](Self::binaries) installed (or the canonical one if none is).
+    pub fn binary(self) -> &'static str {
+        let binaries = self.binaries();
+        binaries.iter().find(|binary| is_binary_installed(binary)).unwrap_or(&binaries[0])
+    }
+
+    /// The **build tool** spawned to run watched commands.
+    pub fn tool(self) -> BuildTool {
+        match self {
+            BuildSystem::Buck => BuildTool::Buck2(self.binary().to_string()),
+            BuildSystem::Bazel => BuildTool::Bazel(self.binary().to_string()),
+            BuildSystem::Cargo => BuildTool::Cargo,
+        }
+    }
+}
+
 enum TaskContext {
     Build(BuildContext),
     // ... more contexts in the future potentially (e.g. test, run, etc.)
@@ -221,20 +255,18 @@ struct Rule {
 pub fn detect_build_system(cmd: &str) -> Result<BuildSystem> {
     let current_dir = env::current_dir().into_diagnostic()?;
 
-    // Search for build system configuration files in current directory and its
-    // parent directories
-    let mut dir = PathBuf::from(&current_dir);
-    loop {
-        if let Some(build_system) = search_for_build_system(&dir) {
-            return Ok(build_system);
-        }
-
-        if !dir.pop() {
-            break; // Reached the root directory
-        }
+    match detect_workspace(&current_dir) {
+        Some((build_system, _)) => Ok(build_system),
+        None => Err(NoBuildSystemDetected { command: cmd.to_string() }.into()),
     }
+}
 
-    Err(NoBuildSystemDetected { command: cmd.to_string() }).into_diagnostic()
+/// Detect the build system and the **root** of the workspace containing `dir`,
+/// i.e. the closest directory (`dir` itself or one of its parents) containing a
+/// build system configuration file (see [`detect_build_system`]).
+pub fn detect_workspace(dir: &Path) -> Option<(BuildSystem, PathBuf)> {
+    dir.ancestors()
+        .find_map(|dir| search_for_build_system(dir).map(|build_system| (build_system, dir.into())))
 }
 
 #[tracing::instrument]
@@ -283,6 +315,8 @@ pub mod build {
 /// and Bazel. In the future, this will be replaced by a more generic solution
 /// that can be used to validate targets for any build system.
 pub(crate) fn validate_targets(subcommand: &cli::Command, build_system: BuildSystem) -> Result<()> {
+    let _topic = utils::log::topic(Topic::Query).entered();
+
     // Use the detected build system to validate the targets specified by the user
     match build_system {
         BuildSystem::Buck => {
@@ -430,7 +464,7 @@ pub(crate) fn validate_targets(subcommand: &cli::Command, build_system: BuildSys
                     //     }
                     // }
                 }
-                cli::Command::Run(cli::Run { target }) => {
+                cli::Command::Run(cli::Run { target, .. }) => {
                     tracing::debug!("Validating target: {:?}", target);
                     // if !all_targets.contains(target) {
                     //     return Err(InvalidTarget { target: target.to_string()
@@ -460,129 +494,55 @@ pub(crate) fn ensure_build_system_executable(build_system: BuildSystem) -> Resul
         BuildSystem::Buck => {
             // Ensure that `buck2` is installed and available on the `PATH`
 
-            if buck2::is_installed() {
-                Ok(())
-            } else {
-                Err(ToolchainError::Buck2NotFound).into_diagnostic()
+            if !buck2::is_installed() {
+                return Err(ToolchainError::Buck2NotFound.into());
             }
         }
         BuildSystem::Bazel => {
             // Ensure that `bazel` is installed and available on the `PATH`
 
-            if bazel::is_installed() {
-                Ok(())
-            } else {
-                Err(ToolchainError::BazelNotFound).into_diagnostic()
+            if !bazel::is_installed() {
+                return Err(ToolchainError::BazelNotFound.into());
             }
         }
         BuildSystem::Cargo => {
             // Ensure that `cargo` is installed and available on the `PATH`
 
-            if cargo::is_installed() {
-                Ok(())
-            } else {
-                Err(ToolchainError::CargoNotFound).into_diagnostic()
+            if !cargo::is_installed() {
+                return Err(ToolchainError::CargoNotFound.into());
             }
         }
     }
-}
-
-/// Check if the given binary is installed and available on the `PATH`.
-pub fn is_binary_installed(binary: &str) -> bool {
-    // Check to see if a cached value exists for this check
-    if let Some(is_installed) = cached_binary_install(binary) {
-        if is_installed {
-            tracing::debug!("Found cached {} install, skipping runtime check", binary);
-            return true;
-        }
-    }
-
-    // At this point, we know that the cached value is either not set or is set
-    // to false. We need to check if the binary is installed and available on the
-    // `PATH`.
-
-    let is_installed = check_binary_install(binary);
-
-    // Update the cache with the result of the check
-    update_binary_install_cache(binary, is_installed)
-        .expect("Unable to update binary install cache");
-
-    is_installed
-}
-
-fn update_binary_install_cache(binary: &str, is_installed: bool) -> Result<()> {
-    let install_cache_path = binary_install_cache_path(binary);
-
-    if let Some(parent) = install_cache_path.parent() {
-        if !parent.exists() {
-            fs::create_dir_all(parent).into_diagnostic()?;
-        }
-    }
 
-    if !install_cache_path.exists() {
-        // Cache file does not exist, so we need to create it
-        match create_binary_install_cache(binary) {
-            Ok(_) => {
-                tracing::debug!("Created {} install cache file", binary);
-            }
-            Err(e) => {
-                tracing::warn!("Unable to create bazel install cache file: {}", e);
-                return Err(e);
-            }
-        }
+    // Ensure that the installed build tool is the version pinned by the
+    // workspace (if any), rather than letting builds fail mysteriously
+    let root = env::current_dir().ok().and_then(|dir| detect_workspace(&dir));
+    if let Some((pin, root)) =
+        root.and_then(|(_, root)| PinnedVersion::read(build_system, &root).map(|pin| (pin, root)))
+    {
+        pin.check(build_system, &root)?;
     }
 
-    fs::write(&install_cache_path, is_installed.to_string()).into_diagnostic().map(|_| ())
-}
-
-fn check_binary_install(binary: &str) -> bool {
-    Command::new(binary).arg("--version").output().is_ok()
+    Ok(())
 }
 
-fn cached_binary_install(binary: &str) -> Option<bool> {
-    let install_cache_path = binary_install_cache_path(binary);
-
-    if install_cache_path.exists() {
-        if let Ok(install_cache) = fs::read_to_string(&install_cache_path) {
-            if let Ok(is_installed) = install_cache.parse::<bool>() {
-                return Some(is_installed);
-            }
-        }
-
-        tracing::warn!("Unable to read cached binary install value for {}, removing cache", binary);
-        fs::remove_file(&install_cache_path).expect("Unable to remove binary install cache");
-        None
-    } else {
-        // Cache file does not exist, so we need to create it
-        match create_binary_install_cache(binary) {
-            Ok(_) => Some(false),
-            Err(e) => {
-                tracing::warn!("Unable to create binary install cache: {}", e);
-                None
-            }
-        }
-    }
-}
+/// Check if the given binary is installed and available on the `PATH`.
+///
+/// **NOTE**: The install is recorded in the [`InstallCache`] of the user and
+/// only probed (i.e. run) again once the binary changes, meaning that checking
+/// an unchanged binary only requires looking it up in the `PATH`.
+pub fn is_binary_installed(binary: &str) -> bool {
+    let _topic = utils::log::topic(Topic::Cache).entered();
 
-fn create_binary_install_cache(binary: &str) -> Result<()> {
-    let install_cache_path = binary_install_cache_path(binary);
+    let Some(cache) = InstallCache::user() else {
+        return find_binary(binary).and_then(|path| Install::probe(&path)).is_some();
+    };
 
-    // Create the cache directory if it does not exist
-    if let Some(parent) = install_cache_path.parent() {
-        if !parent.exists() {
-            fs::create_dir_all(parent).into_diagnostic()?;
+    match cache.resolve(binary) {
+        Ok(install) => install.is_some(),
+        Err(e) => {
+            tracing::warn!("{:?}", Report::new(e));
+            find_binary(binary).and_then(|path| Install::probe(&path)).is_some()
         }
     }
-
-    if !install_cache_path.exists() {
-        fs::File::create(&install_cache_path).into_diagnostic()?;
-    }
-
-    Ok(())
-}
-
-fn binary_install_cache_path(binary_name: &str) -> PathBuf {
-    let cache_dir = cache_dir().expect("Unable to determine cache directory");
-
-    cache_dir.join("byakugan").join(format!("{binary_name}.installed"))
 }
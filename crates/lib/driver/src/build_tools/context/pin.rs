@@ -0,0 +1,199 @@
+use diagnostics::errors::ToolchainError;
+use getset::Getters;
+use miette::Result;
+use serde::Deserialize;
+use std::{
+    fs,
+    path::{
+        Path,
+        PathBuf,
+    },
+    process::Command,
+};
+
+use super::{
+    find_binary,
+    BuildSystem,
+};
+
+/// The files a DotSlash launcher of `buck2` is looked up at, relative to the
+/// root of the workspace.
+pub const DOTSLASH_BUCK2: &[&str] = &["buck2", "bin/buck2", "tools/buck2"];
+
+/// The **version** of the build tool **pinned** by a workspace (e.g. `6.3.2`
+/// in `.bazelversion`), along with the file pinning it.
+#[derive(Debug, Clone, PartialEq, Eq, Getters)]
+#[getset(get = "pub")]
+pub struct PinnedVersion {
+    /// The file pinning the version.
+    file:    PathBuf,
+    /// The contents of the file.
+    src:     String,
+    /// The pinned version (e.g. `6.3.2`, `2024-02-15` or `nightly`).
+    version: String,
+}
+
+impl PinnedVersion {
+    /// Read the version of the build tool pinned by the workspace at `root`,
+    /// if it pins a specific one:
+    ///
+    /// - `.bazelversion` for `bazel` (unless `latest` or a similar label),
+    /// - a DotSlash file named `buck2` (i.e. the release it downloads), or
+    ///   `.buckversion`, for `buck2`,
+    /// - `rust-toolchain.toml` (or `rust-toolchain`) for `cargo` (unless the
+    ///   `stable` channel).
+    pub fn read(build_system: BuildSystem, root: &Path) -> Option<Self> {
+        match build_system {
+            BuildSystem::Bazel => Self::from_file(root.join(".bazelversion"), |src| {
+                let version = first_line(src)?;
+                // Forks are pinned as `<fork>/<version>`
+                let version = version.rsplit('/').next().unwrap_or(version);
+                let labels = ["latest", "last_green", "last_rc", "rolling"];
+                (!labels.iter().any(|label| version.starts_with(label)))
+                    .then(|| version.to_string())
+            }),
+            BuildSystem::Buck => DOTSLASH_BUCK2
+                .iter()
+                .find_map(|file| Self::from_file(root.join(file), dotslash_release))
+                .or_else(|| {
+                    Self::from_file(root.join(".buckversion"), |src| {
+                        first_line(src).map(str::to_string)
+                    })
+                }),
+            BuildSystem::Cargo => Self::from_file(root.join("rust-toolchain.toml"), |src| {
+                toml::from_str::<RustToolchainFile>(src).ok()?.toolchain.channel
+            })
+            .or_else(|| {
+                Self::from_file(root.join("rust-toolchain"), |src| {
+                    // Either a bare channel, or the legacy TOML file
+                    match toml::from_str::<RustToolchainFile>(src) {
+                        Ok(file) => file.toolchain.channel,
+                        Err(_) => first_line(src).map(str::to_string),
+                    }
+                })
+            })
+            .filter(|pin| pin.version != "stable"),
+        }
+    }
+
+    fn from_file(file: PathBuf, version: impl FnOnce(&str) -> Option<String>) -> Option<Self> {
+        let src = fs::read_to_string(&file).ok()?;
+        let version = version(&src)?;
+        Some(Self { file, src, version })
+    }
+
+    /// Whether the version reported by a build tool (e.g. `bazel 6.3.2` or
+    /// `cargo 1.70.0 (ec8a8a0ca 2023-04-25)`) is the pinned one.
+    ///
+    /// ```rust
+    /// use driver::build_tools::{
+    ///     BuildSystem,
+    ///     PinnedVersion,
+    /// };
+    ///
+    /// let dir = std::env::temp_dir().join(format!("bkg-pin-doctest-{}", std::process::id()));
+    /// std::fs::create_dir_all(&dir).unwrap();
+    /// std::fs::write(dir.join(".bazelversion"), "6.x\n").unwrap();
+    ///
+    /// let pin = PinnedVersion::read(BuildSystem::Bazel, &dir).unwrap();
+    /// assert!(pin.matches("bazel 6.3.2"));
+    /// assert!(!pin.matches("bazel 7.0.0"));
+    /// # std::fs::remove_dir_all(&dir).unwrap();
+    /// ```
+    pub fn matches(&self, installed: &str) -> bool {
+        let pinned = self.version.trim_end_matches(".*").trim_end_matches(".x");
+        let channel = ["nightly", "beta"].into_iter().find(|channel| pinned.starts_with(channel));
+
+        installed.split(|c: char| c.is_whitespace() || c == '(' || c == ')').any(|token| {
+            match channel {
+                // e.g. `nightly-2023-05-01` is satisfied by `1.71.0-nightly`
+                Some(channel) => token.contains(&format!("-{channel}")),
+                None => token
+                    .strip_prefix(pinned)
+                    .map_or(false, |rest| rest.is_empty() || rest.starts_with(['.', '-', '+'])),
+            }
+        })
+    }
+
+    /// Check the version reported by the installed build tool (run within the
+    /// workspace at `root`, such that launchers like `rustup` or `bazelisk`
+    /// resolve the pin as builds would) against the pinned version.
+    pub fn check(&self, build_system: BuildSystem, root: &Path) -> Result<()> {
+        let Some(installed) = build_system.binaries().iter().find_map(|binary| {
+            let output = Command::new(find_binary(binary)?)
+                .arg("--version")
+                .current_dir(root)
+                .output()
+                .ok()?;
+            let version =
+                String::from_utf8_lossy(&output.stdout).lines().next()?.trim().to_string();
+            (!version.is_empty()).then_some(version)
+        }) else {
+            // Whether the build tool is installed at all is checked separately
+            return Ok(());
+        };
+
+        if self.matches(&installed) {
+            tracing::debug!("{installed} matches the version pinned in {}", self.file.display());
+            return Ok(());
+        }
+
+        let tool = build_system.binaries()[0];
+        let offset = self.src.find(&self.version).unwrap_or_default();
+        Err(ToolchainError::VersionMismatch {
+            tool:      tool.into(),
+            pinned:    self.version.as_str().into(),
+            installed: installed.into(),
+            hint:      hint(build_system, &self.version).into(),
+            file:      self.file.display().to_string().into(),
+            src:       self.src.clone(),
+            span:      (offset, self.version.len()).into(),
+        }
+        .into())
+    }
+}
+
+/// How to install the pinned version of the build tool of a build system.
+fn hint(build_system: BuildSystem, version: &str) -> String {
+    match build_system {
+        BuildSystem::Bazel => format!(
+            "Install `bazelisk` (as `bazel`), which runs the version pinned by `.bazelversion`, \
+             or install bazel {version}."
+        ),
+        BuildSystem::Buck => format!(
+            "Run `buck2` through its DotSlash file (see https://dotslash-cli.com), or install the \
+             buck2 release {version}."
+        ),
+        BuildSystem::Cargo => format!(
+            "Install the pinned toolchain with `rustup toolchain install {version}`, and run \
+             `cargo` through rustup, which selects it automatically."
+        ),
+    }
+}
+
+/// The first line of a file that is neither empty nor a comment.
+fn first_line(src: &str) -> Option<&str> {
+    src.lines().map(str::trim).find(|line| !line.is_empty() && !line.starts_with('#'))
+}
+
+/// The release a DotSlash file downloads, i.e. the tag of its GitHub release
+/// URLs (e.g. `2024-02-15` in `.../releases/download/2024-02-15/buck2-...`).
+fn dotslash_release(src: &str) -> Option<String> {
+    if !src.starts_with("#!/usr/bin/env dotslash") {
+        return None;
+    }
+
+    let (_, rest) = src.split_once("/releases/download/")?;
+    rest.split('/').next().filter(|tag| !tag.is_empty()).map(str::to_string)
+}
+
+/// The `rust-toolchain.toml` file pinning the Rust toolchain.
+#[derive(Debug, Deserialize)]
+struct RustToolchainFile {
+    toolchain: RustToolchain,
+}
+
+#[derive(Debug, Deserialize)]
+struct RustToolchain {
+    channel: Option<String>,
+}
@@ -0,0 +1,285 @@
+use derive_more::Display;
+use serde_json::Value;
+use std::{
+    fs::File,
+    io::{
+        BufRead,
+        BufReader,
+    },
+    path::{
+        Path,
+        PathBuf,
+    },
+    sync::{
+        atomic::{
+            AtomicBool,
+            AtomicUsize,
+            Ordering,
+        },
+        Arc,
+    },
+    thread::{
+        self,
+        JoinHandle,
+    },
+    time::Duration,
+};
+use tracker::{
+    TaskEvent,
+    Tracker,
+};
+
+use crate::build_tools::BuildSystem;
+
+/// How often an event log is checked for new events once fully read.
+const TAIL_INTERVAL: Duration = Duration::from_millis(50);
+
+/// Buck2 action execution kinds (i.e. `ActionExecutionKind`) denoting an
+/// action served from a cache rather than executed.
+const BUCK2_CACHED_EXECUTION_KINDS: &[&str] =
+    &["3", "9", "ACTION_CACHE", "ActionCache", "REMOTE_DEP_FILE_CACHE", "RemoteDepFileCache"];
+
+/// A machine-readable **event log** written by a build tool while building,
+/// reporting the start and end of every action.
+#[derive(Debug, Display, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum EventLog {
+    /// The JSON event log of Buck2 (i.e. `buck2 build --event-log
+    /// <path>.json-lines`, or the output of `buck2 log show`).
+    #[display(fmt = "Buck2 event log")]
+    Buck2,
+    /// The JSON Build Event Protocol stream of Bazel (i.e. `bazel build
+    /// --build_event_json_file=<path>`).
+    #[display(fmt = "Bazel build event protocol")]
+    Bazel,
+}
+
+impl EventLog {
+    /// The event log written by the given build system, if any.
+    pub fn for_build_system(build_system: BuildSystem) -> Option<Self> {
+        match build_system {
+            BuildSystem::Buck => Some(Self::Buck2),
+            BuildSystem::Bazel => Some(Self::Bazel),
+            BuildSystem::Cargo => None,
+        }
+    }
+
+    /// The arguments instructing the build tool to write the event log to
+    /// `path`.
+    pub fn args(self, path: &Path) -> Vec<String> {
+        match self {
+            Self::Buck2 => vec!["--event-log".to_string(), path.display().to_string()],
+            Self::Bazel => vec![
+                format!("--build_event_json_file={}", path.display()),
+                "--build_event_publish_all_actions".to_string(),
+            ],
+        }
+    }
+
+    /// A fresh path to write an event log to (the extension determines the
+    /// encoding of Buck2 event logs).
+    pub fn temp_path(self) -> PathBuf {
+        static COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+        let extension = match self {
+            Self::Buck2 => "json-lines",
+            Self::Bazel => "json",
+        };
+        std::env::temp_dir().join(format!(
+            "byakugan-{}-{}.{extension}",
+            std::process::id(),
+            COUNTER.fetch_add(1, Ordering::Relaxed)
+        ))
+    }
+
+    /// Parse a line of the event log into the task events it reports.
+    pub fn parse(self, line: &str) -> Vec<TaskEvent> {
+        let Ok(event) = serde_json::from_str::<Value>(line) else {
+            tracing::trace!("Skipping malformed {} line: {}", self, line);
+            return Vec::new();
+        };
+
+        match self {
+            Self::Buck2 => buck2_events(&event),
+            Self::Bazel => bep_events(&event),
+        }
+    }
+}
+
+/// Extract the task events from a Buck2 event (i.e. the start and end of the
+/// span of an action execution).
+pub fn buck2_events(event: &Value) -> Vec<TaskEvent> {
+    let Some(event) = event.get("Event") else {
+        return Vec::new(); // e.g. the invocation record or the command result
+    };
+    let Some(id) = event.get("span_id").map(count).filter(|&id| id != 0) else {
+        return Vec::new();
+    };
+    let id = id.to_string();
+    let data = &event["data"];
+
+    if let Some(action) = data.pointer("/SpanStart/data/ActionExecution") {
+        let parent = event
+            .get("parent_id")
+            .map(count)
+            .filter(|&parent| parent != 0)
+            .map(|parent| parent.to_string().into());
+        return vec![TaskEvent::Begin {
+            id: id.into(),
+            name: buck2_action_name(action).into(),
+            parent,
+        }];
+    }
+
+    if let Some(action) = data.pointer("/SpanEnd/data/ActionExecution") {
+        let mut events = Vec::new();
+        let execution_kind = match &action["execution_kind"] {
+            Value::String(kind) => kind.clone(),
+            kind => kind.to_string(),
+        };
+        if BUCK2_CACHED_EXECUTION_KINDS.contains(&execution_kind.as_str()) {
+            events.push(TaskEvent::CacheHit { id: id.clone().into() });
+        }
+        let failed = action["failed"].as_bool().unwrap_or(false);
+        events.push(TaskEvent::End { id: id.into(), success: !failed });
+        return events;
+    }
+
+    Vec::new()
+}
+
+/// The name of a Buck2 action, e.g. `root//foo:bar (rustc lib.rs)`.
+fn buck2_action_name(action: &Value) -> String {
+    let label = action
+        .pointer("/key/owner/TargetLabel/label")
+        .or_else(|| action.pointer("/key/owner/AnonTarget/name"))
+        .map(|label| match (label["package"].as_str(), label["name"].as_str()) {
+            (Some(package), Some(name)) => format!("{package}:{name}"),
+            _ => label.as_str().unwrap_or_default().to_string(),
+        })
+        .unwrap_or_default();
+    let category = action.pointer("/name/category").and_then(Value::as_str).unwrap_or("action");
+    let identifier = action.pointer("/name/identifier").and_then(Value::as_str).unwrap_or("");
+
+    format!("{label} ({category} {identifier})").replace(" )", ")").trim().to_string()
+}
+
+/// Extract the task events from a Bazel build event (i.e. configured and
+/// completed targets, completed actions and the cache hits of the build
+/// metrics).
+pub fn bep_events(event: &Value) -> Vec<TaskEvent> {
+    let id = &event["id"];
+
+    if let Some(label) = id.pointer("/targetConfigured/label").and_then(Value::as_str) {
+        return vec![TaskEvent::Begin { id: label.into(), name: label.into(), parent: None }];
+    }
+
+    if let Some(label) = id.pointer("/targetCompleted/label").and_then(Value::as_str) {
+        let success = event.pointer("/completed/success").and_then(Value::as_bool).unwrap_or(false);
+        return vec![TaskEvent::End { id: label.into(), success }];
+    }
+
+    if let Some(completed) = id.get("actionCompleted") {
+        let label = completed["label"].as_str().unwrap_or_default();
+        let output = completed["primaryOutput"].as_str().unwrap_or_default();
+        let kind = event.pointer("/action/type").and_then(Value::as_str).unwrap_or("action");
+        let success = event.pointer("/action/success").and_then(Value::as_bool).unwrap_or(false);
+        let action = format!("{label} {output}");
+        return vec![
+            TaskEvent::Begin {
+                id:     action.clone().into(),
+                name:   format!("{label} ({kind})").into(),
+                parent: Some(label.into()),
+            },
+            TaskEvent::End { id: action.into(), success },
+        ];
+    }
+
+    if let Some(summary) = event.pointer("/buildMetrics/actionSummary") {
+        let hits = summary.pointer("/actionCacheStatistics/hits").map_or(0, count);
+        let remote_hits = summary["runnerCount"]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .filter(|runner| runner["name"].as_str().map_or(false, |n| n.ends_with("cache hit")))
+            .map(|runner| count(&runner["count"]))
+            .sum::<usize>();
+        // Executed actions are reported (and counted) individually already
+        return vec![TaskEvent::Summary { completed: 0, cache_hits: hits + remote_hits }];
+    }
+
+    Vec::new()
+}
+
+/// Read a count, encoded as a number or (for 64-bit integers in proto3 JSON)
+/// a string.
+fn count(value: &Value) -> usize {
+    match value {
+        Value::Number(n) => n.as_u64().unwrap_or(0) as usize,
+        Value::String(s) => s.parse().unwrap_or(0),
+        _ => 0,
+    }
+}
+
+/// **Follows** an event log as it is written by the build tool, reporting
+/// its events to a [`Tracker`] from a background thread.
+#[derive(Debug)]
+pub struct Tail {
+    path:   PathBuf,
+    done:   Arc<AtomicBool>,
+    handle: JoinHandle<()>,
+}
+
+impl Tail {
+    pub fn follow(path: PathBuf, log: EventLog, tracker: Tracker) -> Self {
+        let done = Arc::new(AtomicBool::new(false));
+        let handle = thread::spawn({
+            let (path, done) = (path.clone(), done.clone());
+            let span = tracing::Span::current();
+            move || {
+                let _span = span.entered();
+                let mut reader = None;
+                let mut line = String::new();
+
+                loop {
+                    // Checked before reading, to drain the log once the build is done
+                    let finished = done.load(Ordering::Acquire);
+                    if reader.is_none() {
+                        reader = File::open(&path).ok().map(BufReader::new);
+                    }
+
+                    let mut progressed = false;
+                    while let Some(Ok(read)) = reader.as_mut().map(|r| r.read_line(&mut line)) {
+                        if read == 0 {
+                            break;
+                        }
+                        progressed = true;
+                        // Partially written lines are completed by the next read
+                        if line.ends_with('\n') {
+                            log.parse(&line).into_iter().for_each(|event| tracker.handle(event));
+                            line.clear();
+                        }
+                    }
+
+                    if finished {
+                        break;
+                    }
+                    if !progressed {
+                        thread::sleep(TAIL_INTERVAL);
+                    }
+                }
+            }
+        });
+
+        Self { path, done, handle }
+    }
+
+    /// Stop following the event log once the build tool has exited, reading
+    /// any remaining events and removing the log.
+    pub fn stop(self) {
+        self.done.store(true, Ordering::Release);
+        if self.handle.join().is_err() {
+            tracing::warn!("Unable to follow the event log {}", self.path.display());
+        }
+        let _ = std::fs::remove_file(&self.path);
+    }
+}
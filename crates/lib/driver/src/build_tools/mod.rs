@@ -1,7 +1,11 @@
 pub mod context;
+pub mod events;
 pub mod label;
+pub mod progress;
 
 pub use {
     context::*,
+    events::*,
     label::*,
+    progress::*,
 };
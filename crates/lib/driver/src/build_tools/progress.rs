@@ -0,0 +1,125 @@
+use parking_lot::Mutex;
+use tracker::{
+    TaskEvent,
+    Tracker,
+};
+use watch::BuildObserver;
+
+use crate::build_tools::{
+    BuildSystem,
+    EventLog,
+    Tail,
+};
+
+/// Reports the **progress** of the builds spawned while watching to a
+/// [`Tracker`]. Per-action progress (including cache hits and failing
+/// actions) is read from the event log of the build tool when it writes one,
+/// otherwise a task per requested target is rendered alongside the statistics
+/// printed by the build tool at the end of the build.
+#[derive(Debug)]
+pub struct BuildProgress {
+    event_log: Option<EventLog>,
+    current:   Mutex<Option<Build>>,
+}
+
+#[derive(Debug)]
+struct Build {
+    tracker: Tracker,
+    targets: Vec<String>,
+    tail:    Option<Tail>,
+}
+
+impl Build {
+    fn finish(self, success: bool) {
+        match self.tail {
+            Some(tail) => tail.stop(),
+            None => self.targets.iter().for_each(|target| self.tracker.end(target, success)),
+        }
+        self.tracker.finish();
+    }
+}
+
+impl BuildProgress {
+    pub fn new(build_system: BuildSystem) -> Self {
+        Self { event_log: EventLog::for_build_system(build_system), current: Mutex::new(None) }
+    }
+}
+
+impl BuildObserver for BuildProgress {
+    fn started(&self, verb: &str, targets: &[String]) -> Vec<String> {
+        let tracker = Tracker::new(&format!("{verb} {}", targets.join(" ")));
+
+        let (tail, args) = match self.event_log {
+            Some(log) => {
+                let path = log.temp_path();
+                tracing::debug!("Reading build progress from {} {}", log, path.display());
+                let args = log.args(&path);
+                (Some(Tail::follow(path, log, tracker.clone())), args)
+            }
+            None => {
+                for target in targets {
+                    tracker.begin(target.as_str(), target, None);
+                }
+                (None, Vec::new())
+            }
+        };
+
+        let build = Build { tracker, targets: targets.to_vec(), tail };
+        let previous = self.current.lock().replace(build);
+        if let Some(previous) = previous {
+            previous.finish(false);
+        }
+
+        args
+    }
+
+    fn output(&self, line: &str) {
+        let Some((completed, cache_hits)) = action_summary(line) else {
+            return;
+        };
+
+        // Event logs report every action already
+        if let Some(build) = self.current.lock().as_ref().filter(|build| build.tail.is_none()) {
+            build.tracker.handle(TaskEvent::Summary { completed, cache_hits });
+        }
+    }
+
+    fn finished(&self, success: bool) {
+        let build = self.current.lock().take();
+        if let Some(build) = build {
+            build.finish(success);
+        }
+    }
+
+    fn tracker(&self) -> Option<Tracker> {
+        self.current.lock().as_ref().map(|build| build.tracker.clone())
+    }
+}
+
+/// Extract the number of **actions** executed by the build tool and how many
+/// of them were served from a cache from its summary line, e.g. `Commands: 12
+/// (cached: 9, remote: 0, local: 3)` for Buck2 or `INFO: 12 processes: 3
+/// internal, 9 disk cache hit.` for Bazel.
+pub fn action_summary(line: &str) -> Option<(usize, usize)> {
+    let line = line.trim();
+
+    // Buck2
+    if let Some((_, commands)) = line.split_once("Commands: ") {
+        let (actions, rest) = commands.split_once(' ')?;
+        let cached = rest.split_once("cached: ")?.1;
+        let cached = cached.split(|c: char| !c.is_ascii_digit()).next()?;
+        return Some((actions.parse().ok()?, cached.parse().ok()?));
+    }
+
+    // Bazel
+    let processes = line.strip_prefix("INFO: ")?;
+    let (actions, breakdown) = processes.split_once(" processes: ")?;
+    let cache_hits = breakdown
+        .trim_end_matches('.')
+        .split(", ")
+        .filter(|kind| kind.ends_with("cache hit"))
+        .filter_map(|kind| kind.split_once(' ')?.0.parse::<usize>().ok())
+        .sum();
+
+    Some((actions.parse().ok()?, cache_hits))
+}
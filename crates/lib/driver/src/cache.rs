@@ -0,0 +1,91 @@
+use cli::Cache;
+use humansize::{
+    format_size,
+    BINARY,
+};
+use miette::Result;
+use owo_colors::{
+    OwoColorize,
+    Style,
+};
+use std::{
+    process::ExitCode,
+    time::{
+        Duration,
+        SystemTime,
+    },
+};
+
+use crate::build_tools::{
+    find_binary,
+    InstallCache,
+};
+
+/// Runs `bkg cache info|clean` on the cache of the user, printing the outcome
+/// to `stdout`.
+pub fn run(command: Cache, color: bool) -> Result<ExitCode> {
+    let paint = |style: Style| if color { style } else { Style::new() };
+    let Some(cache) = InstallCache::user() else {
+        println!("No cache directory for the user, nothing is cached");
+        return Ok(ExitCode::SUCCESS);
+    };
+
+    match command {
+        Cache::Info => {
+            println!(
+                "{} {} ({})",
+                "Cache directory:".style(paint(Style::new().bold())),
+                cache.dir().display(),
+                format_size(cache.size(), BINARY)
+            );
+
+            let entries = cache.entries();
+            if entries.is_empty() {
+                println!("No cached installs");
+            }
+            let width = entries.iter().map(|(binary, _)| binary.len()).max().unwrap_or_default();
+            for (binary, install) in entries {
+                let Some(install) = install else {
+                    println!(
+                        "  {binary:width$}  {}",
+                        "unreadable, probed again on the next run".style(paint(Style::new().red()))
+                    );
+                    continue;
+                };
+
+                let current = find_binary(&binary).map_or(false, |path| install.is_current(&path));
+                let (status, style) = if current {
+                    ("current", Style::new().green())
+                } else {
+                    ("stale, probed again on the next run", Style::new().yellow())
+                };
+                println!(
+                    "  {binary:width$}  {} ({}), checked {}: {}",
+                    install.path().display(),
+                    install.version(),
+                    ago(install.checked_at()),
+                    status.style(paint(style))
+                );
+            }
+        }
+        Cache::Clean => {
+            let (removed, size) = cache.clean()?;
+            println!(
+                "Removed {removed} entries ({}) from {}",
+                format_size(size, BINARY),
+                cache.dir().display()
+            );
+        }
+    }
+
+    Ok(ExitCode::SUCCESS)
+}
+
+/// How long ago the given time was, to the second (e.g. `2h 5m 3s ago`).
+fn ago(time: SystemTime) -> String {
+    let elapsed = time.elapsed().unwrap_or_default();
+    match elapsed.as_secs() {
+        0 => "just now".to_string(),
+        secs => format!("{} ago", humantime::format_duration(Duration::from_secs(secs))),
+    }
+}
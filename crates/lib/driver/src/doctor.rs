@@ -0,0 +1,447 @@
+use cfg::config::{
+    Config,
+    WatchConfig,
+};
+use derive_more::Display;
+use diagnostics::errors::ToolchainError;
+use getset::{
+    CopyGetters,
+    Getters,
+};
+use humansize::{
+    format_size,
+    BINARY,
+};
+use miette::{
+    Diagnostic,
+    IntoDiagnostic,
+    Result,
+};
+use owo_colors::{
+    OwoColorize,
+    Style,
+};
+use std::{
+    fs,
+    path::{
+        Path,
+        PathBuf,
+    },
+    process::{
+        Command,
+        ExitCode,
+    },
+};
+use utils::render::strip_ansi;
+use walkdir::WalkDir;
+
+use crate::build_tools::{
+    detect_workspace,
+    find_binary,
+    BuildSystem,
+    InstallCache,
+    PinnedVersion,
+};
+
+/// Where the per-user limit of inotify watches is read from (on Linux).
+pub const MAX_USER_WATCHES: &str = "/proc/sys/fs/inotify/max_user_watches";
+
+/// The size of the cache directory above which it is worth cleaning.
+pub const CACHE_SIZE_WARNING: u64 = 256 * 1024 * 1024;
+
+/// The **status** of a check of `bkg doctor`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Display)]
+pub enum Status {
+    /// Nothing to fix.
+    #[display(fmt = "pass")]
+    Pass,
+    /// byakugan works, but may misbehave (e.g. run out of inotify watches as
+    /// the workspace grows).
+    #[display(fmt = "warn")]
+    Warn,
+    /// byakugan does not work until fixed.
+    #[display(fmt = "fail")]
+    Fail,
+}
+
+/// A **check** of the environment byakugan runs in, with a hint to fix it
+/// unless it passes.
+#[derive(Debug, Clone, PartialEq, Eq, Getters, CopyGetters)]
+pub struct Check {
+    /// What is checked (e.g. `build system`).
+    #[getset(get_copy = "pub")]
+    name:    &'static str,
+    #[getset(get_copy = "pub")]
+    status:  Status,
+    /// The outcome of the check, on a single line.
+    #[getset(get = "pub")]
+    summary: String,
+    /// Additional lines detailing the outcome (e.g. the values of a setting).
+    #[getset(get = "pub")]
+    details: Vec<String>,
+    /// How to fix the check, unless it passes.
+    #[getset(get = "pub")]
+    hint:    Option<String>,
+}
+
+impl Check {
+    fn new(name: &'static str, status: Status, summary: impl Into<String>) -> Self {
+        Self { name, status, summary: summary.into(), details: Vec::new(), hint: None }
+    }
+
+    fn with_details(mut self, details: Vec<String>) -> Self {
+        self.details = details;
+        self
+    }
+
+    fn with_hint(mut self, hint: impl Into<String>) -> Self {
+        self.hint = Some(hint.into());
+        self
+    }
+}
+
+/// Runs `bkg doctor`, printing the outcome of every check of the environment
+/// to `stdout`. Fails when any check does.
+pub fn run(color: bool) -> Result<ExitCode> {
+    let paint = |style: Style| if color { style } else { Style::new() };
+    let checks = checks(&std::env::current_dir().into_diagnostic()?);
+
+    let width = checks.iter().map(|check| check.name().len()).max().unwrap_or_default();
+    for check in &checks {
+        let style = match check.status() {
+            Status::Pass => Style::new().green(),
+            Status::Warn => Style::new().yellow(),
+            Status::Fail => Style::new().red().bold(),
+        };
+        println!(
+            "{} {:width$}  {}",
+            format!("[{}]", check.status()).style(paint(style)),
+            check.name().style(paint(Style::new().bold())),
+            check.summary()
+        );
+        for detail in check.details() {
+            println!("{:indent$}- {detail}", "", indent = width + 9);
+        }
+        if let Some(hint) = check.hint() {
+            println!(
+                "{:indent$}{} {hint}",
+                "",
+                "hint:".style(paint(Style::new().cyan())),
+                indent = width + 9
+            );
+        }
+    }
+
+    let count = |status| checks.iter().filter(|check| check.status() == status).count();
+    println!(
+        "\n{} passed, {} warned, {} failed",
+        count(Status::Pass),
+        count(Status::Warn),
+        count(Status::Fail)
+    );
+
+    Ok(if count(Status::Fail) == 0 { ExitCode::SUCCESS } else { ExitCode::FAILURE })
+}
+
+/// Check the environment byakugan runs in from `dir`: the workspace and its
+/// build tools, the inotify watches it needs, its configuration and its cache.
+pub fn checks(dir: &Path) -> Vec<Check> {
+    let workspace = detect_workspace(dir);
+    let root = workspace.as_ref().map_or(dir, |(_, root)| root.as_path());
+
+    let mut checks = vec![build_system(dir, workspace.as_ref())];
+    checks.extend(build_tools(workspace.as_ref().map(|(build_system, _)| *build_system)));
+    checks.extend(workspace.as_ref().and_then(|(build_system, root)| pin(*build_system, root)));
+    checks.push(inotify_watches(root));
+    checks.push(config(dir));
+    checks.extend(cache());
+    checks
+}
+
+fn build_system(dir: &Path, workspace: Option<&(BuildSystem, PathBuf)>) -> Check {
+    match workspace {
+        Some((build_system, root)) => Check::new(
+            "build system",
+            Status::Pass,
+            format!("{} workspace at {}", build_system.binaries()[0], root.display()),
+        ),
+        None => Check::new(
+            "build system",
+            Status::Fail,
+            format!("no build system detected from {}", dir.display()),
+        )
+        .with_hint("run `bkg explain bkg::cli::no_build_system_detected`"),
+    }
+}
+
+/// Check the build tools of the detected build system (or of every build
+/// system, when none is detected) by running them, bypassing the cache of
+/// `is_binary_installed`.
+fn build_tools(detected: Option<BuildSystem>) -> Vec<Check> {
+    let build_systems = match detected {
+        Some(build_system) => vec![build_system],
+        None => vec![BuildSystem::Buck, BuildSystem::Bazel, BuildSystem::Cargo],
+    };
+
+    build_systems
+        .into_iter()
+        .map(|build_system| {
+            let binaries = build_system.binaries();
+            let versions = binaries
+                .iter()
+                .filter_map(|binary| version(binary).map(|version| (binary, version)))
+                .collect::<Vec<_>>();
+
+            if let Some((binary, _)) = versions.first() {
+                let details = versions
+                    .iter()
+                    .map(|(binary, version)| format!("{binary}: {version}"))
+                    .collect();
+                return Check::new("build tools", Status::Pass, format!("{binary} found"))
+                    .with_details(details);
+            }
+
+            let status = if detected.is_some() { Status::Fail } else { Status::Warn };
+            let code = toolchain_error(build_system).code().map(|code| code.to_string());
+            Check::new(
+                "build tools",
+                status,
+                format!("none of {} found in the PATH", binaries.join(", ")),
+            )
+            .with_hint(format!("run `bkg explain {}`", code.unwrap_or_default()))
+        })
+        .collect()
+}
+
+/// Check the installed build tool against the version pinned by the workspace,
+/// if any.
+fn pin(build_system: BuildSystem, root: &Path) -> Option<Check> {
+    let pin = PinnedVersion::read(build_system, root)?;
+    let summary = format!("{} pinned in {}", pin.version(), pin.file().display());
+
+    Some(match pin.check(build_system, root) {
+        Ok(()) => Check::new("toolchain pin", Status::Pass, summary),
+        Err(report) => Check::new("toolchain pin", Status::Fail, summary)
+            .with_details(vec![strip_ansi(&report.to_string())])
+            .with_hint(report.help().map(|help| help.to_string()).unwrap_or_default()),
+    })
+}
+
+fn toolchain_error(build_system: BuildSystem) -> ToolchainError {
+    match build_system {
+        BuildSystem::Buck => ToolchainError::Buck2NotFound,
+        BuildSystem::Bazel => ToolchainError::BazelNotFound,
+        BuildSystem::Cargo => ToolchainError::CargoNotFound,
+    }
+}
+
+/// The version of a binary (i.e. the first line printed by `--version`), if
+/// it is installed.
+fn version(binary: &str) -> Option<String> {
+    let output = Command::new(binary).arg("--version").output().ok()?;
+    let version = [&output.stdout, &output.stderr]
+        .into_iter()
+        .flat_map(|output| {
+            String::from_utf8_lossy(output).lines().map(str::to_string).collect::<Vec<_>>()
+        })
+        .find(|line| !line.trim().is_empty());
+
+    Some(
+        version.map_or_else(|| "unknown version".to_string(), |version| version.trim().to_string()),
+    )
+}
+
+/// The status of the inotify watches, given the number of watches `needed` to
+/// watch the workspace and the per-user limit `max`. The limit is shared with
+/// every other process of the user watching files (e.g. editors), hence a
+/// warning past half of it.
+///
+/// ```rust
+/// use driver::doctor::{
+///     watches_status,
+///     Status,
+/// };
+///
+/// assert_eq!(watches_status(1_000, 8_192), Status::Pass);
+/// assert_eq!(watches_status(5_000, 8_192), Status::Warn);
+/// assert_eq!(watches_status(10_000, 8_192), Status::Fail);
+/// ```
+pub fn watches_status(needed: usize, max: usize) -> Status {
+    if needed > max {
+        Status::Fail
+    } else if needed > max / 2 {
+        Status::Warn
+    } else {
+        Status::Pass
+    }
+}
+
+/// Check that the per-user limit of inotify watches allows watching the
+/// workspace, i.e. one watch per directory under its root.
+fn inotify_watches(root: &Path) -> Check {
+    if !cfg!(target_os = "linux") {
+        return Check::new("inotify", Status::Pass, "not applicable on this platform");
+    }
+
+    let Some(max) =
+        fs::read_to_string(MAX_USER_WATCHES).ok().and_then(|max| max.trim().parse::<usize>().ok())
+    else {
+        return Check::new("inotify", Status::Warn, format!("unable to read {MAX_USER_WATCHES}"));
+    };
+    let needed = WalkDir::new(root)
+        .into_iter()
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.file_type().is_dir())
+        .count();
+
+    let status = watches_status(needed, max);
+    let check = Check::new(
+        "inotify",
+        status,
+        format!("about {needed} watches needed for {}, {max} allowed", root.display()),
+    );
+    if status == Status::Pass {
+        return check;
+    }
+
+    let suggested = (needed * 2).next_power_of_two().max(524_288);
+    check.with_hint(format!(
+        "raise the limit with `sudo sysctl fs.inotify.max_user_watches={suggested}`, and persist \
+         it with `echo fs.inotify.max_user_watches={suggested} | sudo tee \
+         /etc/sysctl.d/90-byakugan.conf`"
+    ))
+}
+
+/// Check the configuration files found from `dir` (i.e. that of the project
+/// merged over that of the user), detailing the merged settings in effect.
+fn config(dir: &Path) -> Check {
+    let files =
+        Config::files(dir).iter().map(|file| file.display().to_string()).collect::<Vec<_>>();
+    let summary = match files.as_slice() {
+        [] => {
+            return Check::new("config", Status::Pass, "no configuration file, using the defaults")
+        }
+        [project, user] => format!("merged {project} over {user}"),
+        files => format!("loaded {}", files.join(", ")),
+    };
+
+    match Config::discover(dir) {
+        Ok(config) => Check::new("config", Status::Pass, summary).with_details(settings(&config)),
+        Err(e) => {
+            let code = e.code().map(|code| code.to_string()).unwrap_or_default();
+            let reason = e.help().map(|help| strip_ansi(&help.to_string()));
+            Check::new("config", Status::Fail, strip_ansi(&e.to_string()))
+                .with_details(reason.into_iter().collect())
+                .with_hint(format!("run `bkg explain {code}`"))
+        }
+    }
+}
+
+/// The settings of a configuration differing from the defaults.
+fn settings(config: &Config) -> Vec<String> {
+    let theme = config.theme();
+    let colors = theme.colors();
+    let mut settings = config
+        .log()
+        .levels()
+        .iter()
+        .map(|(topic, level)| format!("log.levels.{topic} = {}", level.to_string().to_lowercase()))
+        .collect::<Vec<_>>();
+
+    settings.push(format!("theme.preset = {}", theme.preset()));
+    settings
+        .extend(theme.characters().map(|characters| format!("theme.characters = {characters}")));
+    settings.extend(theme.links().map(|links| format!("theme.links = {links}")));
+    settings.extend(theme.context_lines().map(|lines| format!("theme.context_lines = {lines}")));
+    settings.extend(theme.width().map(|width| format!("theme.width = {width}")));
+    let poll_interval = config.watch().poll_interval();
+    if poll_interval != WatchConfig::DEFAULT_POLL_INTERVAL {
+        settings
+            .push(format!("watch.poll_interval = {}", humantime::format_duration(poll_interval)));
+    }
+
+    let customized = [
+        colors.error(),
+        colors.warning(),
+        colors.advice(),
+        colors.help(),
+        colors.link(),
+        colors.linum(),
+    ]
+    .iter()
+    .filter(|color| color.is_some())
+    .count() +
+        usize::from(colors.highlights().is_some()) +
+        usize::from(colors.gradient().is_some());
+    if customized > 0 {
+        settings.push(format!("theme.colors: {customized} customized"));
+    }
+
+    settings
+}
+
+/// Check the size of the cache directory, and that the cached installs of
+/// binaries (i.e. the `*.installed` entries) are still current.
+fn cache() -> Vec<Check> {
+    let Some(cache) = InstallCache::user() else {
+        return vec![Check::new("cache", Status::Warn, "no cache directory for the user")];
+    };
+    if !cache.dir().is_dir() {
+        return vec![Check::new(
+            "cache",
+            Status::Pass,
+            format!("{} is empty", cache.dir().display()),
+        )];
+    }
+
+    let size = cache.size();
+    let mut size_check = Check::new(
+        "cache",
+        if size > CACHE_SIZE_WARNING { Status::Warn } else { Status::Pass },
+        format!("{} in {}", format_size(size, BINARY), cache.dir().display()),
+    );
+    if size > CACHE_SIZE_WARNING {
+        size_check = size_check.with_hint("clean it with `bkg cache clean`");
+    }
+
+    vec![size_check, install_cache(&cache)]
+}
+
+/// Check the cached installs of binaries against the binaries they resolve to.
+fn install_cache(cache: &InstallCache) -> Check {
+    let entries = cache.entries();
+    let stale = entries
+        .iter()
+        .filter_map(|(binary, install)| {
+            let path = find_binary(binary);
+            let reason = match (install, &path) {
+                (Some(install), Some(path)) if install.is_current(path) => return None,
+                (None, _) => "unreadable".to_string(),
+                (Some(_), None) => "cached as installed, but not found".to_string(),
+                (Some(install), Some(path)) if install.path() != path => {
+                    format!(
+                        "cached at {}, but found at {}",
+                        install.path().display(),
+                        path.display()
+                    )
+                }
+                (Some(_), Some(_)) => "modified since it was cached".to_string(),
+            };
+            Some(format!("{}: {reason}", cache.entry(binary).display()))
+        })
+        .collect::<Vec<_>>();
+
+    if stale.is_empty() {
+        return Check::new(
+            "install cache",
+            Status::Pass,
+            format!("{} entries up to date", entries.len()),
+        );
+    }
+
+    Check::new("install cache", Status::Warn, format!("{} stale entries", stale.len()))
+        .with_details(stale)
+        .with_hint(
+            "stale entries are probed again on the next run, or clean them with `bkg cache clean`",
+        )
+}
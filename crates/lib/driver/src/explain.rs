@@ -0,0 +1,70 @@
+use cli::Explain;
+use diagnostics::{
+    errors::CliError,
+    explain::{
+        self,
+        Explanation,
+        EXPLANATIONS,
+    },
+};
+use miette::Result;
+use owo_colors::{
+    OwoColorize,
+    Style,
+};
+use std::process::ExitCode;
+
+/// Runs `bkg explain`, printing the explanation of the given error code (or
+/// the summaries of all error codes when none is given) to `stdout`.
+pub fn run(args: &Explain, color: bool) -> Result<ExitCode> {
+    let paint = |style: Style| if color { style } else { Style::new() };
+
+    let Some(code) = args.code() else {
+        let width = EXPLANATIONS.iter().map(|explanation| explanation.code.len()).max();
+        for explanation in EXPLANATIONS {
+            println!(
+                "{:width$}  {}",
+                explanation.code.style(paint(Style::new().yellow())),
+                explanation.summary(),
+                width = width.unwrap_or_default()
+            );
+        }
+        return Ok(ExitCode::SUCCESS);
+    };
+
+    let explanation =
+        explain::lookup(code).ok_or_else(|| CliError::UnknownErrorCode { code: code.into() })?;
+    print!("{}", render(explanation, paint));
+    Ok(ExitCode::SUCCESS)
+}
+
+/// Render the markdown of an explanation for the terminal: the title follows
+/// the code, headings are emphasized and code blocks are indented.
+fn render(explanation: &Explanation, paint: impl Fn(Style) -> Style) -> String {
+    let mut rendered = String::new();
+    let mut in_code = false;
+    for line in explanation.text.lines() {
+        if line.starts_with("```") {
+            in_code = !in_code;
+            continue;
+        }
+
+        let line = if in_code {
+            format!("    {}", line.style(paint(Style::new().cyan())))
+        } else if let Some(title) = line.strip_prefix("# ") {
+            format!(
+                "{} {}",
+                format!("{}:", explanation.code).style(paint(Style::new().red().bold())),
+                title.style(paint(Style::new().bold()))
+            )
+        } else if let Some(heading) = line.strip_prefix("## ") {
+            heading.style(paint(Style::new().bold().underline())).to_string()
+        } else {
+            line.to_string()
+        };
+        rendered.push_str(&line);
+        rendered.push('\n');
+    }
+
+    rendered
+}
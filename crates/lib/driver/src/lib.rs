@@ -1,11 +1,24 @@
 pub mod build_tools;
+pub mod cache;
+pub mod doctor;
+pub mod explain;
 
-use build_tools::BuildSystem;
-use cfg::settings::byakugan;
+use build_tools::{
+    BuildProgress,
+    BuildSystem,
+};
+use cfg::{
+    config::Config,
+    settings::{
+        byakugan,
+        Topic,
+    },
+};
 use clap::Parser;
 use cli::{
     ByakuganCli,
     Command,
+    Invocation,
 };
 use derive_more::Display;
 use getset::{
@@ -13,16 +26,23 @@ use getset::{
     MutGetters,
     Setters,
 };
-use miette::Result;
+use miette::{
+    IntoDiagnostic,
+    Result,
+};
 use owo_colors::OwoColorize;
 use shrinkwraprs::Shrinkwrap;
 use std::{
     process::ExitCode,
+    sync::Arc,
     time::Duration,
 };
 use typed_builder::TypedBuilder;
 use utils::log;
-use watch::Task;
+use watch::{
+    BuildObserver,
+    Task,
+};
 
 use crate::build_tools::buck2;
 
@@ -76,17 +96,50 @@ impl Byakugan {
         tracing::debug!("Canonical command issued: {}", command);
         // let state = Self::new(cli); // TODO: construct state from cli and config file
 
-        log::init(command.verbosity())?;
+        // The configuration is loaded ahead of the logging subsystem it
+        // configures, any error being reported once it is initialized
+        let config = Config::discover(&std::env::current_dir().into_diagnostic()?);
+        let settings = config.as_ref().cloned().unwrap_or_default();
+        let rendering = log::init(
+            command.verbosity(),
+            *command.renderer(),
+            *command.error_format(),
+            command.log_topic(),
+            &settings,
+            command.trace_file().as_deref(),
+        )?;
+        tracing::debug!("Rendering output with the {} renderer", rendering.renderer());
         tracing::info!("{} is running", byakugan());
 
+        {
+            let _topic = log::topic(Topic::Config).entered();
+            match config?.paths().as_slice() {
+                [] => tracing::debug!("No configuration file found, using the defaults"),
+                paths => {
+                    let paths = paths.iter().map(|path| path.display().to_string());
+                    tracing::debug!(
+                        "Loaded configuration from {}",
+                        paths.collect::<Vec<_>>().join(", ")
+                    )
+                }
+            }
+        }
+
         // If the user specified a subcommand, then use that
         // otherwise, use the default subcommand `build`.
-        let subcommand = if let Some(subcommand) = command.subcommand() {
-            tracing::debug!("Subcommand specified: {}", subcommand);
-            subcommand.clone()
-        } else {
-            tracing::debug!("No subcommand specified, using default: build");
-            Command::Build(Default::default())
+        let subcommand = match command.subcommand() {
+            Some(Invocation::Watch(subcommand)) => {
+                tracing::debug!("Subcommand specified: {}", subcommand);
+                subcommand.clone()
+            }
+            // Tools around byakugan do not watch anything, and run without a workspace
+            Some(Invocation::Explain(args)) => return explain::run(args, rendering.color()),
+            Some(Invocation::Doctor) => return doctor::run(rendering.color()),
+            Some(Invocation::Cache(command)) => return cache::run(*command, rendering.color()),
+            None => {
+                tracing::debug!("No subcommand specified, using default: build");
+                Command::Build(Default::default())
+            }
         };
 
         // Determine the build system to use.
@@ -103,7 +156,7 @@ impl Byakugan {
         // From this point on, we can assume that the build system is installed and
         // that the targets are valid, so we can safely execute the task.
 
-        match subcommand {
+        match &subcommand {
             Command::Build(args) => {
                 tracing::info!(
                     "Canonical command issued in {} mode: {}{} {}{}",
@@ -123,7 +176,6 @@ impl Byakugan {
                         .collect::<Vec<_>>()
                         .join(&",\n\t\t".yellow().italic().to_string())
                 );
-                // watch::watch(build::ctx(args))?;
             }
             Command::Run(run) => {
                 tracing::info!(
@@ -141,6 +193,11 @@ impl Byakugan {
             }
         }
 
+        // Render the progress of builds (within the TUI, if requested).
+        let observer = Arc::new(BuildProgress::new(build_system)) as Arc<dyn BuildObserver>;
+
+        watch::watch(subcommand, build_system.tool(), Some(observer), settings.watch())?;
+
         // dbg!(cli);
 
         // mutate the settings based on the command line arguments
@@ -1,7 +1,28 @@
 #[cfg(test)]
 mod driver_test_suite {
-    use driver::build_tools::label::Repo;
+    use driver::{
+        build_tools::{
+            action_summary,
+            detect_workspace,
+            label::Repo,
+            BuildSystem,
+            EventLog,
+            Install,
+            InstallCache,
+            PinnedVersion,
+        },
+        doctor::{
+            self,
+            Status,
+        },
+    };
     use pretty_assertions_sorted::assert_eq;
+    use std::fs;
+    use tracker::{
+        Stats,
+        TaskEvent,
+        Tracker,
+    };
 
     #[test]
     fn test_utils() {
@@ -16,4 +37,192 @@ mod driver_test_suite {
 
         assert_eq!(repo.to_string(), repo_component);
     }
+
+    #[test]
+    fn test_action_summary() {
+        assert_eq!(
+            action_summary(
+                "Jobs completed: 15. Time elapsed: 2.3s. Cache hits: 60%. Commands: 5 (cached: 3, \
+                 remote: 0, local: 2)"
+            ),
+            Some((5, 3))
+        );
+        assert_eq!(
+            action_summary("INFO: 12 processes: 3 internal, 8 disk cache hit, 1 remote cache hit."),
+            Some((12, 9))
+        );
+        assert_eq!(action_summary("BUILD SUCCEEDED"), None);
+    }
+
+    #[test]
+    fn test_buck2_event_log() {
+        let start = r#"{"Event":{"span_id":7,"parent_id":3,"data":{"SpanStart":{"data":{"ActionExecution":{"key":{"owner":{"TargetLabel":{"label":{"package":"root//foo","name":"bar"}}}},"name":{"category":"rustc","identifier":"lib.rs"}}}}}}}"#;
+        let end = r#"{"Event":{"span_id":7,"parent_id":3,"data":{"SpanEnd":{"data":{"ActionExecution":{"failed":false,"execution_kind":3}}}}}}"#;
+
+        assert_eq!(EventLog::Buck2.parse(start), vec![TaskEvent::Begin {
+            id:     "7".into(),
+            name:   "root//foo:bar (rustc lib.rs)".into(),
+            parent: Some("3".into()),
+        }]);
+        assert_eq!(EventLog::Buck2.parse(end), vec![
+            TaskEvent::CacheHit { id: "7".into() },
+            TaskEvent::End { id: "7".into(), success: true }
+        ]);
+        assert_eq!(EventLog::Buck2.parse(r#"{"command_line_args":["build"]}"#), vec![]);
+        assert_eq!(EventLog::Buck2.parse("not json"), vec![]);
+    }
+
+    #[test]
+    fn test_bazel_build_event_protocol() {
+        let completed =
+            r#"{"id":{"targetCompleted":{"label":"//foo:bar"}},"completed":{"success":false}}"#;
+        let metrics = r#"{"id":{"buildMetrics":{}},"buildMetrics":{"actionSummary":{"actionsExecuted":"4","actionCacheStatistics":{"hits":6},"runnerCount":[{"name":"remote cache hit","count":1},{"name":"linux-sandbox","count":3}]}}}"#;
+
+        assert_eq!(EventLog::Bazel.parse(completed), vec![TaskEvent::End {
+            id:      "//foo:bar".into(),
+            success: false,
+        }]);
+        assert_eq!(EventLog::Bazel.parse(metrics), vec![TaskEvent::Summary {
+            completed:  0,
+            cache_hits: 7,
+        }]);
+    }
+
+    #[test]
+    fn test_bazel_build_event_protocol_stats() {
+        let stream = [
+            r#"{"id":{"targetConfigured":{"label":"//foo:bar"}}}"#,
+            r#"{"id":{"actionCompleted":{"label":"//foo:bar","primaryOutput":"bar.o"}},"action":{"type":"CppCompile","success":true}}"#,
+            r#"{"id":{"actionCompleted":{"label":"//foo:bar","primaryOutput":"bar"}},"action":{"type":"CppLink","success":false}}"#,
+            r#"{"id":{"targetCompleted":{"label":"//foo:bar"}},"completed":{"success":false}}"#,
+            r#"{"id":{"buildMetrics":{}},"buildMetrics":{"actionSummary":{"actionsExecuted":"2","actionCacheStatistics":{"hits":6},"runnerCount":[{"name":"remote cache hit","count":1}]}}}"#,
+        ];
+
+        let tracker = Tracker::new("build //foo:bar");
+        stream
+            .into_iter()
+            .flat_map(|line| EventLog::Bazel.parse(line))
+            .for_each(|event| tracker.handle(event));
+
+        let mut expected = Stats::default();
+        expected.set_completed(1).set_failed(2).set_cache_hits(7);
+        assert_eq!(tracker.finish(), expected);
+    }
+
+    #[test]
+    fn test_doctor_checks() {
+        let root = std::env::temp_dir().join(format!("bkg-doctor-{}", std::process::id()));
+        let dir = root.join("src").join("nested");
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(root.join("Cargo.toml"), "[package]\n").unwrap();
+        fs::write(root.join("byakugan.toml"), "[log.levels]\nwatch = \"debug\"\n").unwrap();
+
+        assert_eq!(detect_workspace(&dir), Some((BuildSystem::Cargo, root.clone())));
+
+        let checks = doctor::checks(&dir);
+        let check = |name| checks.iter().find(|check| check.name() == name).unwrap();
+        assert_eq!(check("build system").status(), Status::Pass);
+        assert!(check("build system").summary().contains(&root.display().to_string()));
+        assert_eq!(check("config").status(), Status::Pass);
+        assert!(check("config").details().contains(&"log.levels.watch = debug".to_string()));
+
+        // The configuration of the user is merged under that of the project
+        let user = root.join("xdg").join("byakugan");
+        fs::create_dir_all(&user).unwrap();
+        fs::write(
+            user.join("config.toml"),
+            "[log.levels]\nwatch = \"trace\"\n\n[theme]\nwidth = 80\n",
+        )
+        .unwrap();
+        std::env::set_var("XDG_CONFIG_HOME", root.join("xdg"));
+        let checks = doctor::checks(&dir);
+        std::env::remove_var("XDG_CONFIG_HOME");
+        let config = checks.iter().find(|check| check.name() == "config").unwrap();
+        assert_eq!(config.status(), Status::Pass);
+        assert!(config.summary().starts_with("merged "));
+        assert!(config.details().contains(&"log.levels.watch = debug".to_string()));
+        assert!(config.details().contains(&"theme.width = 80".to_string()));
+
+        fs::write(root.join("byakugan.toml"), "[log.levels]\nlexer = \"debug\"\n").unwrap();
+        let checks = doctor::checks(&dir);
+        let config = checks.iter().find(|check| check.name() == "config").unwrap();
+        assert_eq!(config.status(), Status::Fail);
+        assert_eq!(config.hint().as_deref(), Some("run `bkg explain bkg::config::invalid`"));
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn test_install_cache() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = std::env::temp_dir().join(format!("bkg-install-cache-{}", std::process::id()));
+        let binary = dir.join("bin").join("tool");
+        fs::create_dir_all(binary.parent().unwrap()).unwrap();
+        fs::write(&binary, "#!/bin/sh\necho tool 1.2.3\n").unwrap();
+        fs::set_permissions(&binary, fs::Permissions::from_mode(0o755)).unwrap();
+
+        let install = Install::probe(&binary).unwrap();
+        assert_eq!(install.version(), "tool 1.2.3");
+        assert!(install.is_current(&binary));
+
+        let cache = InstallCache::new(dir.join("cache"));
+        cache.store("tool", &install).unwrap();
+        fs::write(cache.entry("legacy"), "true").unwrap();
+        assert_eq!(cache.get("tool"), Some(install.clone()));
+        assert_eq!(cache.entries(), vec![
+            ("legacy".to_string(), None),
+            ("tool".to_string(), Some(install.clone()))
+        ]);
+
+        // Revalidated against the file the binary resolves to
+        fs::write(&binary, "#!/bin/sh\necho tool 1.3.0 (upgraded)\n").unwrap();
+        assert!(!install.is_current(&binary));
+        assert!(!install.is_current(&dir.join("bin").join("other")));
+
+        assert_eq!(cache.clean().unwrap().0, 2);
+        assert_eq!(cache.entries(), vec![]);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_pinned_versions() {
+        let root = std::env::temp_dir().join(format!("bkg-pins-{}", std::process::id()));
+        fs::create_dir_all(root.join("tools")).unwrap();
+        let pin = |build_system| {
+            PinnedVersion::read(build_system, &root).map(|pin| pin.version().to_string())
+        };
+
+        fs::write(root.join(".bazelversion"), "# pinned\nlatest\n").unwrap();
+        assert_eq!(pin(BuildSystem::Bazel), None);
+        fs::write(root.join(".bazelversion"), "fork/6.3.2\n").unwrap();
+        assert_eq!(pin(BuildSystem::Bazel).as_deref(), Some("6.3.2"));
+
+        fs::write(root.join(".buckversion"), "2023-11-01\n").unwrap();
+        assert_eq!(pin(BuildSystem::Buck).as_deref(), Some("2023-11-01"));
+        fs::write(
+            root.join("tools").join("buck2"),
+            "#!/usr/bin/env dotslash\n{\n  \"name\": \"buck2\",\n  \"platforms\": {              \"linux-x86_64\": { \"providers\": [{ \"url\":              \"https://github.com/facebook/buck2/releases/download/2024-02-15/buck2.zst\" }] }              },\n}\n",
+        )
+        .unwrap();
+        let buck2 = PinnedVersion::read(BuildSystem::Buck, &root).unwrap();
+        assert_eq!(buck2.version(), "2024-02-15");
+        assert!(buck2.matches("buck2 2024-02-15-a1b2c3d4 <build-id>"));
+        assert!(!buck2.matches("buck2 2023-11-01-a1b2c3d4"));
+
+        fs::write(root.join("rust-toolchain"), "nightly-2023-05-01\n").unwrap();
+        let rust = PinnedVersion::read(BuildSystem::Cargo, &root).unwrap();
+        assert!(rust.matches("cargo 1.71.0-nightly (84b8abcde 2023-04-30)"));
+        assert!(!rust.matches("cargo 1.70.0 (ec8a8a0ca 2023-04-25)"));
+        fs::write(root.join("rust-toolchain.toml"), "[toolchain]\nchannel = \"stable\"\n").unwrap();
+        assert_eq!(pin(BuildSystem::Cargo), None);
+        fs::write(root.join("rust-toolchain.toml"), "[toolchain]\nchannel = \"1.70\"\n").unwrap();
+        let rust = PinnedVersion::read(BuildSystem::Cargo, &root).unwrap();
+        assert!(rust.matches("cargo 1.70.0 (ec8a8a0ca 2023-04-25)"));
+        assert!(!rust.matches("cargo 1.7.0"));
+        assert!(!rust.matches("cargo 1.700.0"));
+
+        fs::remove_dir_all(&root).unwrap();
+    }
 }
@@ -0,0 +1,168 @@
+// src/compaction.rs
+
+//! **Compaction** merges SSTables into fewer, larger ones, dropping the values
+//! their newer entries shadow, and the tombstones no older table needs. It is
+//! planned by a [`CompactionStrategy`], from the live tables of the manifest.
+
+use std::collections::HashSet;
+
+use crate::manifest::{
+    Manifest,
+    TableMeta,
+};
+
+/// The number of levels of an LSM tree.
+pub const MAX_LEVELS: u32 = 7;
+
+/// How SSTables are **compacted**.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum CompactionStrategy {
+    /// Merge tables of similar sizes together: each level is a tier, all of
+    /// whose tables are merged into a single table of the next tier once it
+    /// holds `tables` of them. Writes are cheap (each entry is rewritten once
+    /// per tier), while a key may be looked up in every table of every tier.
+    SizeTiered {
+        /// The number of tables of a tier triggering its compaction.
+        tables: usize,
+    },
+    /// Keep the tables of each level (but level 0) disjoint, with levels
+    /// growing `multiplier` times larger: level 0 is merged into level 1 once
+    /// it holds `level0_tables` tables, and a table of each level larger than
+    /// its budget is merged into the next one. Lookups read at most one table
+    /// per level, at the cost of rewriting entries more often.
+    Leveled {
+        /// The number of tables of level 0 triggering its compaction.
+        level0_tables: usize,
+        /// The size budget of level 1, in bytes.
+        base_size:     u64,
+        /// How many times larger the budget of each level is than the one of
+        /// the level above it.
+        multiplier:    u64,
+        /// The size tables are cut at when compacted, in bytes.
+        table_size:    u64,
+    },
+}
+
+impl Default for CompactionStrategy {
+    fn default() -> Self {
+        Self::leveled()
+    }
+}
+
+impl CompactionStrategy {
+    /// The size-tiered strategy, merging tiers of 4 tables.
+    pub fn size_tiered() -> Self {
+        Self::SizeTiered { tables: 4 }
+    }
+
+    /// The leveled strategy, with levels 10 times larger than the level above
+    /// them, starting at 10 MiB, and tables of 2 MiB.
+    pub fn leveled() -> Self {
+        Self::Leveled {
+            level0_tables: 4,
+            base_size:     10 * 1024 * 1024,
+            multiplier:    10,
+            table_size:    2 * 1024 * 1024,
+        }
+    }
+
+    /// The next compaction to run on the live tables of the `manifest`, if
+    /// any is needed.
+    pub fn pick(&self, manifest: &Manifest) -> Option<Compaction> {
+        let tables = manifest.tables();
+        let level = |level: u32| tables.iter().filter(move |table| table.level == level);
+
+        match *self {
+            Self::SizeTiered { tables: threshold } => {
+                (0..MAX_LEVELS).find(|l| level(*l).count() >= threshold.max(2)).map(|l| {
+                    let inputs = level(l).cloned().collect();
+                    Compaction::new(manifest, inputs, (l + 1).min(MAX_LEVELS - 1), None)
+                })
+            }
+            Self::Leveled { level0_tables, base_size, multiplier, table_size } => {
+                if level(0).count() >= level0_tables.max(1) {
+                    let inputs = level(0).cloned().collect::<Vec<_>>();
+                    return Some(Compaction::with_overlapping(manifest, inputs, 1, table_size));
+                }
+
+                let mut budget = base_size;
+                for l in 1..MAX_LEVELS - 1 {
+                    if level(l).map(|table| table.size).sum::<u64>() > budget {
+                        // The oldest table of the level
+                        let table = level(l).min_by_key(|table| table.file)?.clone();
+                        return Some(Compaction::with_overlapping(
+                            manifest,
+                            vec![table],
+                            l + 1,
+                            table_size,
+                        ));
+                    }
+                    budget = budget.saturating_mul(multiplier);
+                }
+
+                None
+            }
+        }
+    }
+}
+
+/// A **compaction**: the tables to merge, and where to write the result.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Compaction {
+    /// The tables to merge, newest first.
+    pub inputs:          Vec<TableMeta>,
+    /// The level to write the merged tables at.
+    pub output_level:    u32,
+    /// Whether tombstones can be dropped, i.e. no older table outside of the
+    /// inputs may hold their keys.
+    pub drop_tombstones: bool,
+    /// The size merged tables are cut at, if any.
+    pub table_size:      Option<u64>,
+}
+
+impl Compaction {
+    fn new(
+        manifest: &Manifest,
+        mut inputs: Vec<TableMeta>,
+        output_level: u32,
+        table_size: Option<u64>,
+    ) -> Self {
+        inputs.sort_by(|a, b| a.level.cmp(&b.level).then(b.file.cmp(&a.file)));
+
+        let smallest =
+            inputs.iter().map(|table| table.smallest.as_slice()).min().unwrap_or_default();
+        let largest = inputs.iter().map(|table| table.largest.as_slice()).max().unwrap_or_default();
+        let files = inputs.iter().map(|table| table.file).collect::<HashSet<_>>();
+        // Tables at or below the output level are older than the merged ones
+        let drop_tombstones = !manifest.tables().iter().any(|table| {
+            !files.contains(&table.file) &&
+                table.level >= output_level &&
+                table.smallest.as_slice() <= largest &&
+                smallest <= table.largest.as_slice()
+        });
+
+        Self { inputs, output_level, drop_tombstones, table_size }
+    }
+
+    /// The compaction of `inputs` with the tables of `output_level` they
+    /// overlap, which they replace.
+    fn with_overlapping(
+        manifest: &Manifest,
+        mut inputs: Vec<TableMeta>,
+        output_level: u32,
+        table_size: u64,
+    ) -> Self {
+        let smallest = inputs.iter().map(|table| table.smallest.clone()).min().unwrap_or_default();
+        let largest = inputs.iter().map(|table| table.largest.clone()).max().unwrap_or_default();
+        inputs.extend(
+            manifest
+                .tables()
+                .iter()
+                .filter(|table| table.level == output_level)
+                .filter(|table| table.smallest <= largest && smallest <= table.largest)
+                .cloned(),
+        );
+
+        Self::new(manifest, inputs, output_level, Some(table_size))
+    }
+}
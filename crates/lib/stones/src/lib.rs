@@ -1,3 +1,9 @@
+pub mod compaction;
 pub mod lsmtree;
+pub mod manifest;
 pub mod memtable;
+pub mod merge;
+pub mod options;
 pub mod sstable;
+pub mod value;
+pub mod wal;
@@ -1,76 +1,703 @@
 // src/lsmtree.rs
 
+use crate::compaction::Compaction;
+use crate::manifest::{
+    table_path,
+    Manifest,
+    TableMeta,
+};
 use crate::memtable::Memtable;
-use crate::sstable::SSTable;
-use std::fs;
+use crate::merge::{
+    EntryIter,
+    MergeIter,
+};
+use crate::options::Options;
+use crate::sstable::{
+    io_error,
+    FilterStats,
+    SSTableBuilder,
+    SSTableReader,
+};
+use crate::value::Value;
+use crate::wal::{
+    log_path,
+    logs,
+    Record,
+    Wal,
+};
+use arc_swap::ArcSwap;
+use diagnostics::errors::StorageError;
+use parking_lot::{
+    Condvar,
+    Mutex,
+    RwLock,
+};
+use std::collections::HashMap;
+use std::fs::{
+    self,
+    File,
+    OpenOptions,
+};
 use std::io;
+use std::ops::{
+    Bound,
+    RangeBounds,
+};
+use std::path::Path;
+use std::sync::mpsc::{
+    self,
+    Sender,
+};
 use std::sync::Arc;
+use std::thread::{
+    self,
+    JoinHandle,
+};
 
-const MAX_MEMTABLE_SIZE: usize = 1000;
+/// The name of the lock file within the directory of an LSM tree.
+pub const LOCK_FILE: &str = "LOCK";
 
 pub struct LsmTree {
-    memtable:      Arc<Memtable>,
-    sst_directory: String,
+    /// The memtables, shared with the flush thread.
+    memtables:  Arc<Memtables>,
+    /// The log of the active memtable, locked while writing (and rotating it).
+    wal:        Mutex<Wal>,
+    /// The SSTables, shared with the flush and compaction threads.
+    tables:     Arc<Tables>,
+    /// Dropped before the compactor it wakes, such that it finishes flushing
+    /// the immutable memtables before the compaction thread stops.
+    flusher:    Worker,
+    _compactor: Worker,
+    /// Released last, once the threads are done with the directory.
+    _lock:      DirLock,
 }
 
 impl LsmTree {
-    pub fn new(sst_directory: &str) -> Self {
-        LsmTree {
-            memtable:      Arc::new(Memtable::new()),
-            sst_directory: sst_directory.to_owned(),
+    /// Open the LSM tree within the directory at `path` (creating it if
+    /// needed) with the given `options`, recovering its SSTables from its
+    /// manifest, and replaying the writes of its logs not flushed yet. The
+    /// directory is locked until the tree is closed (or dropped), such that no
+    /// other process opens it meanwhile.
+    pub fn open(path: impl AsRef<Path>, options: Options) -> Result<Self, StorageError> {
+        let path = path.as_ref();
+        let sst_directory = path.to_str().ok_or_else(|| {
+            let e = io::Error::new(io::ErrorKind::InvalidInput, "the path is not valid UTF-8");
+            io_error(&path.to_string_lossy(), e)
+        })?;
+        fs::create_dir_all(sst_directory).map_err(|e| io_error(sst_directory, e))?;
+        let lock = DirLock::acquire(sst_directory)?;
+
+        let manifest = Manifest::load(sst_directory)?;
+        let leftovers = manifest.remove_leftovers(sst_directory)?;
+        if leftovers > 0 {
+            tracing::debug!("Removed {leftovers} SSTables missing from the manifest");
+        }
+
+        // The memtables of all the logs but the last one were being flushed
+        let mut logs = logs(sst_directory)?;
+        let active_log = logs.pop().unwrap_or(1);
+        let mut immutables = Vec::new();
+        for log in logs {
+            let memtable = Memtable::new();
+            Wal::open(&log_path(sst_directory, log), options.sync, |record| {
+                memtable.apply(record)
+            })?;
+            immutables.insert(0, LoggedMemtable { memtable: Arc::new(memtable), log });
+        }
+        let memtable = Memtable::new();
+        let wal = Wal::open(&log_path(sst_directory, active_log), options.sync, |record| {
+            memtable.apply(record)
+        })?;
+        let active = LoggedMemtable { memtable: Arc::new(memtable), log: active_log };
+
+        let tables = Arc::new(Tables {
+            dir: sst_directory.to_owned(),
+            options,
+            manifest: RwLock::new(manifest),
+            readers: RwLock::new(HashMap::new()),
+            compacting: Mutex::new(()),
+        });
+        let memtables = Arc::new(Memtables {
+            current: ArcSwap::from_pointee(MemtableSet { active, immutables }),
+            error:   Mutex::new(None),
+            flushed: Condvar::new(),
+        });
+
+        let compactor = {
+            let tables = tables.clone();
+            Worker::spawn("stones-compaction", sst_directory, move || {
+                if let Err(e) = tables.compact() {
+                    tracing::warn!("{:?}", miette::Report::new(e));
+                }
+            })?
+        };
+        let flusher = {
+            let (memtables, tables) = (memtables.clone(), tables.clone());
+            let compactor = compactor.waker();
+            Worker::spawn("stones-flush", sst_directory, move || match memtables.flush(&tables) {
+                Ok(0) => {}
+                Ok(_) => drop(compactor.send(())),
+                Err(e) => memtables.fail(e),
+            })?
+        };
+        // The memtables recovered may need flushing, and the tables compacting
+        flusher.wake();
+        compactor.wake();
+
+        Ok(LsmTree {
+            memtables,
+            wal: Mutex::new(wal),
+            tables,
+            flusher,
+            _compactor: compactor,
+            _lock: lock,
+        })
+    }
+
+    pub fn put(&self, key: &[u8], value: &[u8]) -> Result<(), StorageError> {
+        self.write(Record::Put { key, value })
+    }
+
+    /// The value of `key`, from the newest memtable or SSTable holding it,
+    /// unless it was deleted since.
+    pub fn get(&self, key: &[u8]) -> Result<Option<Vec<u8>>, StorageError> {
+        // The memtables are read before the tables, such that the entries a
+        // concurrent flush moves from the former to the latter are within either
+        if let Some(value) = self.memtables.current.load().get(key) {
+            return Ok(value.into_option());
+        }
+
+        for reader in self.tables.select(|table| table.covers(key))? {
+            if let Some(value) = reader.get(key)? {
+                return Ok(value.into_option());
+            }
+        }
+
+        Ok(None)
+    }
+
+    /// The entries within the `range` of keys, by increasing key, merged
+    /// across the memtables and the SSTables (the newest value of each key
+    /// winning), without the deleted ones. They are read from the memtables
+    /// and the tables as of now, unaffected by later writes and compactions.
+    pub fn scan<K: AsRef<[u8]> + ?Sized>(
+        &self,
+        range: impl RangeBounds<K>,
+    ) -> Result<Scan, StorageError> {
+        let range = (as_bytes(range.start_bound()), as_bytes(range.end_bound()));
+        let (start, end) = (owned(range.0), owned(range.1));
+        if is_empty_range(&range) {
+            return Ok(Scan { entries: MergeIter::new(Vec::new()), start, end });
         }
+
+        // The memtables are read first, as when getting a key
+        let memtables = self.memtables.current.load_full();
+        let mut sources = memtables
+            .newest_first()
+            .map(|memtable| Box::new(memtable.range(range).into_iter().map(Ok)) as EntryIter)
+            .collect::<Vec<_>>();
+        for reader in self.tables.select(|table| table.overlaps(&range))? {
+            sources.push(Box::new(match range.start_bound() {
+                Bound::Included(key) | Bound::Excluded(key) => {
+                    SSTableReader::seek_shared(&reader, key)
+                }
+                Bound::Unbounded => SSTableReader::iter_shared(&reader),
+            }));
+        }
+
+        Ok(Scan { entries: MergeIter::new(sources), start, end })
+    }
+
+    /// The entries whose key starts with `prefix`, as [`scan`](Self::scan).
+    pub fn prefix(&self, prefix: &[u8]) -> Result<Scan, StorageError> {
+        let end = prefix_end(prefix);
+        let end = end.as_deref().map_or(Bound::Unbounded, Bound::Excluded);
+        self.scan::<[u8]>((Bound::Included(prefix), end))
+    }
+
+    /// Delete `key`, leaving a tombstone shadowing its older values until
+    /// compaction drops them all.
+    pub fn delete(&self, key: &[u8]) -> Result<(), StorageError> {
+        self.write(Record::Delete { key })
     }
 
-    pub fn put(&self, key: &[u8], value: &[u8]) -> io::Result<()> {
-        self.memtable.put(key, value);
-        if self.memtable.is_full(MAX_MEMTABLE_SIZE) {
-            self.flush()?;
+    /// Log the `record`, then apply it to the active memtable, once rotated if
+    /// it is full.
+    fn write(&self, record: Record<'_>) -> Result<(), StorageError> {
+        // Writes are applied in the order they are logged
+        let mut wal = self.wal.lock();
+        let memtable_size = self.tables.options.memtable_size;
+        if self.memtables.current.load().active.memtable.is_full(memtable_size) {
+            self.rotate(&mut wal)?;
         }
+
+        wal.append(record)?;
+        self.memtables.current.load().active.memtable.apply(record);
         Ok(())
     }
 
-    pub fn get(&self, key: &[u8]) -> io::Result<Option<Vec<u8>>> {
-        if let Some(value) = self.memtable.get(key) {
-            return Ok(Some(value));
+    /// Flush the memtables to new SSTables at level 0, waiting until they are
+    /// durable (i.e. added to the manifest) and their logs are removed.
+    pub fn flush(&self) -> Result<(), StorageError> {
+        let mut wal = self.wal.lock();
+        if !self.memtables.current.load().active.memtable.is_empty() {
+            self.rotate(&mut wal)?;
         }
+        drop(wal);
+
+        self.memtables.wait(1, &self.flusher)
+    }
+
+    /// Close the tree: flush its memtables, then wait for its background
+    /// threads, and unlock its directory.
+    pub fn close(self) -> Result<(), StorageError> {
+        self.flush()
+    }
+
+    /// Rotate the active memtable (whose log is `wal`) into the immutable ones
+    /// for the flush thread to flush, with a new log. Waits for it to catch up
+    /// first, if too many memtables are being flushed already.
+    fn rotate(&self, wal: &mut Wal) -> Result<(), StorageError> {
+        self.memtables.wait(self.tables.options.max_immutables.max(1), &self.flusher)?;
+
+        let log = self.memtables.current.load().active.log + 1;
+        *wal = Wal::open(&log_path(&self.tables.dir, log), self.tables.options.sync, |_| {})?;
+        let active = LoggedMemtable { memtable: Arc::new(Memtable::new()), log };
+        self.memtables.current.rcu(|current| {
+            let mut immutables = vec![current.active.clone()];
+            immutables.extend(current.immutables.iter().cloned());
+            MemtableSet { active: active.clone(), immutables }
+        });
+
+        self.flusher.wake();
+        Ok(())
+    }
+
+    /// Run the compactions the tables need now, on the current thread (rather
+    /// than on the compaction thread), returning their number.
+    pub fn compact(&self) -> Result<usize, StorageError> {
+        self.tables.compact()
+    }
+
+    /// The statistics of the lookups through the bloom filters of the SSTables
+    /// opened so far.
+    pub fn filter_stats(&self) -> FilterStats {
+        self.tables.readers.read().values().map(|reader| reader.filter_stats()).sum()
+    }
+
+    /// The live SSTables, as of the last flush or compaction.
+    pub fn tables(&self) -> Vec<TableMeta> {
+        self.tables.manifest.read().tables().to_vec()
+    }
+}
+
+/// A memtable, and the number of the log holding its writes.
+#[derive(Clone)]
+struct LoggedMemtable {
+    memtable: Arc<Memtable>,
+    log:      u64,
+}
+
+/// The **memtables** of an LSM tree at some point: the active one, which
+/// writes are applied to, and the full ones being flushed. It is replaced as a
+/// whole whenever the active memtable is rotated, or an immutable one flushed.
+struct MemtableSet {
+    active:     LoggedMemtable,
+    /// The immutable memtables, newest first.
+    immutables: Vec<LoggedMemtable>,
+}
+
+impl MemtableSet {
+    /// All the memtables, newest first.
+    fn newest_first(&self) -> impl Iterator<Item = &Memtable> {
+        let memtables = std::iter::once(&self.active).chain(&self.immutables);
+        memtables.map(|logged| logged.memtable.as_ref())
+    }
+
+    /// The value of `key` within the newest memtable holding it, or its
+    /// tombstone if it was deleted.
+    fn get(&self, key: &[u8]) -> Option<Value> {
+        self.newest_first().find_map(|memtable| memtable.get(key))
+    }
+}
+
+/// The **memtables** of an LSM tree, shared with its flush thread.
+struct Memtables {
+    current: ArcSwap<MemtableSet>,
+    /// The error of the last flush, if it failed, reported to every write
+    /// waiting for the flush thread until a flush succeeds.
+    error:   Mutex<Option<StorageError>>,
+    /// Notified whenever the flush thread flushes a memtable, or fails to.
+    flushed: Condvar,
+}
 
-        for sst_file in self.get_sst_files()? {
-            let sstable = SSTable::new(&sst_file);
-            if let Some(value) = sstable.read()?.get(key) {
-                return Ok(Some(value.clone()));
+impl Memtables {
+    /// Flush the immutable memtables (oldest first, such that newer tables
+    /// hold newer writes) to new SSTables at level 0, then remove their logs,
+    /// returning the number of tables written.
+    fn flush(&self, tables: &Tables) -> Result<usize, StorageError> {
+        let mut written = 0;
+        while let Some(oldest) = self.current.load().immutables.last().cloned() {
+            let data = oldest.memtable.data.read();
+            let entries = data.iter().map(|(key, value)| Ok((key.clone(), value.clone())));
+            let flushed = tables.write(0, entries, None)?;
+            drop(data);
+
+            if !flushed.is_empty() {
+                written += flushed.len();
+                let mut manifest = tables.manifest.write();
+                flushed.into_iter().for_each(|table| manifest.add(table));
+                manifest.save(&tables.dir)?;
             }
+
+            // The memtable remains readable until its tables are
+            self.current.rcu(|current| MemtableSet {
+                active:     current.active.clone(),
+                immutables: current
+                    .immutables
+                    .iter()
+                    .filter(|logged| logged.log != oldest.log)
+                    .cloned()
+                    .collect(),
+            });
+            let path = log_path(&tables.dir, oldest.log);
+            match fs::remove_file(&path) {
+                Err(e) if e.kind() != io::ErrorKind::NotFound => return Err(io_error(&path, e)),
+                _ => {}
+            }
+
+            let mut error = self.error.lock();
+            *error = None;
+            self.flushed.notify_all();
         }
 
-        Ok(None)
+        Ok(written)
     }
 
-    pub fn delete(&self, key: &[u8]) -> io::Result<()> {
-        self.memtable.delete(key);
+    /// Report the error of a flush to the writes waiting for it.
+    fn fail(&self, e: StorageError) {
+        tracing::warn!("Failed to flush a memtable: {e}");
+        *self.error.lock() = Some(e);
+        self.flushed.notify_all();
+    }
+
+    /// Wake the `flusher`, and wait until fewer than `below` immutable
+    /// memtables are being flushed (or it fails to flush them).
+    fn wait(&self, below: usize, flusher: &Worker) -> Result<(), StorageError> {
+        let mut error = self.error.lock();
+        while self.current.load().immutables.len() >= below {
+            // Retried until a flush succeeds, failing every write meanwhile
+            flusher.wake();
+            if let Some(e) = error.as_ref() {
+                return Err(e.clone());
+            }
+            self.flushed.wait(&mut error);
+        }
+
         Ok(())
     }
+}
+
+/// A **scan** of the entries within a range of keys of an LSM tree, by
+/// increasing key.
+pub struct Scan {
+    entries: MergeIter<'static>,
+    start:   Bound<Vec<u8>>,
+    end:     Bound<Vec<u8>>,
+}
+
+impl Iterator for Scan {
+    type Item = Result<(Vec<u8>, Vec<u8>), StorageError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let (key, value) = match self.entries.next()? {
+                Ok(entry) => entry,
+                Err(e) => return Some(Err(e)),
+            };
+
+            // Tables are seeked to the start of the range, which may be excluded
+            if matches!(&self.start, Bound::Excluded(start) if key == *start) {
+                continue;
+            }
+            let past_end = match &self.end {
+                Bound::Included(end) => key > *end,
+                Bound::Excluded(end) => key >= *end,
+                Bound::Unbounded => false,
+            };
+            if past_end {
+                self.entries = MergeIter::new(Vec::new());
+                return None;
+            }
+            if let Value::Put(value) = value {
+                return Some(Ok((key, value)));
+            }
+        }
+    }
+}
+
+fn as_bytes<K: AsRef<[u8]> + ?Sized>(bound: Bound<&K>) -> Bound<&[u8]> {
+    match bound {
+        Bound::Included(key) => Bound::Included(key.as_ref()),
+        Bound::Excluded(key) => Bound::Excluded(key.as_ref()),
+        Bound::Unbounded => Bound::Unbounded,
+    }
+}
+
+/// The owned copy of `bound`.
+fn owned(bound: Bound<&[u8]>) -> Bound<Vec<u8>> {
+    match bound {
+        Bound::Included(key) => Bound::Included(key.to_vec()),
+        Bound::Excluded(key) => Bound::Excluded(key.to_vec()),
+        Bound::Unbounded => Bound::Unbounded,
+    }
+}
+
+/// Whether no key is within `range`.
+fn is_empty_range(range: &impl RangeBounds<[u8]>) -> bool {
+    match (range.start_bound(), range.end_bound()) {
+        (Bound::Included(start), Bound::Included(end)) => start > end,
+        (Bound::Included(start) | Bound::Excluded(start), Bound::Excluded(end)) |
+        (Bound::Excluded(start), Bound::Included(end)) => start >= end,
+        _ => false,
+    }
+}
+
+/// The smallest key greater than all the keys starting with `prefix`, if any.
+fn prefix_end(prefix: &[u8]) -> Option<Vec<u8>> {
+    let last = prefix.iter().rposition(|byte| *byte != u8::MAX)?;
+    let mut end = prefix[..=last].to_vec();
+    end[last] += 1;
+    Some(end)
+}
+
+/// The **SSTables** of an LSM tree, shared with its compaction thread.
+struct Tables {
+    dir:        String,
+    options:    Options,
+    /// The live SSTables.
+    manifest:   RwLock<Manifest>,
+    /// The readers of the SSTables looked up so far, by file number.
+    readers:    RwLock<HashMap<u64, Arc<SSTableReader>>>,
+    /// Held while compacting, such that compactions run one at a time.
+    compacting: Mutex<()>,
+}
+
+impl Tables {
+    /// The readers of the live SSTables matching `filter`, newest first. They
+    /// remain readable once the tables are compacted.
+    fn select(
+        &self,
+        filter: impl Fn(&TableMeta) -> bool,
+    ) -> Result<Vec<Arc<SSTableReader>>, StorageError> {
+        let manifest = self.manifest.read();
+        let tables = manifest.newest_first().into_iter().filter(|table| filter(table));
+        tables.map(|table| self.reader(table.file)).collect()
+    }
+
+    /// The reader of the SSTable numbered `file`, opened once.
+    fn reader(&self, file: u64) -> Result<Arc<SSTableReader>, StorageError> {
+        if let Some(reader) = self.readers.read().get(&file) {
+            return Ok(reader.clone());
+        }
+
+        let reader = Arc::new(SSTableReader::open(&table_path(&self.dir, file))?);
+        self.readers.write().insert(file, reader.clone());
+        Ok(reader)
+    }
+
+    /// Write the `entries` (sorted by key) to new SSTables at `level`, cut at
+    /// `table_size` bytes if given, returning their metadata. They are not
+    /// added to the manifest yet.
+    fn write(
+        &self,
+        level: u32,
+        entries: impl Iterator<Item = Result<(Vec<u8>, Value), StorageError>>,
+        table_size: Option<u64>,
+    ) -> Result<Vec<TableMeta>, StorageError> {
+        let mut tables = Vec::new();
+        let mut table: Option<(TableMeta, SSTableBuilder)> = None;
+        for entry in entries {
+            let (key, value) = entry?;
+            let (meta, builder) = match &mut table {
+                Some(table) => table,
+                None => {
+                    let file = self.manifest.write().next_file();
+                    let path = table_path(&self.dir, file);
+                    let builder = SSTableBuilder::create(&path, &self.options.table())?;
+                    let meta = TableMeta {
+                        file,
+                        level,
+                        smallest: key.clone(),
+                        largest: Vec::new(),
+                        entries: 0,
+                        size: 0,
+                    };
+                    table.insert((meta, builder))
+                }
+            };
+
+            builder.add_entry(&key, value.as_deref())?;
+            meta.largest = key;
+            if table_size.map_or(false, |table_size| builder.size() >= table_size) {
+                tables.push(Self::finish(&self.dir, table.take())?);
+            }
+        }
+        if table.is_some() {
+            tables.push(Self::finish(&self.dir, table.take())?);
+        }
+
+        Ok(tables)
+    }
 
-    fn flush(&self) -> io::Result<()> {
-        let data = self.memtable.data.read().clone();
-        let file_name = format!("{}/sst-{}.sst", self.sst_directory, data.len());
-        let sstable = SSTable::new(&file_name);
-        sstable.create(&data)?;
+    fn finish(
+        dir: &str,
+        table: Option<(TableMeta, SSTableBuilder)>,
+    ) -> Result<TableMeta, StorageError> {
+        let (mut meta, builder) = table.expect("a table is being written");
+        meta.entries = builder.finish()?;
+        let path = meta.path(dir);
+        meta.size = fs::metadata(&path).map_err(|e| io_error(&path, e))?.len();
+        Ok(meta)
+    }
+
+    /// Run the compactions the strategy picks until none is needed, returning
+    /// their number.
+    fn compact(&self) -> Result<usize, StorageError> {
+        let _compacting = self.compacting.lock();
+
+        let mut compactions = 0;
+        loop {
+            let compaction = self.options.compaction.pick(&self.manifest.read());
+            let Some(compaction) = compaction else {
+                return Ok(compactions);
+            };
+            self.run(&compaction)?;
+            compactions += 1;
+        }
+    }
 
-        self.memtable.data.write().clear();
+    /// Merge the inputs of the `compaction` into new tables, then swap them in
+    /// the manifest, and remove the inputs.
+    fn run(&self, compaction: &Compaction) -> Result<(), StorageError> {
+        let inputs = compaction.inputs.iter().map(|table| table.file).collect::<Vec<_>>();
+        tracing::debug!("Compacting tables {inputs:?} into level {}", compaction.output_level);
+
+        let readers =
+            inputs.iter().map(|file| self.reader(*file)).collect::<Result<Vec<_>, _>>()?;
+        let sources = readers.iter().map(|reader| Box::new(reader.iter()) as EntryIter).collect();
+        let entries = MergeIter::new(sources).filter(|entry| {
+            !(compaction.drop_tombstones &&
+                entry.as_ref().map_or(false, |(_, value)| value.is_tombstone()))
+        });
+        let outputs = self.write(compaction.output_level, entries, compaction.table_size)?;
+
+        let mut manifest = self.manifest.write();
+        manifest.replace(&inputs, outputs);
+        manifest.save(&self.dir)?;
+        drop(manifest);
+
+        let mut readers = self.readers.write();
+        inputs.iter().for_each(|file| drop(readers.remove(file)));
+        drop(readers);
+        for file in inputs {
+            let path = table_path(&self.dir, file);
+            fs::remove_file(&path).map_err(|e| io_error(&path, e))?;
+        }
 
         Ok(())
     }
+}
+
+/// The **lock** of the directory of an LSM tree, held while it is open (on
+/// unix), and released by the OS once closed, even if the process crashes.
+struct DirLock {
+    _file: File,
+}
+
+impl DirLock {
+    fn acquire(dir: &str) -> Result<Self, StorageError> {
+        let path = format!("{dir}/{LOCK_FILE}");
+        let file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(false)
+            .open(&path)
+            .map_err(|e| io_error(&path, e))?;
 
-    fn get_sst_files(&self) -> io::Result<Vec<String>> {
-        let mut sst_files = Vec::new();
-        for entry in fs::read_dir(&self.sst_directory)? {
-            let entry = entry?;
-            let path = entry.path();
-            if path.is_file() && path.extension().and_then(|s| s.to_str()) == Some("sst") {
-                sst_files.push(path.to_str().unwrap().to_owned());
+        match lock(&file) {
+            Ok(()) => Ok(Self { _file: file }),
+            Err(e) if e.kind() == io::ErrorKind::WouldBlock => {
+                Err(StorageError::Locked { path: path.into(), reason: e.to_string().into() })
             }
+            Err(e) => Err(io_error(&path, e)),
+        }
+    }
+}
+
+/// Lock `file` exclusively, failing if it is locked already (through another
+/// open file, even within the same process).
+#[cfg(unix)]
+fn lock(file: &File) -> io::Result<()> {
+    use std::os::unix::io::AsRawFd;
+
+    if unsafe { libc::flock(file.as_raw_fd(), libc::LOCK_EX | libc::LOCK_NB) } == 0 {
+        Ok(())
+    } else {
+        Err(io::Error::last_os_error())
+    }
+}
+
+#[cfg(not(unix))]
+fn lock(_file: &File) -> io::Result<()> {
+    Ok(())
+}
+
+/// A **background thread** of an LSM tree (e.g. flushing its memtables, or
+/// compacting its tables), running its task whenever woken, until dropped.
+struct Worker {
+    wake:   Option<Sender<()>>,
+    handle: Option<JoinHandle<()>>,
+}
+
+impl Worker {
+    fn spawn(
+        name: &str,
+        dir: &str,
+        mut task: impl FnMut() + Send + 'static,
+    ) -> Result<Self, StorageError> {
+        let (wake, woken) = mpsc::channel();
+        let handle = thread::Builder::new()
+            .name(name.into())
+            .spawn(move || {
+                while woken.recv().is_ok() {
+                    task();
+                }
+            })
+            .map_err(|e| io_error(dir, e))?;
+
+        Ok(Self { wake: Some(wake), handle: Some(handle) })
+    }
+
+    fn wake(&self) {
+        if let Some(wake) = &self.wake {
+            let _ = wake.send(());
         }
+    }
 
-        sst_files.sort();
-        Ok(sst_files)
+    /// A sender waking the thread, which keeps it running until dropped.
+    fn waker(&self) -> Sender<()> {
+        self.wake.clone().expect("the thread is running")
+    }
+}
+
+impl Drop for Worker {
+    fn drop(&mut self) {
+        // Let the thread finish the tasks it was woken for
+        drop(self.wake.take());
+        if let Some(handle) = self.handle.take() {
+            let _ = handle.join();
+        }
     }
 }
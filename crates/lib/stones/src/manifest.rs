@@ -0,0 +1,233 @@
+// src/manifest.rs
+
+//! The **manifest** of an LSM tree: the list of its live SSTables, with their
+//! file numbers, levels and key ranges. It is replaced atomically (written to
+//! a temporary file, synced, then renamed over the previous one) whenever
+//! tables are added or removed, such that a crash leaves either the previous
+//! or the next manifest, and tables it does not list are leftovers.
+
+use diagnostics::errors::StorageError;
+use serde::{
+    Deserialize,
+    Serialize,
+};
+use std::{
+    fs::{
+        self,
+        File,
+    },
+    io::{
+        self,
+        Write,
+    },
+    ops::{
+        Bound,
+        RangeBounds,
+    },
+    path::Path,
+};
+
+use crate::sstable::io_error;
+
+/// The name of the manifest within the directory of an LSM tree.
+pub const MANIFEST_FILE: &str = "MANIFEST";
+
+/// The version of the manifest written.
+const MANIFEST_VERSION: u32 = 1;
+
+/// The **metadata** of a live SSTable.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct TableMeta {
+    /// The number of its file, increasing with each table written.
+    pub file:     u64,
+    /// Its level: flushed tables are written at level 0 (where their ranges
+    /// may overlap), and moved down by compaction.
+    pub level:    u32,
+    /// Its smallest key.
+    pub smallest: Vec<u8>,
+    /// Its largest key.
+    pub largest:  Vec<u8>,
+    /// Its number of entries (tombstones included).
+    pub entries:  u64,
+    /// The size of its file, in bytes.
+    #[serde(default)]
+    pub size:     u64,
+}
+
+impl TableMeta {
+    /// Whether `key` is within the range of keys of the table.
+    pub fn covers(&self, key: &[u8]) -> bool {
+        self.smallest.as_slice() <= key && key <= self.largest.as_slice()
+    }
+
+    /// Whether the range of keys of the table overlaps `range`.
+    pub fn overlaps(&self, range: &impl RangeBounds<[u8]>) -> bool {
+        let (smallest, largest) = (self.smallest.as_slice(), self.largest.as_slice());
+        let after_start = match range.start_bound() {
+            Bound::Included(start) => start <= largest,
+            Bound::Excluded(start) => start < largest,
+            Bound::Unbounded => true,
+        };
+        let before_end = match range.end_bound() {
+            Bound::Included(end) => smallest <= end,
+            Bound::Excluded(end) => smallest < end,
+            Bound::Unbounded => true,
+        };
+        after_start && before_end
+    }
+
+    /// The path of the table within `dir`.
+    pub fn path(&self, dir: &str) -> String {
+        table_path(dir, self.file)
+    }
+}
+
+/// The path of the table numbered `file` within `dir`.
+///
+/// ```rust
+/// use stones::manifest::{
+///     parse_table_name,
+///     table_path,
+/// };
+///
+/// assert_eq!(table_path("cache", 42), "cache/000042.sst");
+/// assert_eq!(parse_table_name("000042.sst"), Some(42));
+/// assert_eq!(parse_table_name("sst-1000.sst"), None);
+/// ```
+pub fn table_path(dir: &str, file: u64) -> String {
+    format!("{dir}/{file:06}.sst")
+}
+
+/// The number of the table named `name`, if it is one.
+pub fn parse_table_name(name: &str) -> Option<u64> {
+    let number = name.strip_suffix(".sst")?;
+    number.bytes().all(|b| b.is_ascii_digit()).then(|| number.parse().ok()).flatten()
+}
+
+/// The **manifest** of an LSM tree.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct Manifest {
+    version:   u32,
+    /// The number of the next table to write.
+    next_file: u64,
+    tables:    Vec<TableMeta>,
+}
+
+impl Default for Manifest {
+    fn default() -> Self {
+        Self { version: MANIFEST_VERSION, next_file: 1, tables: Vec::new() }
+    }
+}
+
+impl Manifest {
+    /// Load the manifest of the tree within `dir` (empty if there is none).
+    pub fn load(dir: &str) -> Result<Self, StorageError> {
+        let path = format!("{dir}/{MANIFEST_FILE}");
+        let contents = match fs::read(&path) {
+            Ok(contents) => contents,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Self::default()),
+            Err(e) => return Err(io_error(&path, e)),
+        };
+
+        let bad_manifest = |reason: String| StorageError::BadManifest {
+            path:   path.as_str().into(),
+            reason: reason.into(),
+        };
+        let manifest: Self =
+            serde_json::from_slice(&contents).map_err(|e| bad_manifest(e.to_string()))?;
+        if manifest.version != MANIFEST_VERSION {
+            return Err(bad_manifest(format!("unsupported version {}", manifest.version)));
+        }
+        if let Some(table) = manifest.tables.iter().find(|table| table.file >= manifest.next_file) {
+            return Err(bad_manifest(format!(
+                "table {} is numbered past the next one",
+                table.file
+            )));
+        }
+
+        Ok(manifest)
+    }
+
+    /// Replace the manifest of the tree within `dir` by this one, atomically.
+    pub fn save(&self, dir: &str) -> Result<(), StorageError> {
+        let path = format!("{dir}/{MANIFEST_FILE}");
+        let temp_path = format!("{path}.tmp");
+        let contents = serde_json::to_vec_pretty(self).expect("the manifest is serializable");
+
+        File::create(&temp_path)
+            .and_then(|mut file| {
+                file.write_all(&contents)?;
+                file.sync_all()
+            })
+            .map_err(|e| io_error(&temp_path, e))?;
+        fs::rename(&temp_path, &path).map_err(|e| io_error(&path, e))?;
+        sync_dir(dir).map_err(|e| io_error(dir, e))
+    }
+
+    /// Reserve the number of the next table to write.
+    pub fn next_file(&mut self) -> u64 {
+        self.next_file += 1;
+        self.next_file - 1
+    }
+
+    /// Add a table, once written.
+    pub fn add(&mut self, table: TableMeta) {
+        self.tables.push(table);
+    }
+
+    /// Replace the tables numbered `inputs` by the `outputs` merging them,
+    /// once written.
+    pub fn replace(&mut self, inputs: &[u64], outputs: Vec<TableMeta>) {
+        self.tables.retain(|table| !inputs.contains(&table.file));
+        self.tables.extend(outputs);
+    }
+
+    /// All the live tables, in the order they were added.
+    pub fn tables(&self) -> &[TableMeta] {
+        &self.tables
+    }
+
+    /// The live tables in the order keys are looked up through them, i.e.
+    /// newest first: by level, and by decreasing file number within a level.
+    pub fn newest_first(&self) -> Vec<&TableMeta> {
+        let mut tables = self.tables.iter().collect::<Vec<_>>();
+        tables.sort_by(|a, b| a.level.cmp(&b.level).then(b.file.cmp(&a.file)));
+        tables
+    }
+
+    /// Whether the table numbered `file` is live.
+    pub fn contains(&self, file: u64) -> bool {
+        self.tables.iter().any(|table| table.file == file)
+    }
+
+    /// Remove the tables within `dir` the manifest does not list (i.e. written
+    /// before a crash, or replaced), returning their number.
+    pub fn remove_leftovers(&self, dir: &str) -> Result<usize, StorageError> {
+        let _ = fs::remove_file(format!("{dir}/{MANIFEST_FILE}.tmp"));
+
+        let mut removed = 0;
+        let read_dir_error = |e| io_error(dir, e);
+        for entry in fs::read_dir(dir).map_err(read_dir_error)? {
+            let path = entry.map_err(read_dir_error)?.path();
+            let name = path.file_name().and_then(|name| name.to_str()).unwrap_or_default();
+            match parse_table_name(name) {
+                Some(file) if !self.contains(file) => {
+                    fs::remove_file(&path).map_err(|e| io_error(&path.to_string_lossy(), e))?;
+                    removed += 1;
+                }
+                _ => {}
+            }
+        }
+
+        Ok(removed)
+    }
+}
+
+/// Sync the directory `dir`, such that the renames within it are durable.
+fn sync_dir(dir: &str) -> io::Result<()> {
+    if cfg!(unix) {
+        File::open(Path::new(dir))?.sync_all()
+    } else {
+        Ok(())
+    }
+}
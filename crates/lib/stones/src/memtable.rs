@@ -1,34 +1,81 @@
 // src/memtable.rs
 
+use crate::value::Value;
+use crate::wal::Record;
 use parking_lot::RwLock;
-use std::collections::BTreeMap;
+use std::{
+    collections::BTreeMap,
+    ops::Bound,
+    sync::atomic::{
+        AtomicUsize,
+        Ordering,
+    },
+};
 
+#[derive(Default)]
 pub struct Memtable {
-    pub data: RwLock<BTreeMap<Vec<u8>, Vec<u8>>>,
+    pub data: RwLock<BTreeMap<Vec<u8>, Value>>,
+    /// The size of the keys and values of the entries, in bytes.
+    size:     AtomicUsize,
 }
 
 impl Memtable {
     pub fn new() -> Self {
-        Memtable { data: RwLock::new(BTreeMap::new()) }
+        Self::default()
     }
 
     pub fn put(&self, key: &[u8], value: &[u8]) {
-        let mut data = self.data.write();
-        data.insert(key.to_vec(), value.to_vec());
+        self.insert(key, Value::Put(value.to_vec()));
     }
 
-    pub fn get(&self, key: &[u8]) -> Option<Vec<u8>> {
+    /// The value of `key`, or its tombstone if it was deleted.
+    pub fn get(&self, key: &[u8]) -> Option<Value> {
         let data = self.data.read();
         data.get(key).cloned()
     }
 
+    /// Delete `key`, leaving a tombstone shadowing its older values.
     pub fn delete(&self, key: &[u8]) {
+        self.insert(key, Value::Tombstone);
+    }
+
+    fn insert(&self, key: &[u8], value: Value) {
+        let entry_size = |value: &Value| key.len() + value.as_deref().map_or(0, <[u8]>::len);
         let mut data = self.data.write();
-        data.remove(key);
+        self.size.fetch_add(entry_size(&value), Ordering::Relaxed);
+        if let Some(replaced) = data.insert(key.to_vec(), value) {
+            self.size.fetch_sub(entry_size(&replaced), Ordering::Relaxed);
+        }
+    }
+
+    /// Apply the write of a `record` of the log.
+    pub fn apply(&self, record: Record<'_>) {
+        match record {
+            Record::Put { key, value } => self.put(key, value),
+            Record::Delete { key } => self.delete(key),
+        }
+    }
+
+    /// A copy of the entries (tombstones included) within the `range` of keys,
+    /// by increasing key.
+    pub fn range(&self, range: (Bound<&[u8]>, Bound<&[u8]>)) -> Vec<(Vec<u8>, Value)> {
+        let data = self.data.read();
+        data.range::<[u8], _>(range).map(|(key, value)| (key.clone(), value.clone())).collect()
+    }
+
+    /// The size of the keys and values of the entries (tombstones included),
+    /// in bytes.
+    pub fn size(&self) -> usize {
+        self.size.load(Ordering::Relaxed)
     }
 
+    /// Whether the entries reach `max_size` bytes.
     pub fn is_full(&self, max_size: usize) -> bool {
+        self.size() >= max_size
+    }
+
+    pub fn is_empty(&self) -> bool {
         let data = self.data.read();
-        data.len() >= max_size
+        data.is_empty()
     }
 }
@@ -0,0 +1,114 @@
+// src/merge.rs
+
+use diagnostics::errors::StorageError;
+use std::{
+    cmp::Reverse,
+    collections::BinaryHeap,
+};
+
+use crate::value::Value;
+
+/// An iterator over sorted entries (e.g. those of an SSTable), which a
+/// [`MergeIter`] merges.
+pub type EntryIter<'a> = Box<dyn Iterator<Item = Result<(Vec<u8>, Value), StorageError>> + 'a>;
+
+/// A **merging iterator** over sources of entries sorted by key (e.g. the
+/// memtable and SSTables), given newest first: yields each key once, with its
+/// value within the newest source holding it (tombstones included), by
+/// increasing key.
+///
+/// ```rust
+/// use stones::{
+///     merge::MergeIter,
+///     value::Value,
+/// };
+///
+/// let source = |entries: &[(&str, Option<&str>)]| {
+///     let entries = entries
+///         .iter()
+///         .map(|(key, value)| {
+///             Ok((key.as_bytes().to_vec(), Value::from(value.map(str::as_bytes))))
+///         })
+///         .collect::<Vec<_>>();
+///     Box::new(entries.into_iter()) as stones::merge::EntryIter
+/// };
+/// let newer = source(&[("a", Some("new")), ("c", None)]);
+/// let older = source(&[("a", Some("old")), ("b", Some("old")), ("c", Some("old"))]);
+///
+/// let merged = MergeIter::new(vec![newer, older]).collect::<Result<Vec<_>, _>>().unwrap();
+/// assert_eq!(merged, [
+///     (b"a".to_vec(), Value::Put(b"new".to_vec())),
+///     (b"b".to_vec(), Value::Put(b"old".to_vec())),
+///     (b"c".to_vec(), Value::Tombstone),
+/// ]);
+/// ```
+pub struct MergeIter<'a> {
+    sources: Vec<EntryIter<'a>>,
+    /// The current value of each source.
+    values:  Vec<Option<Value>>,
+    /// The current key of each source (with its position, such that newer
+    /// sources come first for equal keys), smallest first.
+    heap:    BinaryHeap<Reverse<(Vec<u8>, usize)>>,
+    started: bool,
+    /// Whether a source failed, which ends the iteration.
+    failed:  bool,
+}
+
+impl<'a> MergeIter<'a> {
+    /// Merge the `sources`, given newest first.
+    pub fn new(sources: Vec<EntryIter<'a>>) -> Self {
+        let values = vec![None; sources.len()];
+        Self { sources, values, heap: BinaryHeap::new(), started: false, failed: false }
+    }
+
+    /// Move the source at `position` to its next entry.
+    fn advance(&mut self, position: usize) -> Result<(), StorageError> {
+        match self.sources[position].next() {
+            Some(Ok((key, value))) => {
+                self.values[position] = Some(value);
+                self.heap.push(Reverse((key, position)));
+            }
+            Some(Err(e)) => return Err(e),
+            None => self.values[position] = None,
+        }
+
+        Ok(())
+    }
+
+    fn entry(&mut self) -> Result<Option<(Vec<u8>, Value)>, StorageError> {
+        if !self.started {
+            self.started = true;
+            for position in 0..self.sources.len() {
+                self.advance(position)?;
+            }
+        }
+
+        let Some(Reverse((key, position))) = self.heap.pop() else {
+            return Ok(None);
+        };
+        let value = self.values[position].take().expect("the source has a current entry");
+        self.advance(position)?;
+
+        // The older values of the key are shadowed
+        while self.heap.peek().map_or(false, |Reverse((next, _))| *next == key) {
+            let Reverse((_, older)) = self.heap.pop().expect("the heap is not empty");
+            self.advance(older)?;
+        }
+
+        Ok(Some((key, value)))
+    }
+}
+
+impl<'a> Iterator for MergeIter<'a> {
+    type Item = Result<(Vec<u8>, Value), StorageError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.failed {
+            return None;
+        }
+
+        let entry = self.entry();
+        self.failed = entry.is_err();
+        entry.transpose()
+    }
+}
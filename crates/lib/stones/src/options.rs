@@ -0,0 +1,83 @@
+// src/options.rs
+
+use typed_builder::TypedBuilder;
+
+use crate::{
+    compaction::CompactionStrategy,
+    sstable::{
+        Compression,
+        TableOptions,
+        DEFAULT_BITS_PER_KEY,
+        DEFAULT_BLOCK_SIZE,
+    },
+    wal::SyncMode,
+};
+
+/// The size the active memtable is rotated at, unless configured.
+pub const DEFAULT_MEMTABLE_SIZE: usize = 4 * 1024 * 1024;
+
+/// The number of immutable memtables being flushed past which writes wait,
+/// unless configured.
+pub const DEFAULT_MAX_IMMUTABLES: usize = 4;
+
+/// The **options** of an LSM tree.
+///
+/// ```rust
+/// use stones::{
+///     options::Options,
+///     sstable::Compression,
+///     wal::SyncMode,
+/// };
+///
+/// let options = Options::builder()
+///     .memtable_size(64 * 1024)
+///     .compression(Compression::Deflate)
+///     .sync(SyncMode::Batch(100))
+///     .build();
+/// assert_eq!(options.table().compression, Compression::Deflate);
+/// assert_eq!(options.bits_per_key, Options::default().bits_per_key);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, TypedBuilder)]
+pub struct Options {
+    /// The size (of the keys and values) the active memtable is rotated at,
+    /// to be flushed, in bytes.
+    #[builder(default = DEFAULT_MEMTABLE_SIZE)]
+    pub memtable_size:  usize,
+    /// The number of immutable memtables being flushed past which writes wait
+    /// for the flush thread to catch up.
+    #[builder(default = DEFAULT_MAX_IMMUTABLES)]
+    pub max_immutables: usize,
+    /// The size the data blocks of its SSTables are cut at, in bytes.
+    #[builder(default = DEFAULT_BLOCK_SIZE)]
+    pub block_size:     usize,
+    /// How the data blocks of its SSTables are compressed.
+    #[builder(default)]
+    pub compression:    Compression,
+    /// The number of bits per key of the bloom filters of its SSTables, or 0
+    /// to write none.
+    #[builder(default = DEFAULT_BITS_PER_KEY)]
+    pub bits_per_key:   usize,
+    /// When its write-ahead log is synced.
+    #[builder(default)]
+    pub sync:           SyncMode,
+    /// How its SSTables are compacted, in the background.
+    #[builder(default)]
+    pub compaction:     CompactionStrategy,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Self::builder().build()
+    }
+}
+
+impl Options {
+    /// The options its SSTables are written with.
+    pub fn table(&self) -> TableOptions {
+        TableOptions {
+            block_size:   self.block_size,
+            bits_per_key: self.bits_per_key,
+            compression:  self.compression,
+        }
+    }
+}
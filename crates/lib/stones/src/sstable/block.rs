@@ -0,0 +1,127 @@
+// src/sstable/block.rs
+
+use arrayref::array_ref;
+use diagnostics::errors::StorageError;
+
+use super::format::corrupted;
+
+/// The size of the header of each entry (the sizes of its key and value).
+const ENTRY_HEADER_SIZE: usize = 8;
+
+/// The value size of tombstones, which have no value.
+const TOMBSTONE: u32 = u32::MAX;
+
+/// A **builder** of the contents of a block: its entries, sorted by key.
+///
+/// ```text
+/// +----------+------------+-----+-------+
+/// | key size | value size | key | value |   (for each entry)
+/// |   u32    |    u32     |     |       |
+/// +----------+------------+-----+-------+
+/// ```
+///
+/// Tombstones have a value size of `u32::MAX`, and no value.
+#[derive(Debug, Default)]
+pub struct BlockBuilder {
+    contents: Vec<u8>,
+}
+
+impl BlockBuilder {
+    pub fn add(&mut self, key: &[u8], value: &[u8]) {
+        self.add_entry(key, Some(value));
+    }
+
+    pub fn add_tombstone(&mut self, key: &[u8]) {
+        self.add_entry(key, None);
+    }
+
+    /// Add the entry of `key`, a tombstone unless it has a `value`.
+    pub fn add_entry(&mut self, key: &[u8], value: Option<&[u8]>) {
+        let value_size = value.map_or(TOMBSTONE, |value| value.len() as u32);
+        self.contents.extend_from_slice(&(key.len() as u32).to_le_bytes());
+        self.contents.extend_from_slice(&value_size.to_le_bytes());
+        self.contents.extend_from_slice(key);
+        self.contents.extend_from_slice(value.unwrap_or_default());
+    }
+
+    /// The size of the contents of the block so far.
+    pub fn size(&self) -> usize {
+        self.contents.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.contents.is_empty()
+    }
+
+    /// Take the contents of the block, leaving the builder empty.
+    pub fn finish(&mut self) -> Vec<u8> {
+        std::mem::take(&mut self.contents)
+    }
+}
+
+/// An entry decoded from a block: its key, its value (none for tombstones)
+/// and the position of the next entry.
+pub type Entry<'a> = (&'a [u8], Option<&'a [u8]>, usize);
+
+/// Decode the entry of the `contents` of a block at `position` (or why it is
+/// malformed).
+pub fn decode_entry(contents: &[u8], position: usize) -> Result<Entry<'_>, &'static str> {
+    let rest = &contents[position..];
+    if rest.len() < ENTRY_HEADER_SIZE {
+        return Err("the header of an entry overruns the block");
+    }
+
+    let key_size = u32::from_le_bytes(*array_ref!(rest, 0, 4)) as usize;
+    let value_size = match u32::from_le_bytes(*array_ref!(rest, 4, 4)) {
+        TOMBSTONE => None,
+        value_size => Some(value_size as usize),
+    };
+    let rest = &rest[ENTRY_HEADER_SIZE..];
+    if rest.len() < key_size + value_size.unwrap_or_default() {
+        return Err("an entry overruns the block");
+    }
+
+    let value = value_size.map(|value_size| &rest[key_size..key_size + value_size]);
+    let next = position + ENTRY_HEADER_SIZE + key_size + value_size.unwrap_or_default();
+    Ok((&rest[..key_size], value, next))
+}
+
+/// An **iterator** over the entries of the contents of a block (with no value
+/// for tombstones), read from the file at `path` at `offset`.
+pub struct BlockIter<'a> {
+    path:     &'a str,
+    offset:   u64,
+    contents: &'a [u8],
+    position: usize,
+}
+
+impl<'a> BlockIter<'a> {
+    pub fn new(path: &'a str, offset: u64, contents: &'a [u8]) -> Self {
+        Self { path, offset, contents, position: 0 }
+    }
+
+    fn entry(&mut self) -> Result<(&'a [u8], Option<&'a [u8]>), StorageError> {
+        match decode_entry(self.contents, self.position) {
+            Ok((key, value, next)) => {
+                self.position = next;
+                Ok((key, value))
+            }
+            Err(reason) => Err(self.corrupted(reason)),
+        }
+    }
+
+    fn corrupted(&mut self, reason: &str) -> StorageError {
+        let offset = self.offset + self.position as u64;
+        // Stop iterating past the corruption
+        self.position = self.contents.len();
+        corrupted(self.path, offset, reason)
+    }
+}
+
+impl<'a> Iterator for BlockIter<'a> {
+    type Item = Result<(&'a [u8], Option<&'a [u8]>), StorageError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        (self.position < self.contents.len()).then(|| self.entry())
+    }
+}
@@ -0,0 +1,171 @@
+// src/sstable/builder.rs
+
+use diagnostics::errors::StorageError;
+use std::{
+    fs::File,
+    io::{
+        BufWriter,
+        Write,
+    },
+};
+
+use super::{
+    block::BlockBuilder,
+    filter::FilterBuilder,
+    format::{
+        compress_block,
+        encode_header,
+        encode_trailer,
+        BlockHandle,
+        Footer,
+        HEADER_SIZE,
+        UNCOMPRESSED,
+    },
+    io_error,
+    Compression,
+    TableOptions,
+};
+
+/// A **builder** of an SSTable, writing its entries (added in increasing order
+/// of their keys) block by block, such that tables larger than memory can be
+/// written (e.g. when merging tables).
+pub struct SSTableBuilder {
+    path:        String,
+    file:        BufWriter<File>,
+    /// The size data blocks are cut at.
+    block_size:  usize,
+    /// How data blocks are compressed.
+    compression: Compression,
+    /// The offset of the next block within the file.
+    offset:      u64,
+    data:        BlockBuilder,
+    index:       BlockBuilder,
+    /// The bloom filter of the keys, unless disabled.
+    filter:      Option<FilterBuilder>,
+    /// The last key added, i.e. that of the current data block once cut.
+    last_key:    Vec<u8>,
+    entries:     u64,
+}
+
+impl SSTableBuilder {
+    /// Create the SSTable at `path`, with the given `options`.
+    pub fn create(path: &str, options: &TableOptions) -> Result<Self, StorageError> {
+        let mut file = BufWriter::new(File::create(path).map_err(|e| io_error(path, e))?);
+        file.write_all(&encode_header()).map_err(|e| io_error(path, e))?;
+
+        Ok(Self {
+            path: path.to_owned(),
+            file,
+            block_size: options.block_size,
+            compression: options.compression,
+            offset: HEADER_SIZE as u64,
+            data: BlockBuilder::default(),
+            index: BlockBuilder::default(),
+            filter: (options.bits_per_key > 0).then(|| FilterBuilder::new(options.bits_per_key)),
+            last_key: Vec::new(),
+            entries: 0,
+        })
+    }
+
+    /// Add an entry, whose key must be greater than the keys added before it.
+    pub fn add(&mut self, key: &[u8], value: &[u8]) -> Result<(), StorageError> {
+        self.add_entry(key, Some(value))
+    }
+
+    /// Add the tombstone of a deleted key, shadowing its values within older
+    /// tables.
+    pub fn add_tombstone(&mut self, key: &[u8]) -> Result<(), StorageError> {
+        self.add_entry(key, None)
+    }
+
+    /// Add the entry of `key`, a tombstone unless it has a `value`.
+    pub fn add_entry(&mut self, key: &[u8], value: Option<&[u8]>) -> Result<(), StorageError> {
+        debug_assert!(
+            self.entries == 0 || key > self.last_key.as_slice(),
+            "keys must be added in increasing order"
+        );
+
+        self.data.add_entry(key, value);
+        if let Some(filter) = &mut self.filter {
+            filter.add(key);
+        }
+        self.last_key.clear();
+        self.last_key.extend_from_slice(key);
+        self.entries += 1;
+
+        if self.data.size() >= self.block_size {
+            self.cut_block()?;
+        }
+
+        Ok(())
+    }
+
+    /// The number of entries added so far.
+    pub fn entries(&self) -> u64 {
+        self.entries
+    }
+
+    /// The size of the table so far, i.e. of the blocks written and of the
+    /// current data block.
+    pub fn size(&self) -> u64 {
+        self.offset + self.data.size() as u64
+    }
+
+    /// Write the last data block, the filter (unless the table is empty), the
+    /// index and the footer, and sync the file, returning the number of
+    /// entries of the table.
+    pub fn finish(mut self) -> Result<u64, StorageError> {
+        self.cut_block()?;
+
+        let filter = match self.filter.take() {
+            Some(filter) if self.entries > 0 => {
+                Some(self.write_block(&filter.finish(), Compression::None)?)
+            }
+            _ => None,
+        };
+        let index = self.index.finish();
+        let index = self.write_block(&index, Compression::None)?;
+        let footer = Footer { index, filter, entries: self.entries };
+        self.file.write_all(&footer.encode()).map_err(|e| io_error(&self.path, e))?;
+
+        let file = self.file.into_inner().map_err(|e| io_error(&self.path, e.into_error()))?;
+        file.sync_all().map_err(|e| io_error(&self.path, e))?;
+
+        Ok(self.entries)
+    }
+
+    /// Write the current data block (if any), and index it by its last key.
+    fn cut_block(&mut self) -> Result<(), StorageError> {
+        if self.data.is_empty() {
+            return Ok(());
+        }
+
+        let contents = self.data.finish();
+        let handle = self.write_block(&contents, self.compression)?;
+        self.index.add(&self.last_key, &handle.encode());
+
+        Ok(())
+    }
+
+    /// Write a block of `contents`, compressed as given if it is worth it.
+    fn write_block(
+        &mut self,
+        contents: &[u8],
+        compression: Compression,
+    ) -> Result<BlockHandle, StorageError> {
+        let compressed = compress_block(contents, compression);
+        let (contents, kind) = match &compressed {
+            Some((compressed, kind)) => (compressed.as_slice(), *kind),
+            None => (contents, UNCOMPRESSED),
+        };
+
+        let handle = BlockHandle { offset: self.offset, size: contents.len() as u32 };
+        self.file
+            .write_all(contents)
+            .and_then(|_| self.file.write_all(&encode_trailer(contents, kind)))
+            .map_err(|e| io_error(&self.path, e))?;
+        self.offset = handle.end();
+
+        Ok(handle)
+    }
+}
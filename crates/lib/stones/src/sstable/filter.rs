@@ -0,0 +1,189 @@
+// src/sstable/filter.rs
+
+use std::sync::atomic::{
+    AtomicU64,
+    Ordering,
+};
+
+/// The maximum number of probes of a filter.
+const MAX_PROBES: u8 = 30;
+
+/// A **builder** of the bloom filter of an SSTable, covering all its keys,
+/// with `bits_per_key` bits per key (i.e. about 1% of false positives with 10
+/// bits per key).
+///
+/// The filter is its bits, followed by the number of probes per key.
+#[derive(Debug)]
+pub struct FilterBuilder {
+    bits_per_key: usize,
+    hashes:       Vec<u64>,
+}
+
+impl FilterBuilder {
+    pub fn new(bits_per_key: usize) -> Self {
+        Self { bits_per_key, hashes: Vec::new() }
+    }
+
+    pub fn add(&mut self, key: &[u8]) {
+        self.hashes.push(hash(key));
+    }
+
+    /// The contents of the filter block.
+    pub fn finish(&self) -> Vec<u8> {
+        // k = ln(2) * bits per key minimizes the rate of false positives
+        let probes = ((self.bits_per_key as f64 * 0.69) as u8).clamp(1, MAX_PROBES);
+        let bits = (self.hashes.len() * self.bits_per_key).max(64);
+        let bytes = (bits + 7) / 8;
+        let bits = bytes as u64 * 8;
+
+        let mut filter = vec![0; bytes + 1];
+        for hash in &self.hashes {
+            for bit in probe(*hash, probes, bits) {
+                filter[(bit / 8) as usize] |= 1 << (bit % 8);
+            }
+        }
+        filter[bytes] = probes;
+
+        filter
+    }
+}
+
+/// Whether the key may be one of those the `filter` was built from. Keys for
+/// which it returns `false` were definitely not.
+///
+/// ```rust
+/// use stones::sstable::{
+///     may_contain,
+///     FilterBuilder,
+/// };
+///
+/// let mut builder = FilterBuilder::new(10);
+/// builder.add(b"//app:server");
+/// let filter = builder.finish();
+///
+/// assert!(may_contain(&filter, b"//app:server"));
+/// assert!(!may_contain(&filter, b"//app:client"));
+/// ```
+pub fn may_contain(filter: &[u8], key: &[u8]) -> bool {
+    let Some((&probes, filter)) = filter.split_last() else {
+        return true;
+    };
+    // Filters with more probes are reserved, hence match everything
+    if filter.is_empty() || probes > MAX_PROBES {
+        return true;
+    }
+
+    let bits = filter.len() as u64 * 8;
+    probe(hash(key), probes, bits).all(|bit| filter[(bit / 8) as usize] & (1 << (bit % 8)) != 0)
+}
+
+/// The bits probed for a key, by double hashing its hash.
+fn probe(hash: u64, probes: u8, bits: u64) -> impl Iterator<Item = u64> {
+    let (h1, h2) = (hash & 0xffff_ffff, hash >> 32);
+    (0..u64::from(probes)).map(move |i| h1.wrapping_add(i.wrapping_mul(h2)) % bits)
+}
+
+/// The hash of a key (FNV-1a, mixed by the finalizer of splitmix64), which
+/// must be stable across runs as it is written to disk.
+fn hash(key: &[u8]) -> u64 {
+    let mut hash = 0xcbf2_9ce4_8422_2325_u64;
+    for byte in key {
+        hash ^= u64::from(*byte);
+        hash = hash.wrapping_mul(0x0000_0100_0000_01b3);
+    }
+
+    hash ^= hash >> 30;
+    hash = hash.wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    hash ^= hash >> 27;
+    hash = hash.wrapping_mul(0x94d0_49bb_1331_11eb);
+    hash ^ (hash >> 31)
+}
+
+/// The **statistics** of the lookups of keys through bloom filters.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct FilterStats {
+    /// The number of lookups the filter was consulted for.
+    pub lookups:         u64,
+    /// The number of lookups the filter answered alone, the key being
+    /// definitely absent (i.e. without reading any data block).
+    pub negatives:       u64,
+    /// The number of lookups the filter let through, although the key was
+    /// absent.
+    pub false_positives: u64,
+}
+
+impl FilterStats {
+    /// The ratio of lookups answered by the filter alone.
+    pub fn hit_rate(&self) -> f64 {
+        ratio(self.negatives, self.lookups)
+    }
+
+    /// The ratio of lookups of absent keys the filter let through.
+    ///
+    /// ```rust
+    /// use stones::sstable::FilterStats;
+    ///
+    /// let stats = FilterStats { lookups: 200, negatives: 99, false_positives: 1 };
+    /// assert_eq!(stats.hit_rate(), 0.495);
+    /// assert_eq!(stats.false_positive_rate(), 0.01);
+    /// assert_eq!(FilterStats::default().false_positive_rate(), 0.0);
+    /// ```
+    pub fn false_positive_rate(&self) -> f64 {
+        ratio(self.false_positives, self.negatives + self.false_positives)
+    }
+}
+
+impl std::ops::Add for FilterStats {
+    type Output = Self;
+
+    fn add(self, other: Self) -> Self {
+        Self {
+            lookups:         self.lookups + other.lookups,
+            negatives:       self.negatives + other.negatives,
+            false_positives: self.false_positives + other.false_positives,
+        }
+    }
+}
+
+impl std::iter::Sum for FilterStats {
+    fn sum<I: Iterator<Item = Self>>(stats: I) -> Self {
+        stats.fold(Self::default(), |sum, stats| sum + stats)
+    }
+}
+
+fn ratio(count: u64, total: u64) -> f64 {
+    if total == 0 {
+        0.0
+    } else {
+        count as f64 / total as f64
+    }
+}
+
+/// The counters of the [`FilterStats`] of a reader, updated concurrently.
+#[derive(Debug, Default)]
+pub(crate) struct FilterMetrics {
+    lookups:         AtomicU64,
+    negatives:       AtomicU64,
+    false_positives: AtomicU64,
+}
+
+impl FilterMetrics {
+    /// Record a lookup through the filter, given whether the filter let it
+    /// through and whether the key was found.
+    pub(crate) fn record(&self, passed: bool, found: bool) {
+        self.lookups.fetch_add(1, Ordering::Relaxed);
+        match (passed, found) {
+            (false, _) => self.negatives.fetch_add(1, Ordering::Relaxed),
+            (true, false) => self.false_positives.fetch_add(1, Ordering::Relaxed),
+            (true, true) => 0,
+        };
+    }
+
+    pub(crate) fn stats(&self) -> FilterStats {
+        FilterStats {
+            lookups:         self.lookups.load(Ordering::Relaxed),
+            negatives:       self.negatives.load(Ordering::Relaxed),
+            false_positives: self.false_positives.load(Ordering::Relaxed),
+        }
+    }
+}
@@ -0,0 +1,281 @@
+// src/sstable/format.rs
+
+use arrayref::array_ref;
+use diagnostics::errors::StorageError;
+use flate2::{
+    read::DeflateDecoder,
+    write::DeflateEncoder,
+};
+use std::{
+    borrow::Cow,
+    io::{
+        Read,
+        Write,
+    },
+};
+
+use super::Compression;
+
+/// The **magic** bytes SSTables start and end with.
+pub const MAGIC: [u8; 8] = *b"STONESST";
+
+/// The **version** of the format SSTables are written in (4, as of
+/// tombstones).
+pub const FORMAT_VERSION: u32 = 4;
+
+/// The oldest version of the format SSTables can still be read in (i.e.
+/// without a bloom filter).
+pub const MIN_FORMAT_VERSION: u32 = 2;
+
+/// The size of the header (the magic bytes and the version of the format).
+pub const HEADER_SIZE: usize = MAGIC.len() + 4;
+
+/// The size of the footer.
+pub const FOOTER_SIZE: usize = 44;
+
+/// The size of the footer in version 2 of the format.
+const FOOTER_V2_SIZE: usize = 32;
+
+/// The size of the trailer following the contents of each block (the kind of
+/// its contents and their checksum).
+pub const BLOCK_TRAILER_SIZE: usize = 5;
+
+/// The kind of the contents of a block stored as is.
+pub const UNCOMPRESSED: u8 = 0;
+
+/// The kind of the contents of a block compressed with deflate.
+pub const DEFLATE: u8 = 1;
+
+/// The size of the footer of SSTables written in `version` of the format.
+pub fn footer_size(version: u32) -> usize {
+    if version < 3 {
+        FOOTER_V2_SIZE
+    } else {
+        FOOTER_SIZE
+    }
+}
+
+/// The **header** of an SSTable.
+pub fn encode_header() -> [u8; HEADER_SIZE] {
+    let mut header = [0; HEADER_SIZE];
+    header[..MAGIC.len()].copy_from_slice(&MAGIC);
+    header[MAGIC.len()..].copy_from_slice(&FORMAT_VERSION.to_le_bytes());
+    header
+}
+
+/// Check the `header` of the SSTable at `path`, of `size` bytes, returning the
+/// version of the format it was written in.
+pub fn check_header(path: &str, header: &[u8], size: u64) -> Result<u32, StorageError> {
+    if header.len() < MAGIC.len() || header[..MAGIC.len()] != MAGIC {
+        return Err(StorageError::BadMagic { path: path.into() });
+    }
+    if size < HEADER_SIZE as u64 {
+        return Err(corrupted(path, size, "the file is truncated"));
+    }
+
+    let version = u32::from_le_bytes(*array_ref!(header, MAGIC.len(), 4));
+    if !(MIN_FORMAT_VERSION..=FORMAT_VERSION).contains(&version) {
+        return Err(StorageError::UnsupportedVersion {
+            path: path.into(),
+            version,
+            supported: FORMAT_VERSION,
+        });
+    }
+    if size < (HEADER_SIZE + footer_size(version)) as u64 {
+        return Err(corrupted(path, size, "the file is truncated"));
+    }
+
+    Ok(version)
+}
+
+/// The location of a **block** within an SSTable: the offset and size of its
+/// contents (i.e. without its trailer).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct BlockHandle {
+    /// The offset of the block within the file.
+    pub offset: u64,
+    /// The size of the contents of the block.
+    pub size:   u32,
+}
+
+impl BlockHandle {
+    /// The size of an encoded handle.
+    pub const SIZE: usize = 12;
+
+    pub fn encode(&self) -> [u8; Self::SIZE] {
+        let mut handle = [0; Self::SIZE];
+        handle[..8].copy_from_slice(&self.offset.to_le_bytes());
+        handle[8..].copy_from_slice(&self.size.to_le_bytes());
+        handle
+    }
+
+    /// Decode a handle, if `handle` is one.
+    pub fn decode(handle: &[u8]) -> Option<Self> {
+        if handle.len() != Self::SIZE {
+            return None;
+        }
+
+        Some(Self {
+            offset: u64::from_le_bytes(*array_ref!(handle, 0, 8)),
+            size:   u32::from_le_bytes(*array_ref!(handle, 8, 4)),
+        })
+    }
+
+    /// The offset of the end of the block, including its trailer.
+    pub fn end(&self) -> u64 {
+        self.offset + u64::from(self.size) + BLOCK_TRAILER_SIZE as u64
+    }
+}
+
+/// The **footer** of an SSTable, locating its index and its bloom filter
+/// (whose handle is all zeros if the table has none):
+///
+/// ```text
+/// +-------+--------+---------+-------+-------+
+/// | index | filter | entries | crc32 | magic |
+/// | 12 B  |  12 B  |   u64   |  u32  | 8 B   |
+/// +-------+--------+---------+-------+-------+
+/// ```
+///
+/// Version 2 of the format has no filter handle.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Footer {
+    /// The handle of the index block.
+    pub index:   BlockHandle,
+    /// The handle of the filter block, if any.
+    pub filter:  Option<BlockHandle>,
+    /// The number of entries of the SSTable.
+    pub entries: u64,
+}
+
+impl Footer {
+    pub fn encode(&self) -> [u8; FOOTER_SIZE] {
+        let no_filter = BlockHandle { offset: 0, size: 0 };
+        let mut footer = [0; FOOTER_SIZE];
+        footer[..12].copy_from_slice(&self.index.encode());
+        footer[12..24].copy_from_slice(&self.filter.unwrap_or(no_filter).encode());
+        footer[24..32].copy_from_slice(&self.entries.to_le_bytes());
+        let checksum = crc32fast::hash(&footer[..32]);
+        footer[32..36].copy_from_slice(&checksum.to_le_bytes());
+        footer[36..].copy_from_slice(&MAGIC);
+        footer
+    }
+
+    /// Decode the `footer` of the SSTable at `path`, written in `version` of
+    /// the format and found at `offset`.
+    pub fn decode(
+        path: &str,
+        footer: &[u8],
+        version: u32,
+        offset: u64,
+    ) -> Result<Self, StorageError> {
+        debug_assert_eq!(footer.len(), footer_size(version));
+        let (fields, rest) = footer.split_at(footer.len() - 12);
+        if rest[4..] != MAGIC {
+            return Err(corrupted(path, offset, "the file is truncated (no footer)"));
+        }
+
+        let expected = u32::from_le_bytes(*array_ref!(rest, 0, 4));
+        let actual = crc32fast::hash(fields);
+        if expected != actual {
+            return Err(StorageError::ChecksumMismatch {
+                path: path.into(),
+                offset,
+                expected,
+                actual,
+            });
+        }
+
+        let index = BlockHandle::decode(&fields[..12]).expect("the handle is 12 bytes");
+        if index.offset < HEADER_SIZE as u64 || index.end() > offset {
+            return Err(corrupted(path, offset, "the index is out of bounds"));
+        }
+
+        let filter = match version {
+            2 => None,
+            _ => BlockHandle::decode(&fields[12..24]).filter(|filter| filter.size > 0),
+        };
+        if filter.map_or(false, |filter| {
+            filter.offset < HEADER_SIZE as u64 || filter.end() > index.offset
+        }) {
+            return Err(corrupted(path, offset, "the filter is out of bounds"));
+        }
+
+        let entries = u64::from_le_bytes(*array_ref!(fields, fields.len() - 8, 8));
+        Ok(Self { index, filter, entries })
+    }
+}
+
+/// The checksum of a block, covering its contents and their kind.
+pub fn block_checksum(contents: &[u8], kind: u8) -> u32 {
+    let mut hasher = crc32fast::Hasher::new();
+    hasher.update(contents);
+    hasher.update(&[kind]);
+    hasher.finalize()
+}
+
+/// The trailer of a block.
+pub fn encode_trailer(contents: &[u8], kind: u8) -> [u8; BLOCK_TRAILER_SIZE] {
+    let mut trailer = [kind, 0, 0, 0, 0];
+    trailer[1..].copy_from_slice(&block_checksum(contents, kind).to_le_bytes());
+    trailer
+}
+
+/// The `contents` of a block compressed as given, and their kind, unless
+/// compressing them is not worth it.
+pub fn compress_block(contents: &[u8], compression: Compression) -> Option<(Vec<u8>, u8)> {
+    match compression {
+        Compression::None => None,
+        Compression::Deflate => {
+            let mut encoder = DeflateEncoder::new(
+                Vec::with_capacity(contents.len()),
+                flate2::Compression::default(),
+            );
+            encoder.write_all(contents).ok()?;
+            let compressed = encoder.finish().ok()?;
+            (compressed.len() <= contents.len() - contents.len() / 8)
+                .then_some((compressed, DEFLATE))
+        }
+    }
+}
+
+/// The contents of the `block` at `handle` (i.e. its contents followed by its
+/// trailer) within the SSTable at `path`, once their checksum is verified, and
+/// decompressed if they are.
+pub fn decode_block<'a>(
+    path: &str,
+    handle: BlockHandle,
+    block: &'a [u8],
+) -> Result<Cow<'a, [u8]>, StorageError> {
+    if block.len() != handle.size as usize + BLOCK_TRAILER_SIZE {
+        return Err(corrupted(path, handle.offset, "the block overruns the file"));
+    }
+
+    let (contents, trailer) = block.split_at(handle.size as usize);
+    let kind = trailer[0];
+    let expected = u32::from_le_bytes(*array_ref!(trailer, 1, 4));
+    let actual = block_checksum(contents, kind);
+    if expected != actual {
+        return Err(StorageError::ChecksumMismatch {
+            path: path.into(),
+            offset: handle.offset,
+            expected,
+            actual,
+        });
+    }
+    match kind {
+        UNCOMPRESSED => Ok(Cow::Borrowed(contents)),
+        DEFLATE => {
+            let mut decompressed = Vec::with_capacity(contents.len() * 2);
+            DeflateDecoder::new(contents).read_to_end(&mut decompressed).map_err(|e| {
+                corrupted(path, handle.offset, &format!("the block cannot be decompressed ({e})"))
+            })?;
+            Ok(Cow::Owned(decompressed))
+        }
+        _ => Err(corrupted(path, handle.offset, &format!("unknown block kind {kind}"))),
+    }
+}
+
+pub(crate) fn corrupted(path: &str, offset: u64, reason: &str) -> StorageError {
+    StorageError::Corrupted { path: path.into(), offset, reason: reason.into() }
+}
@@ -0,0 +1,165 @@
+// src/sstable/mod.rs
+
+//! **SSTables** (sorted string tables): immutable files of entries sorted by
+//! key, written when the memtable is flushed. Their format (version 4) is:
+//!
+//! ```text
+//! +--------+--------------+-----+--------------+--------------+-------------+--------+
+//! | header | data block 0 | ... | data block n | filter block | index block | footer |
+//! +--------+--------------+-----+--------------+--------------+-------------+--------+
+//! ```
+//!
+//! - the **header** holds the magic bytes `STONESST` and the version of the
+//!   format,
+//! - **data blocks** hold the entries (including the tombstones of deleted
+//!   keys), cut once they reach the block size,
+//! - the (sparse) **index block** maps the last key of each data block to its
+//!   handle (i.e. its offset and size), such that a key is looked up in a
+//!   single data block,
+//! - the (optional) **filter block** is a bloom filter of all the keys, such
+//!   that most lookups of absent keys read no data block,
+//! - the **footer** holds the handles of the index and filter blocks, and the
+//!   number of entries.
+//!
+//! Each block is followed by a trailer holding the kind of its contents (as is,
+//! or compressed with deflate) and their CRC32 checksum, verified when it is
+//! read (as is the checksum of the footer). Tables are written by an
+//! [`SSTableBuilder`], and read by an [`SSTableReader`].
+
+mod block;
+mod builder;
+mod filter;
+pub mod format;
+mod reader;
+
+use crate::value::Value;
+use diagnostics::errors::StorageError;
+use std::{
+    collections::BTreeMap,
+    fs,
+    io,
+    path::Path,
+};
+
+pub use block::{
+    BlockBuilder,
+    BlockIter,
+};
+pub use builder::SSTableBuilder;
+pub use filter::{
+    may_contain,
+    FilterBuilder,
+    FilterStats,
+};
+use format::corrupted;
+pub use reader::{
+    Access,
+    SSTableIter,
+    SSTableReader,
+};
+
+/// The size data blocks are cut at, unless configured.
+pub const DEFAULT_BLOCK_SIZE: usize = 4 * 1024;
+
+/// The number of bits per key of bloom filters, unless configured (i.e. about
+/// 1% of false positives).
+pub const DEFAULT_BITS_PER_KEY: usize = 10;
+
+/// How the data blocks of SSTables are **compressed**.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Compression {
+    /// Store blocks as is.
+    #[default]
+    None,
+    /// Compress blocks with deflate, storing those it does not shrink by at
+    /// least an eighth as is.
+    Deflate,
+}
+
+/// The **options** SSTables are written with.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct TableOptions {
+    /// The size data blocks are cut at (before compression).
+    pub block_size:   usize,
+    /// The number of bits per key of the bloom filter of each table, or 0 to
+    /// write no filter.
+    pub bits_per_key: usize,
+    /// How data blocks are compressed.
+    pub compression:  Compression,
+}
+
+impl Default for TableOptions {
+    fn default() -> Self {
+        Self {
+            block_size:   DEFAULT_BLOCK_SIZE,
+            bits_per_key: DEFAULT_BITS_PER_KEY,
+            compression:  Compression::None,
+        }
+    }
+}
+
+pub struct SSTable {
+    file_path: String,
+}
+
+impl SSTable {
+    pub fn new(file_path: &str) -> Self {
+        SSTable { file_path: file_path.to_owned() }
+    }
+
+    pub fn create(&self, data: &BTreeMap<Vec<u8>, Value>) -> Result<(), StorageError> {
+        self.create_with(data, &TableOptions::default())
+    }
+
+    pub fn create_with(
+        &self,
+        data: &BTreeMap<Vec<u8>, Value>,
+        options: &TableOptions,
+    ) -> Result<(), StorageError> {
+        let mut builder = SSTableBuilder::create(&self.file_path, options)?;
+        for (key, value) in data.iter() {
+            builder.add_entry(key, value.as_deref())?;
+        }
+        builder.finish()?;
+
+        Ok(())
+    }
+
+    /// Read all the entries of the table, validating its header, footer and
+    /// index, and the checksums of all its blocks.
+    pub fn read(&self) -> Result<BTreeMap<Vec<u8>, Value>, StorageError> {
+        let reader = SSTableReader::open(&self.file_path)?;
+
+        let mut data = BTreeMap::new();
+        for entry in reader.iter() {
+            let (key, value) = entry?;
+            if data.keys().next_back().map_or(false, |last: &Vec<u8>| key <= *last) {
+                return Err(corrupted(&self.file_path, 0, "the keys are out of order"));
+            }
+            data.insert(key, value);
+        }
+
+        if data.len() as u64 != reader.entries() {
+            return Err(corrupted(
+                &self.file_path,
+                0,
+                &format!("{} entries found, {} expected", data.len(), reader.entries()),
+            ));
+        }
+
+        Ok(data)
+    }
+
+    pub fn delete(&self) -> Result<(), StorageError> {
+        fs::remove_file(&self.file_path).map_err(|e| io_error(&self.file_path, e))?;
+        Ok(())
+    }
+
+    pub fn exists(&self) -> bool {
+        Path::new(&self.file_path).exists()
+    }
+}
+
+pub(crate) fn io_error(path: &str, e: io::Error) -> StorageError {
+    StorageError::Io { path: path.into(), reason: e.to_string().into() }
+}
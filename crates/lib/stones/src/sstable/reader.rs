@@ -0,0 +1,378 @@
+// src/sstable/reader.rs
+
+use diagnostics::errors::StorageError;
+use memmap2::Mmap;
+use std::{
+    borrow::Cow,
+    fs::File,
+    io,
+    sync::Arc,
+};
+
+use super::{
+    block::{
+        decode_entry,
+        BlockIter,
+    },
+    filter::{
+        may_contain,
+        FilterMetrics,
+        FilterStats,
+    },
+    format::{
+        check_header,
+        corrupted,
+        decode_block,
+        footer_size,
+        BlockHandle,
+        Footer,
+        BLOCK_TRAILER_SIZE,
+        HEADER_SIZE,
+    },
+    io_error,
+};
+use crate::value::Value;
+
+/// How the blocks of an SSTable are **accessed** by its reader.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Access {
+    /// Read each block with a positional read.
+    #[default]
+    Read,
+    /// Map the file in memory, such that blocks are read through the page
+    /// cache without being copied.
+    ///
+    /// The file must not be truncated while mapped: reading past its new end
+    /// raises `SIGBUS` instead of an error. SSTables are only removed once
+    /// compacted, never truncated, but a file truncated by another process
+    /// (or on a full disk) would crash the reader.
+    Mmap,
+}
+
+/// A **reader** of an SSTable, opened once: its index and bloom filter are read
+/// (and validated) when opening it, such that looking a key up reads at most
+/// the data block that may contain it, and none if the filter rules it out.
+pub struct SSTableReader {
+    path:    String,
+    source:  Source,
+    footer:  Footer,
+    /// The last key of each data block and its handle, by increasing key.
+    index:   Vec<(Vec<u8>, BlockHandle)>,
+    /// The contents of the bloom filter block, if any.
+    filter:  Option<Vec<u8>>,
+    metrics: FilterMetrics,
+}
+
+impl SSTableReader {
+    /// Open the SSTable at `path`, reading its blocks as needed.
+    pub fn open(path: &str) -> Result<Self, StorageError> {
+        Self::open_with(path, Access::Read)
+    }
+
+    /// Open the SSTable at `path`, accessing its blocks as given.
+    pub fn open_with(path: &str, access: Access) -> Result<Self, StorageError> {
+        let file = File::open(path).map_err(|e| io_error(path, e))?;
+        let size = file.metadata().map_err(|e| io_error(path, e))?.len();
+        let source = Source::new(file, size, access).map_err(|e| io_error(path, e))?;
+
+        let header =
+            source.read(0, HEADER_SIZE.min(size as usize)).map_err(|e| io_error(path, e))?;
+        let version = check_header(path, &header, size)?;
+
+        let footer_size = footer_size(version);
+        let offset = size - footer_size as u64;
+        let footer = source.read(offset, footer_size).map_err(|e| io_error(path, e))?;
+        let footer = Footer::decode(path, &footer, version, offset)?;
+
+        let mut reader = Self {
+            path: path.to_owned(),
+            source,
+            footer,
+            index: Vec::new(),
+            filter: None,
+            metrics: FilterMetrics::default(),
+        };
+        // Data blocks are followed by the filter, if any, then by the index
+        let data_end = footer.filter.unwrap_or(footer.index).offset;
+        if footer.filter.map_or(false, |filter| filter.end() != footer.index.offset) {
+            return Err(corrupted(
+                path,
+                footer.index.offset,
+                "the filter is not followed by the index",
+            ));
+        }
+
+        let index = reader.read_block(footer.index)?;
+        let mut next_block = HEADER_SIZE as u64;
+        let mut handles = Vec::new();
+        for entry in BlockIter::new(path, footer.index.offset, &index) {
+            let (last_key, handle) = entry?;
+            let handle = handle
+                .and_then(BlockHandle::decode)
+                .ok_or_else(|| corrupted(path, footer.index.offset, "invalid block handle"))?;
+            // Data blocks are contiguous up to the index, by increasing key
+            let sorted =
+                handles.last().map_or(true, |(last, _): &(Vec<u8>, _)| last.as_slice() < last_key);
+            if handle.offset != next_block || handle.end() > data_end || !sorted {
+                return Err(corrupted(path, footer.index.offset, "the index is inconsistent"));
+            }
+            next_block = handle.end();
+            handles.push((last_key.to_vec(), handle));
+        }
+        if next_block != data_end {
+            return Err(corrupted(path, next_block, "a data block is not indexed"));
+        }
+        drop(index);
+        reader.index = handles;
+
+        if let Some(filter) = footer.filter {
+            reader.filter = Some(reader.read_block(filter)?.into_owned());
+        }
+
+        Ok(reader)
+    }
+
+    /// The path of the table.
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+
+    /// The number of entries of the table.
+    pub fn entries(&self) -> u64 {
+        self.footer.entries
+    }
+
+    /// The value of `key` (or its tombstone), if the table contains it. The
+    /// bloom filter of the table (if any) is consulted first, such that most
+    /// misses read no block.
+    pub fn get(&self, key: &[u8]) -> Result<Option<Value>, StorageError> {
+        let passed = self.may_contain(key);
+        if !passed {
+            self.metrics.record(false, false);
+            return Ok(None);
+        }
+
+        let value = match self.seek(key).next() {
+            Some(Ok((found, value))) if found == key => Some(value),
+            Some(Err(e)) => return Err(e),
+            _ => None,
+        };
+        if self.filter.is_some() {
+            self.metrics.record(true, value.is_some());
+        }
+
+        Ok(value)
+    }
+
+    /// Whether the table may contain `key`, according to its bloom filter
+    /// (always, without one).
+    pub fn may_contain(&self, key: &[u8]) -> bool {
+        self.filter.as_deref().map_or(true, |filter| may_contain(filter, key))
+    }
+
+    /// The statistics of the lookups through the bloom filter of the table.
+    pub fn filter_stats(&self) -> FilterStats {
+        self.metrics.stats()
+    }
+
+    /// An iterator over the entries of the table, from the first one whose
+    /// key is greater than or equal to `key`.
+    pub fn seek(&self, key: &[u8]) -> SSTableIter<'_> {
+        // The first block whose last key is not less than `key` is the only
+        // one which may contain it
+        let block = self.index.partition_point(|(last, _)| last.as_slice() < key);
+        SSTableIter { next_block: block, seek: Some(key.to_vec()), ..self.iter() }
+    }
+
+    /// An iterator over all the entries of the table.
+    pub fn iter(&self) -> SSTableIter<'_> {
+        SSTableIter::new(ReaderRef::Borrowed(self))
+    }
+
+    /// Like [`seek`](Self::seek), but owning the reader, such that the
+    /// iterator may outlive the reference it was created from (e.g. once the
+    /// table is compacted away), at the cost of copying mapped blocks.
+    pub fn seek_shared(reader: &Arc<Self>, key: &[u8]) -> SSTableIter<'static> {
+        let block = reader.index.partition_point(|(last, _)| last.as_slice() < key);
+        SSTableIter {
+            next_block: block,
+            seek: Some(key.to_vec()),
+            ..SSTableIter::new(ReaderRef::Shared(reader.clone()))
+        }
+    }
+
+    /// Like [`iter`](Self::iter), but owning the reader.
+    pub fn iter_shared(reader: &Arc<Self>) -> SSTableIter<'static> {
+        SSTableIter::new(ReaderRef::Shared(reader.clone()))
+    }
+
+    /// The contents of the block at `handle`, once their checksum is verified
+    /// (and decompressed, if they are).
+    fn read_block(&self, handle: BlockHandle) -> Result<Cow<'_, [u8]>, StorageError> {
+        let size = handle.size as usize + BLOCK_TRAILER_SIZE;
+        let block = self.source.read(handle.offset, size).map_err(|e| io_error(&self.path, e))?;
+
+        match block {
+            Cow::Borrowed(block) => decode_block(&self.path, handle, block),
+            Cow::Owned(mut block) => {
+                let decompressed = match decode_block(&self.path, handle, &block)? {
+                    Cow::Owned(decompressed) => Some(decompressed),
+                    Cow::Borrowed(_) => None,
+                };
+                Ok(Cow::Owned(decompressed.unwrap_or_else(|| {
+                    block.truncate(handle.size as usize);
+                    block
+                })))
+            }
+        }
+    }
+}
+
+/// An **iterator** over the entries of an SSTable (tombstones included), by
+/// increasing key, reading its data blocks one at a time.
+pub struct SSTableIter<'a> {
+    reader:     ReaderRef<'a>,
+    /// The position of the next block to read within the index.
+    next_block: usize,
+    /// The contents of the current block, and its handle.
+    block:      Option<(Cow<'a, [u8]>, BlockHandle)>,
+    /// The position of the next entry within the current block.
+    position:   usize,
+    /// The key the iterator was seeked to, until an entry reaches it.
+    seek:       Option<Vec<u8>>,
+    /// Whether reading the table failed, which ends the iteration.
+    failed:     bool,
+}
+
+/// The reader an [`SSTableIter`] reads from.
+enum ReaderRef<'a> {
+    Borrowed(&'a SSTableReader),
+    Shared(Arc<SSTableReader>),
+}
+
+impl<'a> SSTableIter<'a> {
+    fn new(reader: ReaderRef<'a>) -> Self {
+        Self { reader, next_block: 0, block: None, position: 0, seek: None, failed: false }
+    }
+
+    fn reader(&self) -> &SSTableReader {
+        match &self.reader {
+            ReaderRef::Borrowed(reader) => reader,
+            ReaderRef::Shared(reader) => reader,
+        }
+    }
+
+    /// The contents of the block at `handle`, borrowed from the reader if it
+    /// is.
+    fn read_block(&self, handle: BlockHandle) -> Result<Cow<'a, [u8]>, StorageError> {
+        match &self.reader {
+            ReaderRef::Borrowed(reader) => reader.read_block(handle),
+            ReaderRef::Shared(reader) => {
+                reader.read_block(handle).map(|block| Cow::Owned(block.into_owned()))
+            }
+        }
+    }
+}
+
+impl<'a> Iterator for SSTableIter<'a> {
+    type Item = Result<(Vec<u8>, Value), StorageError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while !self.failed {
+            let Some((contents, handle)) = &self.block else {
+                let (_, handle) = *self.reader().index.get(self.next_block)?;
+                self.next_block += 1;
+                match self.read_block(handle) {
+                    Ok(contents) => self.block = Some((contents, handle)),
+                    Err(e) => {
+                        self.failed = true;
+                        return Some(Err(e));
+                    }
+                }
+                self.position = 0;
+                continue;
+            };
+
+            if self.position >= contents.len() {
+                self.block = None;
+                continue;
+            }
+
+            match decode_entry(contents, self.position) {
+                Ok((key, value, next)) => {
+                    self.position = next;
+                    if self.seek.as_deref().map_or(false, |seek| key < seek) {
+                        continue;
+                    }
+
+                    self.seek = None;
+                    return Some(Ok((key.to_vec(), Value::from(value))));
+                }
+                Err(reason) => {
+                    let offset = handle.offset + self.position as u64;
+                    self.failed = true;
+                    return Some(Err(corrupted(&self.reader().path, offset, reason)));
+                }
+            }
+        }
+
+        None
+    }
+}
+
+/// The **source** the blocks of an SSTable are read from.
+enum Source {
+    File(File),
+    Mmap(Mmap),
+}
+
+impl Source {
+    fn new(file: File, size: u64, access: Access) -> io::Result<Self> {
+        match access {
+            // Safety: SSTables are immutable once written, and never truncated
+            // (see `Access::Mmap`)
+            Access::Mmap if size > 0 => Ok(Self::Mmap(unsafe { Mmap::map(&file)? })),
+            _ => Ok(Self::File(file)),
+        }
+    }
+
+    /// Read `size` bytes at `offset`.
+    fn read(&self, offset: u64, size: usize) -> io::Result<Cow<'_, [u8]>> {
+        match self {
+            Self::File(file) => {
+                let mut buf = vec![0; size];
+                read_exact_at(file, &mut buf, offset)?;
+                Ok(Cow::Owned(buf))
+            }
+            Self::Mmap(mmap) => usize::try_from(offset)
+                .ok()
+                .and_then(|offset| mmap.get(offset..offset.checked_add(size)?))
+                .map(Cow::Borrowed)
+                .ok_or_else(|| io::ErrorKind::UnexpectedEof.into()),
+        }
+    }
+}
+
+#[cfg(unix)]
+fn read_exact_at(file: &File, buf: &mut [u8], offset: u64) -> io::Result<()> {
+    use std::os::unix::fs::FileExt;
+
+    file.read_exact_at(buf, offset)
+}
+
+#[cfg(windows)]
+fn read_exact_at(file: &File, mut buf: &mut [u8], mut offset: u64) -> io::Result<()> {
+    use std::os::windows::fs::FileExt;
+
+    while !buf.is_empty() {
+        match file.seek_read(buf, offset)? {
+            0 => return Err(io::ErrorKind::UnexpectedEof.into()),
+            read => {
+                buf = &mut buf[read..];
+                offset += read as u64;
+            }
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,46 @@
+// src/value.rs
+
+/// The **value** of a key within the memtable or an SSTable: either the value
+/// put, or a tombstone left by deleting the key. Tombstones shadow the older
+/// values of their key (i.e. within older SSTables), and are only dropped by
+/// compaction once no older table can contain the key.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Value {
+    Put(Vec<u8>),
+    Tombstone,
+}
+
+impl Value {
+    pub fn is_tombstone(&self) -> bool {
+        matches!(self, Self::Tombstone)
+    }
+
+    /// The value put, unless the key was deleted.
+    pub fn as_deref(&self) -> Option<&[u8]> {
+        match self {
+            Self::Put(value) => Some(value),
+            Self::Tombstone => None,
+        }
+    }
+
+    /// The value put, unless the key was deleted.
+    ///
+    /// ```rust
+    /// use stones::value::Value;
+    ///
+    /// assert_eq!(Value::Put(b"value".to_vec()).into_option(), Some(b"value".to_vec()));
+    /// assert_eq!(Value::Tombstone.into_option(), None);
+    /// ```
+    pub fn into_option(self) -> Option<Vec<u8>> {
+        match self {
+            Self::Put(value) => Some(value),
+            Self::Tombstone => None,
+        }
+    }
+}
+
+impl From<Option<&[u8]>> for Value {
+    fn from(value: Option<&[u8]>) -> Self {
+        value.map_or(Self::Tombstone, |value| Self::Put(value.to_vec()))
+    }
+}
@@ -0,0 +1,237 @@
+// src/wal.rs
+
+//! The **write-ahead log** (WAL) of an LSM tree: each write is appended to it
+//! (as a checksummed record) before being applied to the memtable, such that
+//! the writes not flushed yet to an SSTable are replayed after a crash. Each
+//! memtable has its own log, numbered in the order they were rotated, which is
+//! removed once the memtable is flushed.
+//!
+//! ```text
+//! +-------+------+------+----------+-----+-------+
+//! | crc32 | size | kind | key size | key | value |   (for each record)
+//! |  u32  | u32  |  u8  |   u32    |     |       |
+//! +-------+------+------+----------+-----+-------+
+//! ```
+//!
+//! where `size` is the size of the record following it, and its checksum
+//! covers it.
+
+use arrayref::array_ref;
+use diagnostics::errors::StorageError;
+use std::{
+    fs::{
+        self,
+        File,
+        OpenOptions,
+    },
+    io::{
+        Read,
+        Seek,
+        SeekFrom,
+        Write,
+    },
+};
+
+use crate::sstable::io_error;
+
+/// The path of the log numbered `number` within `dir`.
+///
+/// ```rust
+/// use stones::wal::{
+///     log_path,
+///     parse_log_name,
+/// };
+///
+/// assert_eq!(log_path("cache", 7), "cache/000007.log");
+/// assert_eq!(parse_log_name("000007.log"), Some(7));
+/// assert_eq!(parse_log_name("000007.sst"), None);
+/// ```
+pub fn log_path(dir: &str, number: u64) -> String {
+    format!("{dir}/{number:06}.log")
+}
+
+/// The number of the log named `name`, if it is one.
+pub fn parse_log_name(name: &str) -> Option<u64> {
+    let number = name.strip_suffix(".log")?;
+    number.bytes().all(|b| b.is_ascii_digit()).then(|| number.parse().ok()).flatten()
+}
+
+/// The numbers of the logs within `dir`, in increasing order.
+pub fn logs(dir: &str) -> Result<Vec<u64>, StorageError> {
+    let mut logs = Vec::new();
+    let read_dir_error = |e| io_error(dir, e);
+    for entry in fs::read_dir(dir).map_err(read_dir_error)? {
+        let name = entry.map_err(read_dir_error)?.file_name();
+        logs.extend(name.to_str().and_then(parse_log_name));
+    }
+    logs.sort_unstable();
+
+    Ok(logs)
+}
+
+/// The size of the header of each record (its checksum and size).
+const RECORD_HEADER_SIZE: usize = 8;
+
+const PUT: u8 = 1;
+const DELETE: u8 = 2;
+
+/// When the log is **synced** to disk, trading durability for throughput.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SyncMode {
+    /// Sync the log after every write, such that no write is lost, even on
+    /// power loss.
+    #[default]
+    Always,
+    /// Sync the log once every given number of writes, such that at most as
+    /// many writes are lost on power loss.
+    Batch(usize),
+    /// Never sync the log (i.e. leave it to the OS), such that writes survive
+    /// crashes of the process, but not of the machine.
+    None,
+}
+
+/// A **record** of the log, i.e. a write.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Record<'a> {
+    Put { key: &'a [u8], value: &'a [u8] },
+    Delete { key: &'a [u8] },
+}
+
+impl<'a> Record<'a> {
+    pub fn encode(&self) -> Vec<u8> {
+        let (kind, key, value) = match *self {
+            Self::Put { key, value } => (PUT, key, value),
+            Self::Delete { key } => (DELETE, key, &[][..]),
+        };
+
+        let size = 1 + 4 + key.len() + value.len();
+        let mut record = Vec::with_capacity(RECORD_HEADER_SIZE + size);
+        record.extend_from_slice(&[0; 4]);
+        record.extend_from_slice(&(size as u32).to_le_bytes());
+        record.push(kind);
+        record.extend_from_slice(&(key.len() as u32).to_le_bytes());
+        record.extend_from_slice(key);
+        record.extend_from_slice(value);
+
+        let checksum = crc32fast::hash(&record[RECORD_HEADER_SIZE..]);
+        record[..4].copy_from_slice(&checksum.to_le_bytes());
+        record
+    }
+
+    /// Decode the record at the start of `log`, returning it and its size (or
+    /// why it is not a valid record).
+    pub fn decode(log: &'a [u8]) -> Result<(Self, usize), &'static str> {
+        if log.len() < RECORD_HEADER_SIZE {
+            return Err("the header of a record is truncated");
+        }
+
+        let checksum = u32::from_le_bytes(*array_ref!(log, 0, 4));
+        let size = u32::from_le_bytes(*array_ref!(log, 4, 4)) as usize;
+        let Some(record) = log[RECORD_HEADER_SIZE..].get(..size) else {
+            return Err("a record is truncated");
+        };
+        if crc32fast::hash(record) != checksum {
+            return Err("the checksum of a record does not match");
+        }
+        if size < 5 {
+            return Err("a record is too small");
+        }
+
+        let key_size = u32::from_le_bytes(*array_ref!(record, 1, 4)) as usize;
+        let Some(key) = record[5..].get(..key_size) else {
+            return Err("the key of a record overruns it");
+        };
+        let value = &record[5 + key_size..];
+        let record = match record[0] {
+            PUT => Self::Put { key, value },
+            DELETE if value.is_empty() => Self::Delete { key },
+            _ => return Err("unknown record kind"),
+        };
+
+        Ok((record, RECORD_HEADER_SIZE + size))
+    }
+}
+
+/// The **write-ahead log** of an LSM tree.
+pub struct Wal {
+    path:     String,
+    file:     File,
+    sync:     SyncMode,
+    /// The number of records appended since the log was last synced.
+    unsynced: usize,
+}
+
+impl Wal {
+    /// Open the log at `path` (creating it if needed), and replay its records
+    /// through `apply`, in the order they were appended. A torn tail (i.e. a
+    /// record not fully written before a crash) is dropped with a warning.
+    pub fn open(
+        path: &str,
+        sync: SyncMode,
+        mut apply: impl FnMut(Record<'_>),
+    ) -> Result<Self, StorageError> {
+        let mut file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(false)
+            .open(path)
+            .map_err(|e| io_error(path, e))?;
+        let mut log = Vec::new();
+        file.read_to_end(&mut log).map_err(|e| io_error(path, e))?;
+
+        let mut end = 0;
+        while end < log.len() {
+            match Record::decode(&log[end..]) {
+                Ok((record, size)) => {
+                    apply(record);
+                    end += size;
+                }
+                Err(reason) => {
+                    let warning = StorageError::TornWal {
+                        path:    path.into(),
+                        offset:  end as u64,
+                        dropped: (log.len() - end) as u64,
+                        reason:  reason.into(),
+                    };
+                    tracing::warn!("{:?}", miette::Report::new(warning));
+
+                    file.set_len(end as u64)
+                        .and_then(|_| file.sync_all())
+                        .map_err(|e| io_error(path, e))?;
+                    break;
+                }
+            }
+        }
+        file.seek(SeekFrom::Start(end as u64)).map_err(|e| io_error(path, e))?;
+
+        Ok(Self { path: path.to_owned(), file, sync, unsynced: 0 })
+    }
+
+    /// The path of the log.
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+
+    /// Append a record, syncing the log as configured.
+    pub fn append(&mut self, record: Record<'_>) -> Result<(), StorageError> {
+        self.file.write_all(&record.encode()).map_err(|e| io_error(&self.path, e))?;
+        self.unsynced += 1;
+
+        match self.sync {
+            SyncMode::Always => self.sync(),
+            SyncMode::Batch(writes) if self.unsynced >= writes => self.sync(),
+            _ => Ok(()),
+        }
+    }
+
+    /// Sync the records appended so far to disk.
+    pub fn sync(&mut self) -> Result<(), StorageError> {
+        if self.unsynced > 0 {
+            self.file.sync_data().map_err(|e| io_error(&self.path, e))?;
+            self.unsynced = 0;
+        }
+
+        Ok(())
+    }
+}
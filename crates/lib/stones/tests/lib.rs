@@ -1,5 +1,6 @@
 mod lsmtree;
 mod sstable;
+mod wal;
 // #[cfg(test)]
 // mod lsmtree_test_suite {
 //     #[test]
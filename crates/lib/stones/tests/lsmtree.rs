@@ -1 +1,403 @@
+#[cfg(test)]
+mod lsmtree_test_suite {
+    // tests/lsmtree.rs
 
+    pub type Result<T> = std::result::Result<T, Box<dyn std::error::Error>>;
+    use std::{
+        fs,
+        ops::Bound,
+        path::PathBuf,
+        sync::atomic::{
+            AtomicBool,
+            Ordering,
+        },
+        thread,
+    };
+
+    use diagnostics::errors::StorageError;
+    use stones::{
+        compaction::CompactionStrategy,
+        lsmtree::{
+            LsmTree,
+            LOCK_FILE,
+        },
+        manifest::{
+            table_path,
+            MANIFEST_FILE,
+        },
+        options::Options,
+        sstable::Compression,
+        wal::{
+            log_path,
+            logs,
+            SyncMode,
+        },
+    };
+
+    /// An empty directory for the tree of a test, within the temporary
+    /// directory.
+    fn tree_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("stones-{}", std::process::id())).join(name);
+        let _ = fs::remove_dir_all(&dir);
+        dir
+    }
+
+    #[test]
+    fn test_lsmtree_tombstones() -> Result<()> {
+        let dir = tree_dir("test_lsmtree_tombstones");
+
+        // Flush the first 1000 keys to an SSTable
+        let tree = LsmTree::open(&dir, Options::default())?;
+        for i in 0..1_000 {
+            tree.put(format!("key{i:05}").as_bytes(), format!("value{i}").as_bytes())?;
+        }
+        tree.flush()?;
+        assert!(fs::read_dir(&dir)?.any(|entry| entry
+            .unwrap()
+            .path()
+            .extension()
+            .map_or(false, |e| e == "sst")));
+
+        // Deleted keys do not reappear from the SSTable
+        tree.delete(b"key00042")?;
+        tree.delete(b"absent")?;
+        assert_eq!(tree.get(b"key00042")?, None);
+        assert_eq!(tree.get(b"absent")?, None);
+        assert_eq!(tree.get(b"key00043")?, Some(b"value43".to_vec()));
+
+        // Nor once the tombstones are replayed from the log
+        drop(tree);
+        let tree = LsmTree::open(&dir, Options::default())?;
+        assert_eq!(tree.get(b"key00042")?, None);
+        assert_eq!(tree.get(b"key00043")?, Some(b"value43".to_vec()));
+
+        // Keys put again after being deleted are back
+        tree.put(b"key00042", b"again")?;
+        assert_eq!(tree.get(b"key00042")?, Some(b"again".to_vec()));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_lsmtree_manifest() -> Result<()> {
+        let dir = tree_dir("test_lsmtree_manifest");
+        let dir_str = dir.to_str().unwrap();
+
+        // Two flushes, the second overwriting the keys of the first
+        let tree = LsmTree::open(&dir, Options::default())?;
+        for i in 0..1_000 {
+            tree.put(format!("key{i:05}").as_bytes(), b"old")?;
+        }
+        tree.flush()?;
+        for i in 500..1_500 {
+            tree.put(format!("key{i:05}").as_bytes(), b"new")?;
+        }
+        tree.flush()?;
+        tree.put(b"key00000", b"memtable")?;
+
+        let tables = tree.tables();
+        assert_eq!(tables.iter().map(|table| table.file).collect::<Vec<_>>(), [1, 2]);
+        assert_eq!(tables[1].smallest, b"key00500");
+        assert_eq!(tables[1].largest, b"key01499");
+        assert!(tables.iter().all(|table| table.level == 0 && table.entries == 1_000));
+
+        let check = |tree: &LsmTree| -> Result<()> {
+            assert_eq!(tree.get(b"key00000")?, Some(b"memtable".to_vec()));
+            assert_eq!(tree.get(b"key00499")?, Some(b"old".to_vec()));
+            assert_eq!(tree.get(b"key00500")?, Some(b"new".to_vec()));
+            assert_eq!(tree.get(b"key01499")?, Some(b"new".to_vec()));
+            assert_eq!(tree.get(b"key01500")?, None);
+            Ok(())
+        };
+        check(&tree)?;
+        drop(tree);
+
+        // Tables are recovered from the manifest, and those it does not list
+        // (e.g. written before a crash) are removed
+        fs::write(table_path(dir_str, 3), b"leftover")?;
+        let tree = LsmTree::open(&dir, Options::default())?;
+        assert_eq!(tree.tables().len(), 2);
+        assert!(!dir.join("000003.sst").exists());
+        check(&tree)?;
+        drop(tree);
+
+        fs::write(dir.join(MANIFEST_FILE), b"{}")?;
+        assert!(matches!(
+            LsmTree::open(&dir, Options::default()),
+            Err(StorageError::BadManifest { .. })
+        ));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_lsmtree_scan() -> Result<()> {
+        let dir = tree_dir("test_lsmtree_scan");
+        let tree = LsmTree::open(&dir, Options::default())?;
+
+        // An SSTable of cache entries for two packages, partly overwritten
+        // and deleted within the memtable
+        for i in 0..500 {
+            tree.put(format!("pkg/a/{i:03}").as_bytes(), b"old")?;
+            tree.put(format!("pkg/b/{i:03}").as_bytes(), b"old")?;
+        }
+        tree.flush()?;
+        assert_eq!(tree.tables().len(), 1);
+        tree.put(b"pkg/a/001", b"new")?;
+        tree.put(b"pkg/a/1000", b"new")?;
+        tree.delete(b"pkg/a/002")?;
+        tree.delete(b"pkg/a/003")?;
+        tree.put(b"pkg/a/003", b"again")?;
+        tree.put(b"pkg/ab", b"other")?;
+
+        let keys = |scan: stones::lsmtree::Scan| -> Result<Vec<String>> {
+            let entries = scan.collect::<std::result::Result<Vec<_>, _>>()?;
+            Ok(entries.into_iter().map(|(key, _)| String::from_utf8(key).unwrap()).collect())
+        };
+
+        let entries = tree.prefix(b"pkg/a/")?.collect::<std::result::Result<Vec<_>, _>>()?;
+        assert_eq!(entries.len(), 500);
+        assert_eq!(entries[..4], [
+            (b"pkg/a/000".to_vec(), b"old".to_vec()),
+            (b"pkg/a/001".to_vec(), b"new".to_vec()),
+            (b"pkg/a/003".to_vec(), b"again".to_vec()),
+            (b"pkg/a/004".to_vec(), b"old".to_vec()),
+        ]);
+        assert_eq!(entries[499], (b"pkg/a/499".to_vec(), b"old".to_vec()));
+        assert!(entries.windows(2).all(|pair| pair[0].0 < pair[1].0));
+        assert!(entries.iter().any(|(key, _)| key == b"pkg/a/1000"));
+
+        assert_eq!(keys(tree.scan(&b"pkg/a/497"[..]..&b"pkg/ab"[..])?)?, [
+            "pkg/a/497",
+            "pkg/a/498",
+            "pkg/a/499"
+        ]);
+        assert_eq!(
+            keys(tree.scan::<[u8]>((
+                Bound::Excluded(&b"pkg/a/498"[..]),
+                Bound::Included(&b"pkg/b/000"[..])
+            ))?)?,
+            ["pkg/a/499", "pkg/ab", "pkg/b/000"]
+        );
+        assert_eq!(keys(tree.scan::<[u8]>(..)?)?.len(), 1_001);
+        assert!(keys(tree.scan(&b"pkg/b"[..]..&b"pkg/a"[..])?)?.is_empty());
+        assert!(keys(tree.prefix(b"pkg/c/")?)?.is_empty());
+        assert_eq!(keys(tree.prefix(b"")?)?.len(), 1_001);
+
+        // A scan reads the tree as of when it started
+        let scan = tree.prefix(b"pkg/b/")?;
+        tree.delete(b"pkg/b/000")?;
+        assert_eq!(keys(scan)?.len(), 500);
+        assert_eq!(keys(tree.prefix(b"pkg/b/")?)?.len(), 499);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_lsmtree_background_flush() -> Result<()> {
+        let dir = tree_dir("test_lsmtree_background_flush");
+        let dir_str = dir.to_str().unwrap();
+        // Small memtables, with writes waiting for each one to be flushed
+        // before rotating the next
+        let options = Options::builder()
+            .memtable_size(16 * 1024)
+            .max_immutables(1)
+            .sync(SyncMode::None)
+            .build();
+        let tree = LsmTree::open(&dir, options)?;
+        tree.put(b"first", b"value")?;
+
+        // Memtables remain readable while they are flushed, and until their
+        // tables are
+        let writing = AtomicBool::new(true);
+        thread::scope(|scope| -> Result<()> {
+            let reader = scope.spawn(|| {
+                while writing.load(Ordering::Relaxed) {
+                    assert_eq!(tree.get(b"first").unwrap(), Some(b"value".to_vec()));
+                }
+            });
+            for i in 0..10_000 {
+                tree.put(format!("key{i:05}").as_bytes(), format!("value{i}").as_bytes())?;
+                if i % 1_000 == 999 {
+                    let key = format!("key{:05}", i - 999);
+                    assert_eq!(tree.get(key.as_bytes())?, Some(format!("value{}", i - 999).into()));
+                }
+            }
+            writing.store(false, Ordering::Relaxed);
+            reader.join().unwrap();
+            Ok(())
+        })?;
+
+        // Once flushed, only the log of the new active memtable remains
+        tree.flush()?;
+        assert_eq!(logs(dir_str)?.len(), 1);
+        assert!(tree.tables().len() > 1);
+        assert_eq!(tree.scan::<[u8]>(..)?.count(), 10_001);
+        drop(tree);
+
+        let tree = LsmTree::open(&dir, Options::default())?;
+        assert_eq!(tree.get(b"first")?, Some(b"value".to_vec()));
+        assert_eq!(tree.get(b"key09999")?, Some(b"value9999".to_vec()));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_lsmtree_failed_flush() -> Result<()> {
+        let dir = tree_dir("test_lsmtree_failed_flush");
+        let dir_str = dir.to_str().unwrap();
+        let options =
+            Options::builder().memtable_size(1024).max_immutables(1).sync(SyncMode::None).build();
+        let tree = LsmTree::open(&dir, options)?;
+
+        // Tables cannot be created over directories, failing every flush
+        let blocked: Vec<_> = (0..1_000).map(|file| table_path(dir_str, file)).collect();
+        blocked.iter().try_for_each(fs::create_dir)?;
+
+        // Every write waiting for the flush thread is failed, not just the
+        // first one
+        thread::scope(|scope| {
+            let writers: Vec<_> = (0..2)
+                .map(|writer| {
+                    let tree = &tree;
+                    scope.spawn(move || {
+                        (0..10_000).try_for_each(|i| {
+                            tree.put(format!("{writer}-{i:05}").as_bytes(), b"value")
+                        })
+                    })
+                })
+                .collect();
+            for writer in writers {
+                assert!(writer.join().unwrap().is_err());
+            }
+        });
+        assert!(tree.flush().is_err());
+
+        // Until a flush succeeds
+        blocked.iter().try_for_each(fs::remove_dir)?;
+        let flushed = (0..100).any(|_| {
+            tree.flush().is_ok() || {
+                thread::sleep(std::time::Duration::from_millis(10));
+                false
+            }
+        });
+        assert!(flushed);
+        assert_eq!(tree.get(b"0-00000")?, Some(b"value".to_vec()));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_lsmtree_lifecycle() -> Result<()> {
+        let dir = tree_dir("test_lsmtree_lifecycle");
+        let dir_str = dir.to_str().unwrap();
+        let options = Options::builder().compression(Compression::Deflate).build();
+
+        // The directory is locked while the tree is open
+        let tree = LsmTree::open(&dir, options)?;
+        assert!(dir.join(LOCK_FILE).exists());
+        assert!(matches!(LsmTree::open(&dir, options), Err(StorageError::Locked { .. })));
+        for i in 0..1_000 {
+            tree.put(format!("key{i:05}").as_bytes(), b"value")?;
+        }
+
+        // Closing the tree flushes its memtable, and unlocks it
+        tree.close()?;
+        assert_eq!(logs(dir_str)?.len(), 1);
+        assert!(fs::read(log_path(dir_str, logs(dir_str)?[0]))?.is_empty());
+
+        let tree = LsmTree::open(&dir, Options::default())?;
+        assert_eq!(tree.tables().len(), 1);
+        assert_eq!(tree.get(b"key00042")?, Some(b"value".to_vec()));
+        assert_eq!(tree.prefix(b"key")?.count(), 1_000);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_lsmtree_size_tiered_compaction() -> Result<()> {
+        let dir = tree_dir("test_lsmtree_size_tiered_compaction");
+        let compaction = CompactionStrategy::SizeTiered { tables: 2 };
+        let tree = LsmTree::open(&dir, Options::builder().compaction(compaction).build())?;
+
+        // A table of values, then a table of their tombstones and new values
+        for i in 0..1_000 {
+            tree.put(format!("key{i:05}").as_bytes(), b"value")?;
+        }
+        tree.flush()?;
+        for i in 0..999 {
+            tree.delete(format!("key{i:05}").as_bytes())?;
+        }
+        tree.put(b"key01000", b"value")?;
+        tree.flush()?;
+        tree.compact()?;
+
+        // With no older table, the tombstones are dropped along with the
+        // values they shadow
+        let tables = tree.tables();
+        assert_eq!(tables.len(), 1);
+        assert_eq!((tables[0].level, tables[0].entries), (1, 2));
+        assert_eq!(tree.get(b"key00000")?, None);
+        assert_eq!(tree.get(b"key00999")?, Some(b"value".to_vec()));
+        assert_eq!(tree.get(b"key01000")?, Some(b"value".to_vec()));
+
+        // The merged tables are removed
+        let files = fs::read_dir(&dir)?.filter(|entry| {
+            entry.as_ref().unwrap().path().extension().map_or(false, |e| e == "sst")
+        });
+        assert_eq!(files.count(), 1);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_lsmtree_leveled_compaction() -> Result<()> {
+        let dir = tree_dir("test_lsmtree_leveled_compaction");
+        let compaction = CompactionStrategy::Leveled {
+            level0_tables: 2,
+            base_size:     32 * 1024,
+            multiplier:    2,
+            table_size:    16 * 1024,
+        };
+        let options = Options::builder().memtable_size(16 * 1024).compaction(compaction).build();
+        let tree = LsmTree::open(&dir, options)?;
+
+        for round in 0..3 {
+            for i in (round..6_000).step_by(3) {
+                tree.put(format!("key{i:05}").as_bytes(), format!("value{i}-{round}").as_bytes())?;
+            }
+        }
+        for i in (0..6_000).step_by(1_000) {
+            tree.delete(format!("key{i:05}").as_bytes())?;
+        }
+        tree.compact()?;
+        drop(tree);
+
+        let tree = LsmTree::open(&dir, options)?;
+        tree.compact()?;
+        for i in 0..6_000 {
+            let expected = (i % 1_000 != 0).then(|| format!("value{i}-{}", i % 3).into_bytes());
+            assert_eq!(tree.get(format!("key{i:05}").as_bytes())?, expected, "key{i:05}");
+        }
+
+        // The tables of each level but level 0 are disjoint, and all levels
+        // are within their budget
+        let tables = tree.tables();
+        assert!(tables.iter().filter(|table| table.level == 0).count() < 2);
+        for level in 1..7 {
+            let mut ranges = tables
+                .iter()
+                .filter(|table| table.level == level)
+                .map(|table| (table.smallest.clone(), table.largest.clone()))
+                .collect::<Vec<_>>();
+            ranges.sort();
+            assert!(ranges.windows(2).all(|pair| pair[0].1 < pair[1].0), "level {level}");
+
+            let size = tables.iter().filter(|table| table.level == level).map(|t| t.size);
+            assert!(size.sum::<u64>() <= (32 * 1024) << (level - 1), "level {level}");
+        }
+        assert!(tables.iter().any(|table| table.level >= 2));
+
+        Ok(())
+    }
+}
@@ -5,18 +5,70 @@ mod sstable_test_suite {
     pub type Result<T> = std::result::Result<T, Box<dyn std::error::Error>>;
     use std::{
         collections::BTreeMap,
-        path::PathBuf,
+        fs,
+        path::{
+            Path,
+            PathBuf,
+        },
     };
 
-    use stones::sstable::SSTable;
+    use diagnostics::errors::StorageError;
+    use stones::sstable::{
+        format::{
+            FOOTER_SIZE,
+            FORMAT_VERSION,
+            HEADER_SIZE,
+            MAGIC,
+        },
+        Access,
+        Compression,
+        FilterStats,
+        SSTable,
+        SSTableBuilder,
+        SSTableReader,
+        TableOptions,
+    };
+    use stones::value::Value;
+
+    /// A path for the table of a test, within the temporary directory.
+    fn table_path(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("stones-{}", std::process::id()));
+        fs::create_dir_all(&dir).expect("Unable to create the directory of the tables");
+        dir.join(name)
+    }
+
+    /// Write a table of `count` entries, cutting blocks at `block_size` bytes.
+    fn build_table(path: &Path, count: usize, block_size: usize) -> BTreeMap<Vec<u8>, Value> {
+        build_table_with(path, count, &TableOptions { block_size, ..TableOptions::default() })
+    }
+
+    fn build_table_with(
+        path: &Path,
+        count: usize,
+        options: &TableOptions,
+    ) -> BTreeMap<Vec<u8>, Value> {
+        let data = (0..count)
+            .map(|i| (format!("key{i:05}").into_bytes(), format!("value{i}").into_bytes()))
+            .map(|(key, value)| (key, Value::Put(value)))
+            .collect::<BTreeMap<_, _>>();
+
+        let mut builder = SSTableBuilder::create(path.to_str().unwrap(), options).unwrap();
+        for (key, value) in &data {
+            builder.add_entry(key, value.as_deref()).unwrap();
+        }
+        assert_eq!(builder.finish().unwrap(), count as u64);
+
+        data
+    }
 
     #[test]
     fn test_sstable_create_and_read() -> Result<()> {
         let mut data = BTreeMap::new();
-        data.insert(b"key1".to_vec(), b"value1".to_vec());
-        data.insert(b"key2".to_vec(), b"value2".to_vec());
+        data.insert(b"key1".to_vec(), Value::Put(b"value1".to_vec()));
+        data.insert(b"key2".to_vec(), Value::Put(b"value2".to_vec()));
+        data.insert(b"key3".to_vec(), Value::Tombstone);
 
-        let file_path = PathBuf::from("test_sstable_create_and_read.sst");
+        let file_path = table_path("test_sstable_create_and_read.sst");
         let file_path_str = file_path.to_str().expect("Unable to convert path to string");
 
         let sstable = SSTable::new(file_path_str);
@@ -25,15 +77,20 @@ mod sstable_test_suite {
         let read_data = sstable.read().unwrap();
         assert_eq!(read_data, data);
 
+        let reader = SSTableReader::open(file_path_str)?;
+        assert_eq!(reader.get(b"key3")?, Some(Value::Tombstone));
+        assert_eq!(reader.get(b"key4")?, None);
+
         Ok(())
     }
 
     #[test]
     fn test_sstable_delete() -> Result<()> {
-        let file_path = PathBuf::from("test_sstable_delete.sst");
+        let file_path = table_path("test_sstable_delete.sst");
         let file_path_str = file_path.to_str().expect("Unable to convert path to string");
 
         let sstable = SSTable::new(file_path_str);
+        sstable.create(&BTreeMap::new())?;
         assert!(sstable.exists());
 
         sstable.delete()?;
@@ -41,4 +98,226 @@ mod sstable_test_suite {
 
         Ok(())
     }
+
+    #[test]
+    fn test_sstable_format() -> Result<()> {
+        let file_path = table_path("test_sstable_format.sst");
+        let data = build_table(&file_path, 1_000, 256);
+
+        let file = fs::read(&file_path)?;
+        assert_eq!(file[..MAGIC.len()], MAGIC);
+        assert_eq!(file[MAGIC.len()..HEADER_SIZE], FORMAT_VERSION.to_le_bytes());
+        assert_eq!(file[file.len() - MAGIC.len()..], MAGIC);
+
+        let sstable = SSTable::new(file_path.to_str().unwrap());
+        assert_eq!(sstable.read()?, data);
+
+        // An empty table is only its header, an empty index and its footer (no
+        // filter)
+        let empty_path = table_path("test_sstable_format_empty.sst");
+        assert!(build_table(&empty_path, 0, 256).is_empty());
+        assert_eq!(fs::metadata(&empty_path)?.len() as usize, HEADER_SIZE + 5 + FOOTER_SIZE);
+        assert!(SSTable::new(empty_path.to_str().unwrap()).read()?.is_empty());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_sstable_corruption() -> Result<()> {
+        let file_path = table_path("test_sstable_corruption.sst");
+        build_table(&file_path, 100, 256);
+        let original = fs::read(&file_path)?;
+        let sstable = SSTable::new(file_path.to_str().unwrap());
+        let corrupt = |update: &dyn Fn(&mut Vec<u8>)| {
+            let mut file = original.clone();
+            update(&mut file);
+            fs::write(&file_path, file).unwrap();
+            sstable.read().unwrap_err()
+        };
+
+        // A flipped bit within the first data block
+        let error = corrupt(&|file| file[HEADER_SIZE + 10] ^= 1);
+        assert!(
+            matches!(error, StorageError::ChecksumMismatch { offset, .. } if offset == HEADER_SIZE as u64)
+        );
+
+        // A flipped bit within the footer
+        let error = corrupt(&|file| {
+            let offset = file.len() - FOOTER_SIZE + 2;
+            file[offset] ^= 1
+        });
+        assert!(matches!(error, StorageError::ChecksumMismatch { .. }));
+
+        let error = corrupt(&|file| file[..MAGIC.len()].copy_from_slice(b"NOTATABL"));
+        assert!(matches!(error, StorageError::BadMagic { .. }));
+
+        let error = corrupt(&|file| file[MAGIC.len()..HEADER_SIZE].copy_from_slice(&[9, 0, 0, 0]));
+        assert!(matches!(error, StorageError::UnsupportedVersion {
+            version: 9,
+            supported: FORMAT_VERSION,
+            ..
+        }));
+
+        let error = corrupt(&|file| file.truncate(file.len() / 2));
+        assert!(matches!(error, StorageError::Corrupted { .. }));
+
+        // The records written by the format predating the header
+        let error = corrupt(&|file| *file = [4, 0, 0, 0, 6, 0, 0, 0].to_vec());
+        assert!(matches!(error, StorageError::BadMagic { .. }));
+
+        let missing = SSTable::new(table_path("test_sstable_missing.sst").to_str().unwrap());
+        assert!(matches!(missing.read().unwrap_err(), StorageError::Io { .. }));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_sstable_reader() -> Result<()> {
+        let file_path = table_path("test_sstable_reader.sst");
+        let data = build_table(&file_path, 1_000, 256);
+
+        for access in [Access::Read, Access::Mmap] {
+            let reader = SSTableReader::open_with(file_path.to_str().unwrap(), access)?;
+            assert_eq!(reader.entries(), 1_000);
+
+            // Keys of the first, a middle and the last block, and misses
+            // before, between and after them
+            assert_eq!(reader.get(b"key00000")?, Some(Value::Put(b"value0".to_vec())));
+            assert_eq!(reader.get(b"key00500")?, Some(Value::Put(b"value500".to_vec())));
+            assert_eq!(reader.get(b"key00999")?, Some(Value::Put(b"value999".to_vec())));
+            assert_eq!(reader.get(b"a")?, None);
+            assert_eq!(reader.get(b"key00500a")?, None);
+            assert_eq!(reader.get(b"z")?, None);
+
+            let seeked = reader.seek(b"key00997").collect::<std::result::Result<Vec<_>, _>>()?;
+            assert_eq!(seeked.len(), 3);
+            assert_eq!(seeked[0], (b"key00997".to_vec(), Value::Put(b"value997".to_vec())));
+            let seeked = reader.seek(b"key00499a").map(|entry| entry.unwrap().0).next();
+            assert_eq!(seeked, Some(b"key00500".to_vec()));
+            assert_eq!(reader.seek(b"z").count(), 0);
+
+            let all = reader.iter().collect::<std::result::Result<BTreeMap<_, _>, _>>()?;
+            assert_eq!(all, data);
+        }
+
+        // Blocks are verified as they are read
+        let mut file = fs::read(&file_path)?;
+        file[HEADER_SIZE + 10] ^= 1;
+        fs::write(&file_path, file)?;
+        let reader = SSTableReader::open(file_path.to_str().unwrap())?;
+        assert_eq!(reader.get(b"key00999")?, Some(Value::Put(b"value999".to_vec())));
+        assert!(matches!(reader.get(b"key00000"), Err(StorageError::ChecksumMismatch { .. })));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_sstable_bloom_filter() -> Result<()> {
+        let file_path = table_path("test_sstable_bloom_filter.sst");
+        let data = build_table(&file_path, 1_000, 256);
+        let reader = SSTableReader::open(file_path.to_str().unwrap())?;
+
+        // No false negatives
+        for (key, value) in &data {
+            assert!(reader.may_contain(key));
+            assert_eq!(reader.get(key)?.as_ref(), Some(value));
+        }
+        assert_eq!(reader.filter_stats(), FilterStats {
+            lookups:         1_000,
+            negatives:       0,
+            false_positives: 0,
+        });
+
+        // About 1% of false positives with 10 bits per key
+        for i in 0..1_000 {
+            assert_eq!(reader.get(format!("absent{i:05}").as_bytes())?, None);
+        }
+        let stats = reader.filter_stats();
+        assert_eq!(stats.lookups, 2_000);
+        assert_eq!(stats.negatives + stats.false_positives, 1_000);
+        assert!(stats.false_positive_rate() < 0.05, "{stats:?}");
+        assert!(stats.hit_rate() > 0.45, "{stats:?}");
+
+        // Misses ruled out by the filter read no data block
+        let mut file = fs::read(&file_path)?;
+        file[HEADER_SIZE + 10] ^= 1;
+        fs::write(&file_path, file)?;
+        let reader = SSTableReader::open(file_path.to_str().unwrap())?;
+        let absent = (0..100)
+            .map(|i| format!("absent{i:05}").into_bytes())
+            .find(|key| !reader.may_contain(key))
+            .unwrap();
+        assert_eq!(reader.get(&absent)?, None);
+
+        // Tables written without a filter are looked up as before
+        let file_path = table_path("test_sstable_no_filter.sst");
+        let options = TableOptions { bits_per_key: 0, ..TableOptions::default() };
+        build_table_with(&file_path, 1_000, &options);
+        let reader = SSTableReader::open(file_path.to_str().unwrap())?;
+        assert!(reader.may_contain(b"absent"));
+        assert_eq!(reader.get(b"absent")?, None);
+        assert_eq!(reader.get(b"key00042")?, Some(Value::Put(b"value42".to_vec())));
+        assert_eq!(reader.filter_stats(), FilterStats::default());
+
+        // Tables written in version 2 of the format (without a filter handle)
+        // are still read
+        let mut file = fs::read(&file_path)?;
+        let footer = file.split_off(file.len() - FOOTER_SIZE);
+        let mut fields = footer[..12].to_vec();
+        fields.extend_from_slice(&footer[24..32]);
+        let checksum = crc32fast::hash(&fields);
+        file.extend_from_slice(&fields);
+        file.extend_from_slice(&checksum.to_le_bytes());
+        file.extend_from_slice(&MAGIC);
+        file[MAGIC.len()..HEADER_SIZE].copy_from_slice(&2u32.to_le_bytes());
+        fs::write(&file_path, file)?;
+        let reader = SSTableReader::open(file_path.to_str().unwrap())?;
+        assert_eq!(reader.entries(), 1_000);
+        assert_eq!(reader.get(b"key00042")?, Some(Value::Put(b"value42".to_vec())));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_sstable_compression() -> Result<()> {
+        let plain_path = table_path("test_sstable_compression_none.sst");
+        build_table(&plain_path, 1_000, 1_024);
+        let file_path = table_path("test_sstable_compression.sst");
+        let options = TableOptions {
+            block_size: 1_024,
+            compression: Compression::Deflate,
+            ..TableOptions::default()
+        };
+        let data = build_table_with(&file_path, 1_000, &options);
+
+        // Blocks are compressed, and decompressed when read
+        assert!(fs::metadata(&file_path)?.len() < fs::metadata(&plain_path)?.len() / 2);
+        assert_eq!(SSTable::new(file_path.to_str().unwrap()).read()?, data);
+        for access in [Access::Read, Access::Mmap] {
+            let reader = SSTableReader::open_with(file_path.to_str().unwrap(), access)?;
+            assert_eq!(reader.get(b"key00042")?, Some(Value::Put(b"value42".to_vec())));
+            let entries = reader.seek(b"key00998").collect::<std::result::Result<Vec<_>, _>>()?;
+            assert_eq!(entries.len(), 2);
+        }
+
+        // Blocks which compression does not shrink enough are stored as is
+        let file_path = table_path("test_sstable_compression_random.sst");
+        let mut builder = SSTableBuilder::create(file_path.to_str().unwrap(), &options)?;
+        let mut state = 0x2545_f491_4f6c_dd1du64;
+        for i in 0..100 {
+            let value = (0..64)
+                .map(|_| {
+                    state ^= state << 13;
+                    state ^= state >> 7;
+                    state ^= state << 17;
+                    state as u8
+                })
+                .collect::<Vec<_>>();
+            builder.add(format!("key{i:05}").as_bytes(), &value)?;
+        }
+        builder.finish()?;
+        assert_eq!(SSTableReader::open(file_path.to_str().unwrap())?.iter().count(), 100);
+
+        Ok(())
+    }
 }
@@ -0,0 +1,132 @@
+#[cfg(test)]
+mod wal_test_suite {
+    // tests/wal.rs
+
+    pub type Result<T> = std::result::Result<T, Box<dyn std::error::Error>>;
+    use std::{
+        fs,
+        path::PathBuf,
+    };
+
+    use stones::{
+        lsmtree::LsmTree,
+        options::Options,
+        wal::{
+            log_path,
+            Record,
+            SyncMode,
+            Wal,
+        },
+    };
+
+    /// An empty directory for the tree of a test, within the temporary
+    /// directory.
+    fn tree_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("stones-{}", std::process::id())).join(name);
+        let _ = fs::remove_dir_all(&dir);
+        dir
+    }
+
+    #[test]
+    fn test_wal_records() {
+        let records = [
+            Record::Put { key: b"key", value: b"value" },
+            Record::Put { key: b"", value: b"" },
+            Record::Delete { key: b"key" },
+        ];
+        for record in records {
+            let encoded = record.encode();
+            assert_eq!(Record::decode(&encoded), Ok((record, encoded.len())));
+
+            let mut flipped = encoded.clone();
+            *flipped.last_mut().unwrap() ^= 1;
+            assert!(Record::decode(&flipped).is_err());
+            assert!(Record::decode(&encoded[..encoded.len() - 1]).is_err());
+        }
+    }
+
+    #[test]
+    fn test_wal_recovery() -> Result<()> {
+        let dir = tree_dir("test_wal_recovery");
+
+        for sync in [SyncMode::Always, SyncMode::Batch(2), SyncMode::None] {
+            let tree = LsmTree::open(&dir, Options::builder().sync(sync).build())?;
+            tree.put(b"key1", b"value1")?;
+            tree.put(b"key2", b"value2")?;
+            tree.put(b"key1", b"value3")?;
+            tree.delete(b"key2")?;
+            // A crash, without flushing the memtable
+            drop(tree);
+
+            let tree = LsmTree::open(&dir, Options::default())?;
+            assert_eq!(tree.get(b"key1")?, Some(b"value3".to_vec()));
+            assert_eq!(tree.get(b"key2")?, None);
+            drop(tree);
+            fs::remove_dir_all(&dir)?;
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_wal_torn_tail() -> Result<()> {
+        let dir = tree_dir("test_wal_torn_tail");
+        let dir_str = dir.to_str().unwrap();
+        let wal_path = PathBuf::from(log_path(dir_str, 1));
+
+        let tree = LsmTree::open(&dir, Options::default())?;
+        tree.put(b"key1", b"value1")?;
+        tree.put(b"key2", b"value2")?;
+        drop(tree);
+
+        // The last record was only partially written
+        let log = fs::read(&wal_path)?;
+        fs::write(&wal_path, &log[..log.len() - 3])?;
+        let mut replayed = Vec::new();
+        let wal = Wal::open(wal_path.to_str().unwrap(), SyncMode::Always, |record| {
+            replayed.push(format!("{record:?}"))
+        })?;
+        assert_eq!(replayed.len(), 1);
+        drop(wal);
+        let truncated = fs::read(&wal_path)?;
+        assert_eq!(truncated, log[..Record::Put { key: b"key1", value: b"value1" }.encode().len()]);
+
+        // Writes are appended after the valid records
+        let tree = LsmTree::open(&dir, Options::default())?;
+        assert_eq!(tree.get(b"key1")?, Some(b"value1".to_vec()));
+        assert_eq!(tree.get(b"key2")?, None);
+        tree.put(b"key3", b"value3")?;
+        drop(tree);
+        let tree = LsmTree::open(&dir, Options::default())?;
+        assert_eq!(tree.get(b"key1")?, Some(b"value1".to_vec()));
+        assert_eq!(tree.get(b"key3")?, Some(b"value3".to_vec()));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_wal_removed_after_flush() -> Result<()> {
+        let dir = tree_dir("test_wal_removed_after_flush");
+        let dir_str = dir.to_str().unwrap();
+
+        // Memtables of 1000 entries of 13 bytes
+        let options = Options::builder().memtable_size(13 * 1_000).sync(SyncMode::None).build();
+        let tree = LsmTree::open(&dir, options)?;
+        for i in 0..1_001 {
+            tree.put(format!("key{i:05}").as_bytes(), b"value")?;
+        }
+        // The first 1000 writes are flushed (at the latest once the tree is
+        // dropped), hence their log is removed
+        drop(tree);
+        assert!(!PathBuf::from(log_path(dir_str, 1)).exists());
+        let log = fs::read(log_path(dir_str, 2))?;
+        assert_eq!(log, Record::Put { key: b"key01000", value: b"value" }.encode());
+
+        let tree = LsmTree::open(&dir, Options::default())?;
+        assert_eq!(tree.get(b"key00000")?, Some(b"value".to_vec()));
+        assert_eq!(tree.get(b"key01000")?, Some(b"value".to_vec()));
+        assert_eq!(tree.tables().len(), 1);
+
+        Ok(())
+    }
+}
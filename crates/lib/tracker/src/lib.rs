@@ -0,0 +1,339 @@
+//! **Progress rendering** for the tasks executed by a build tool (e.g. the
+//! actions of a `buck2 build`), as a tree of spinners below a header
+//! summarizing in-progress and completed tasks, cache hits and elapsed time.
+//!
+//! Tasks are rendered as `tracing` spans by the [`layer`], which must be part
+//! of the global subscriber for progress to be shown. When progress cannot be
+//! redrawn in place (e.g. in CI logs), [`Mode::Periodic`] logs plain-text
+//! progress lines instead, and [`Mode::Detached`] leaves rendering to the
+//! caller (e.g. a TUI) through [`Tracker::running`].
+
+mod stats;
+pub mod style;
+
+use getset::{
+    CopyGetters,
+    Getters,
+};
+use once_cell::sync::Lazy;
+use owo_colors::OwoColorize;
+use parking_lot::{
+    Mutex,
+    RwLock,
+};
+use smartstring::alias::String;
+use std::{
+    collections::HashMap,
+    sync::{
+        atomic::{
+            AtomicUsize,
+            Ordering,
+        },
+        mpsc::{
+            self,
+            RecvTimeoutError,
+            Sender,
+        },
+        Arc,
+    },
+    thread,
+    time::{
+        Duration,
+        Instant,
+        SystemTime,
+        UNIX_EPOCH,
+    },
+};
+use tracing::{
+    info,
+    info_span,
+    Metadata,
+    Span,
+    Subscriber,
+};
+use tracing_indicatif::{
+    span_ext::IndicatifSpanExt,
+    IndicatifLayer,
+};
+use tracing_subscriber::registry::LookupSpan;
+
+pub use stats::Stats;
+
+/// How the progress of tracked tasks is **rendered**.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum Mode {
+    /// Progress bars redrawn in place by the [`layer`].
+    #[default]
+    Live,
+    /// A plain-text progress line logged at the given interval.
+    Periodic(Duration),
+    /// Nothing rendered by the tracker itself, the caller rendering progress
+    /// from the [running](Tracker::running) tasks instead (e.g. within a TUI).
+    Detached,
+}
+
+static MODE: Lazy<RwLock<Mode>> = Lazy::new(|| RwLock::new(Mode::default()));
+
+/// Set how the progress of trackers created from now on is rendered.
+pub fn set_mode(mode: Mode) {
+    *MODE.write() = mode;
+}
+
+/// An **event** reported by a build tool about one of its tasks.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum TaskEvent {
+    /// A task started, optionally nested under another (running) task.
+    Begin { id: String, name: String, parent: Option<String> },
+    /// A task finished, successfully or not.
+    End { id: String, success: bool },
+    /// A task was served from a cache (e.g. a remote action cache).
+    CacheHit { id: String },
+    /// Tasks reported in aggregate by the build tool (e.g. in its build
+    /// metrics) rather than individually.
+    Summary { completed: usize, cache_hits: usize },
+}
+
+/// A **running task**, as reported by [`Tracker::running`].
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Getters, CopyGetters)]
+pub struct RunningTask {
+    #[getset(get = "pub")]
+    name:    String,
+    /// How many running tasks the task is nested under.
+    #[getset(get_copy = "pub")]
+    depth:   usize,
+    #[getset(get_copy = "pub")]
+    elapsed: Duration,
+}
+
+#[derive(Debug)]
+struct Task {
+    /// The order the task started in, among the tasks of the tracker.
+    order:   usize,
+    span:    Span,
+    name:    String,
+    depth:   usize,
+    started: Instant,
+}
+
+/// A **tracker** rendering the progress of the tasks executed for a command
+/// (e.g. `build //foo:bar`). Cloning a tracker yields a handle to the same
+/// set of tasks.
+#[derive(Debug, Clone)]
+pub struct Tracker {
+    inner: Arc<Inner>,
+}
+
+#[derive(Debug)]
+struct Inner {
+    id:      String,
+    command: String,
+    mode:    Mode,
+    stats:   Arc<RwLock<Stats>>,
+    header:  Mutex<Option<Span>>,
+    tasks:   Mutex<HashMap<String, Task>>,
+    begun:   AtomicUsize,
+    started: Instant,
+    /// Stops logging periodic progress lines when dropped.
+    ticker:  Mutex<Option<Sender<()>>>,
+}
+
+impl Tracker {
+    /// Start tracking the tasks executed for `command`, rendering the header.
+    pub fn new(command: &str) -> Self {
+        let id = task_id();
+        let stats = Arc::new(RwLock::new(Stats::default()));
+
+        let mode = *MODE.read();
+        let header = match mode {
+            Mode::Detached => Span::none(),
+            Mode::Live | Mode::Periodic(_) => info_span!("header"),
+        };
+        header.pb_set_style(&style::header_style(&style::task_display(command, &id), &stats));
+        header.pb_start();
+        // Display full "-----" line underneath the header.
+        header.pb_set_length(1);
+        header.pb_set_position(1);
+
+        let started = Instant::now();
+        let ticker = match mode {
+            Mode::Live | Mode::Detached => None,
+            Mode::Periodic(interval) => {
+                info!("Executing tasks for command: {}", command);
+                Some(ticker(command.into(), stats.clone(), started, interval))
+            }
+        };
+
+        Self {
+            inner: Arc::new(Inner {
+                id,
+                command: command.into(),
+                mode,
+                stats,
+                header: Mutex::new(Some(header)),
+                tasks: Mutex::new(HashMap::new()),
+                begun: AtomicUsize::new(0),
+                started,
+                ticker: Mutex::new(ticker),
+            }),
+        }
+    }
+
+    /// The (unique) ID of the tracked command.
+    pub fn id(&self) -> &str {
+        &self.inner.id
+    }
+
+    /// The command the tasks are executed for.
+    pub fn command(&self) -> &str {
+        &self.inner.command
+    }
+
+    pub fn stats(&self) -> Stats {
+        *self.inner.stats.read()
+    }
+
+    /// How long the tasks have been tracked for.
+    pub fn elapsed(&self) -> Duration {
+        self.inner.started.elapsed()
+    }
+
+    /// The tasks still running, in the order they started.
+    pub fn running(&self) -> Vec<RunningTask> {
+        let tasks = self.inner.tasks.lock();
+        let mut running = tasks.values().collect::<Vec<_>>();
+        running.sort_by_key(|task| task.order);
+        running
+            .into_iter()
+            .map(|task| RunningTask {
+                name:    task.name.clone(),
+                depth:   task.depth,
+                elapsed: task.started.elapsed(),
+            })
+            .collect()
+    }
+
+    /// Handle an **event** reported by the build tool.
+    pub fn handle(&self, event: TaskEvent) {
+        match event {
+            TaskEvent::Begin { id, name, parent } => self.begin(id, &name, parent.as_deref()),
+            TaskEvent::End { id, success } => self.end(&id, success),
+            TaskEvent::CacheHit { id } => self.cache_hit(&id),
+            TaskEvent::Summary { completed, cache_hits } => self.record(completed, cache_hits),
+        }
+    }
+
+    /// Start rendering a task, nested under `parent` if it is still running.
+    pub fn begin(&self, id: impl Into<String>, name: &str, parent: Option<&str>) {
+        let mut tasks = self.inner.tasks.lock();
+        let parent = parent.and_then(|parent| tasks.get(parent));
+        let span = match (self.inner.mode, parent) {
+            (Mode::Detached, _) => Span::none(),
+            (_, Some(parent)) => info_span!(parent: &parent.span, "task", message = %name),
+            (_, None) => match self.inner.header.lock().as_ref() {
+                Some(header) => info_span!(parent: header, "task", message = %name),
+                None => info_span!("task", message = %name),
+            },
+        };
+        span.pb_set_style(&style::task_style());
+        span.pb_start();
+
+        let depth = parent.map_or(0, |parent| parent.depth + 1);
+        let order = self.inner.begun.fetch_add(1, Ordering::Relaxed);
+        let task = Task { order, span, name: name.into(), depth, started: Instant::now() };
+        if tasks.insert(id.into(), task).is_none() {
+            self.inner.stats.write().begin();
+        }
+    }
+
+    /// Stop rendering a task.
+    pub fn end(&self, id: &str, success: bool) {
+        if self.inner.tasks.lock().remove(id).is_some() {
+            self.inner.stats.write().end(success);
+        }
+    }
+
+    pub fn cache_hit(&self, id: &str) {
+        tracing::trace!("Cache hit for task {}", id);
+        self.inner.stats.write().cache_hit();
+    }
+
+    /// Record tasks reported **in aggregate** by the build tool (e.g. in the
+    /// summary printed at the end of a build) rather than through events.
+    pub fn record(&self, completed: usize, cache_hits: usize) {
+        self.inner.stats.write().record(completed, cache_hits);
+    }
+
+    /// Stop rendering every task (and the header), logging a summary of the
+    /// tasks executed.
+    pub fn finish(&self) -> Stats {
+        let remaining = self.inner.tasks.lock().drain().count();
+        if remaining > 0 {
+            tracing::debug!("{} task(s) still running when finished", remaining);
+        }
+        self.inner.header.lock().take();
+        self.inner.ticker.lock().take();
+
+        let stats = self.stats();
+        let elapsed = self.inner.started.elapsed();
+        info!("Finished executing tasks for command: {}", self.inner.command);
+        info!(" Jobs Finished{} {}", ":".black(), stats.completed().green().bold().italic());
+        if stats.failed() > 0 {
+            info!(" Jobs Failed{} {}", ":".black(), stats.failed().red().bold().italic());
+        }
+        info!(" {}{}", style::cache_hits_msg(stats.cache_hits_percentage()), "%".black());
+        info!(
+            " Time elapsed{} {}{}{}s",
+            ":".black(),
+            elapsed.as_secs().to_string().cyan().italic(),
+            ".".black(),
+            elapsed.subsec_millis().to_string().cyan().italic()
+        );
+
+        stats
+    }
+}
+
+/// The `tracing` **layer** rendering tracked tasks as progress bars.
+pub fn layer<S>() -> IndicatifLayer<S>
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+{
+    IndicatifLayer::new()
+        .with_progress_style(style::task_style())
+        .with_span_child_prefix_symbol(style::child_prefix_symbol())
+        .with_span_child_prefix_indent(" ")
+}
+
+/// Whether `metadata` is that of a span rendered by the [`layer`] (i.e. a
+/// tracked task or its header), rather than of any other span (e.g. the topic
+/// of a log).
+pub fn is_progress_span(metadata: &Metadata<'_>) -> bool {
+    metadata.is_span() && metadata.target() == module_path!()
+}
+
+/// Log a plain-text progress line every `interval` until the returned sender
+/// is dropped, within the current span (e.g. the topic of the build).
+fn ticker(
+    command: String,
+    stats: Arc<RwLock<Stats>>,
+    started: Instant,
+    interval: Duration,
+) -> Sender<()> {
+    let (tx, rx) = mpsc::channel();
+    let span = Span::current();
+    thread::spawn(move || {
+        let _span = span.entered();
+        while let Err(RecvTimeoutError::Timeout) = rx.recv_timeout(interval) {
+            let elapsed = started.elapsed().as_secs_f64();
+            info!("Progress of `{}`: {} ({:.1}s elapsed)", command, stats.read(), elapsed);
+        }
+    });
+    tx
+}
+
+/// Generate a (sufficiently) unique ID for a tracked command.
+fn task_id() -> String {
+    let nanos = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_nanos();
+    let pid = u64::from(std::process::id()).rotate_left(24);
+    format!("{:010X}", (nanos as u64 ^ pid) & 0xFF_FFFF_FFFF).into()
+}
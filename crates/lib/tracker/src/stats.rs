@@ -0,0 +1,53 @@
+use derive_more::Display;
+use getset::{
+    CopyGetters,
+    Setters,
+};
+
+/// **Statistics** of the tasks reported to a [`Tracker`](crate::Tracker),
+/// shown in its header.
+#[derive(Debug, Clone, Copy, Default, Display, PartialEq, Eq, Hash, CopyGetters, Setters)]
+#[display(fmt = "In progress {in_progress}, completed {completed}, failed {failed}, cache hits \
+                 {cache_hits}")]
+#[getset(get_copy = "pub", set = "pub")]
+pub struct Stats {
+    in_progress: usize,
+    completed:   usize,
+    failed:      usize,
+    cache_hits:  usize,
+}
+
+impl Stats {
+    pub(crate) fn begin(&mut self) {
+        self.in_progress += 1;
+    }
+
+    pub(crate) fn end(&mut self, success: bool) {
+        self.in_progress = self.in_progress.saturating_sub(1);
+        if success {
+            self.completed += 1;
+        } else {
+            self.failed += 1;
+        }
+    }
+
+    pub(crate) fn record(&mut self, completed: usize, cache_hits: usize) {
+        self.completed += completed;
+        self.cache_hits += cache_hits;
+    }
+
+    pub(crate) fn cache_hit(&mut self) {
+        self.cache_hits += 1;
+    }
+
+    /// The percentage (from `0` to `100`) of finished tasks served from a
+    /// cache.
+    pub fn cache_hits_percentage(&self) -> f64 {
+        let finished = self.completed + self.failed;
+        if finished > 0 {
+            (self.cache_hits.min(finished) as f64 / finished as f64) * 100.0
+        } else {
+            0.0
+        }
+    }
+}
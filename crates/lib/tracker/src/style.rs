@@ -0,0 +1,223 @@
+use indicatif::{
+    ProgressState,
+    ProgressStyle,
+};
+use once_cell::sync::Lazy;
+use owo_colors::{
+    OwoColorize,
+    Rgb,
+};
+use parking_lot::RwLock;
+use smartstring::alias::String;
+use std::{
+    fmt::Write,
+    sync::Arc,
+    time::Duration,
+};
+
+use crate::Stats;
+
+// Durations for different progress indicators
+pub const SHORT_DURATION_START_TIME: Duration = Duration::from_secs(1);
+pub const MEDIUM_DURATION_START_TIME: Duration = Duration::from_secs(4);
+pub const LONG_DURATION_START_TIME: Duration = Duration::from_secs(8);
+pub const VERY_LONG_DURATION_START_TIME: Duration = Duration::from_secs(12);
+
+// Colors
+pub const RED: Rgb = Rgb(255, 0, 0);
+pub const ORANGE: Rgb = Rgb(255, 165, 0);
+pub const YELLOW: Rgb = Rgb(255, 255, 0);
+pub const GREEN: Rgb = Rgb(0, 255, 0);
+pub const DARK_GREEN: Rgb = Rgb(0, 100, 0);
+
+/// The default **gradient** progress is painted along, from best to worst.
+pub const GRADIENT: [Rgb; 5] = [DARK_GREEN, GREEN, YELLOW, ORANGE, RED];
+
+/// The gradient progress is currently painted along, as set by
+/// [`set_gradient`].
+static CURRENT_GRADIENT: Lazy<RwLock<[Rgb; 5]>> = Lazy::new(|| RwLock::new(GRADIENT));
+
+// Symbols
+static RIGHT_ARROW_SYMBOL: Lazy<std::string::String> =
+    Lazy::new(|| "↳ ".italic().color(Rgb(0, 0, 0)).to_string());
+
+/// Frames of the spinner shown next to in-progress tasks.
+pub const SPINNER: [&str; 4] = ["◐", "◓", "◑", "◒"];
+
+/// Paint progress along the given `gradient` (e.g. that of the theme of the
+/// user), from best to worst.
+pub fn set_gradient(gradient: [Rgb; 5]) {
+    *CURRENT_GRADIENT.write() = gradient;
+}
+
+/// The gradient progress is painted along, from best to worst.
+pub fn gradient() -> [Rgb; 5] {
+    *CURRENT_GRADIENT.read()
+}
+
+/// The symbol prefixing nested tasks.
+pub fn child_prefix_symbol() -> &'static str {
+    &RIGHT_ARROW_SYMBOL
+}
+
+/// The style of the progress bar of a single **task**: a spinner, a label
+/// describing how long the task has been running for, and the elapsed time,
+/// colored along a green-to-red gradient.
+pub fn task_style() -> ProgressStyle {
+    ProgressStyle::with_template(
+        r"{spinner:.green}{color_start}{span_child_prefix}{span_fields}{wide_msg}{elapsed_subsec}{color_end}",
+    )
+    .expect("task progress template is valid")
+    .tick_strings(&SPINNER)
+    .with_key("elapsed_subsec", elapsed_subsec)
+    .with_key("color_start", task_msg_display)
+    .with_key("color_end", color_end)
+}
+
+/// The style of the **header** summarizing the progress of every task of
+/// `command` (i.e. in-progress, completed and failed tasks, cache hits and
+/// elapsed time).
+pub fn header_style(command: &str, stats: &Arc<RwLock<Stats>>) -> ProgressStyle {
+    let template = "Executing tasks for command: {task_display}. {wide_msg} Jobs: In progress: \
+                    {in_progress}. Finished: {completed}. Failed: {failed}. \
+                    {cache_hits_percentage}%. Time elapsed: {elapsed_subsec}
+\n{wide_bar}";
+
+    let (in_progress, completed, failed) = (stats.clone(), stats.clone(), stats.clone());
+    let cache_hits = stats.clone();
+    ProgressStyle::with_template(&prettify_template(template, command))
+        .expect("header progress template is valid")
+        .with_key("elapsed_subsec", elapsed_subsec)
+        .with_key("in_progress", move |_: &ProgressState, w: &mut dyn Write| {
+            let _ = write!(w, "{}", in_progress.read().in_progress());
+        })
+        .with_key("completed", move |_: &ProgressState, w: &mut dyn Write| {
+            let _ = write!(w, "{}", completed.read().completed());
+        })
+        .with_key("failed", move |_: &ProgressState, w: &mut dyn Write| {
+            let _ = write!(w, "{}", failed.read().failed());
+        })
+        .with_key("cache_hits_percentage", move |_: &ProgressState, w: &mut dyn Write| {
+            let _ = write!(w, "{}", cache_hits_msg(cache_hits.read().cache_hits_percentage()));
+        })
+        .progress_chars("---")
+}
+
+/// Render the elapsed time of a task with sub-second precision, shifting from
+/// green to red as the task keeps running.
+pub fn elapsed_subsec(state: &ProgressState, writer: &mut dyn Write) {
+    let elapsed = state.elapsed();
+    let elapsed_secs = elapsed.as_secs_f64();
+    let msg = format!("{}.{}s", elapsed.as_secs(), (elapsed.as_millis() % 1000) / 100);
+    let [dark_green, green, yellow, orange, red] = gradient();
+
+    let _ = if elapsed > VERY_LONG_DURATION_START_TIME {
+        let t = (elapsed_secs - VERY_LONG_DURATION_START_TIME.as_secs_f64()) / 3.0;
+        write!(writer, "{}", msg.color(interpolate_color(&orange, &red, t)))
+    } else if elapsed > LONG_DURATION_START_TIME {
+        let t = (elapsed_secs - LONG_DURATION_START_TIME.as_secs_f64()) / 3.0;
+        write!(writer, "{}", msg.color(interpolate_color(&yellow, &orange, t)))
+    } else if elapsed > MEDIUM_DURATION_START_TIME {
+        let t = (elapsed_secs - MEDIUM_DURATION_START_TIME.as_secs_f64()) / 3.0;
+        write!(writer, "{}", msg.color(interpolate_color(&green, &yellow, t)).italic())
+    } else {
+        let t = elapsed_secs / 3.0;
+        write!(writer, "{}", msg.color(interpolate_color(&dark_green, &green, t)).italic())
+    };
+}
+
+/// Label a task according to how long it has been running for (e.g.
+/// `[Long]`).
+pub fn task_msg_display(state: &ProgressState, writer: &mut dyn Write) {
+    let elapsed = state.elapsed();
+
+    if elapsed > VERY_LONG_DURATION_START_TIME {
+        let _ = write!(
+            writer,
+            " {} ",
+            very_long_running_task_msg(elapsed - VERY_LONG_DURATION_START_TIME)
+        );
+    } else if elapsed > LONG_DURATION_START_TIME {
+        let _ = write!(writer, " {} ", long_running_task_msg(elapsed - LONG_DURATION_START_TIME));
+    } else if elapsed > MEDIUM_DURATION_START_TIME {
+        let _ =
+            write!(writer, " {} ", medium_running_task_msg(elapsed - MEDIUM_DURATION_START_TIME));
+    } else if elapsed > SHORT_DURATION_START_TIME {
+        let _ = write!(writer, " {} ", short_running_task_msg(elapsed - SHORT_DURATION_START_TIME));
+    }
+}
+
+fn color_end(state: &ProgressState, writer: &mut dyn Write) {
+    if state.elapsed() > MEDIUM_DURATION_START_TIME {
+        let _ = write!(writer, "\x1b[0m");
+    }
+}
+
+pub fn short_running_task_msg(duration: Duration) -> String {
+    let [dark_green, green, ..] = gradient();
+    let gradient = interpolate_color(&dark_green, &green, duration.as_secs_f64() / 3.0);
+    format!("{}{}{}", "[".black(), "Short".color(gradient).italic(), "]".black()).into()
+}
+
+pub fn medium_running_task_msg(duration: Duration) -> String {
+    let [_, green, yellow, ..] = gradient();
+    let gradient = interpolate_color(&green, &yellow, duration.as_secs_f64() / 3.0);
+    format!("{}{}{}", "[".black(), "Medium".color(gradient).italic(), "]".black()).into()
+}
+
+pub fn long_running_task_msg(duration: Duration) -> String {
+    let [_, _, yellow, orange, _] = gradient();
+    let gradient = interpolate_color(&yellow, &orange, duration.as_secs_f64() / 6.0);
+    format!("{}{}{}", "[".black(), "Long".color(gradient).italic(), "]".black()).into()
+}
+
+pub fn very_long_running_task_msg(duration: Duration) -> String {
+    let [.., orange, red] = gradient();
+    let gradient = interpolate_color(&orange, &red, duration.as_secs_f64() / 6.0);
+    format!("{}{}{}", "[".black(), "Very Long".color(gradient).italic(), "]".black()).into()
+}
+
+/// Render a cache hit `percentage` (from `0` to `100`), colored from red
+/// (no hits) to green (all hits).
+pub fn cache_hits_msg(percentage: f64) -> String {
+    let [_, green, .., red] = gradient();
+    let gradient = interpolate_color(&red, &green, percentage / 100.0);
+    format!("Cache Hits{} {:.2}", ":".black(), percentage.color(gradient).italic()).into()
+}
+
+/// Linearly interpolate between two colors, with `t` clamped to `0..=1`.
+pub fn interpolate_color(from: &Rgb, to: &Rgb, t: f64) -> Rgb {
+    let t = t.clamp(0.0, 1.0);
+    Rgb(interpolate(from.0, to.0, t), interpolate(from.1, to.1, t), interpolate(from.2, to.2, t))
+}
+
+fn interpolate(a: u8, b: u8, t: f64) -> u8 {
+    let result = a as f64 * (1.0 - t) + b as f64 * t;
+    result.round() as u8
+}
+
+/// Render the `command` a set of tasks is executed for, alongside its
+/// (shortened) ID.
+pub fn task_display(command: &str, id: &str) -> String {
+    format!(
+        "{}{}{} {}{}{}",
+        "`".red(),
+        command.green(),
+        "`".red(),
+        "[".black(),
+        id.chars().take(5).collect::<String>().cyan().italic(),
+        "]".black()
+    )
+    .into()
+}
+
+fn prettify_template(template: &str, task_display: &str) -> std::string::String {
+    template
+        .replace('.', &format!("{}", ".".black()))
+        .replace('%', &format!("{}", "%".black()))
+        .replace(':', &format!("{}", ":".black()))
+        .replace("In progress", &format!("{}", "In progress".bright_yellow()))
+        .replace("Finished", &format!("{}", "Finished".green()))
+        .replace("Failed", &format!("{}", "Failed".red()))
+        .replace("{task_display}", task_display)
+}
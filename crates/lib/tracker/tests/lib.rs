@@ -0,0 +1,66 @@
+#[cfg(test)]
+mod tracker_test_suite {
+    use owo_colors::Rgb;
+    use tracker::{
+        style::{
+            interpolate_color,
+            GREEN,
+            RED,
+        },
+        Mode,
+        TaskEvent,
+        Tracker,
+    };
+
+    #[test]
+    fn test_task_events() {
+        let tracker = Tracker::new("build //...");
+        tracker.handle(TaskEvent::Begin {
+            id:     "a".into(),
+            name:   "//foo:a".into(),
+            parent: None,
+        });
+        tracker.handle(TaskEvent::Begin {
+            id:     "a.1".into(),
+            name:   "rustc".into(),
+            parent: Some("a".into()),
+        });
+        tracker.handle(TaskEvent::CacheHit { id: "a.1".into() });
+        tracker.handle(TaskEvent::End { id: "a.1".into(), success: true });
+
+        let stats = tracker.stats();
+        assert_eq!((stats.in_progress(), stats.completed(), stats.cache_hits()), (1, 1, 1));
+
+        tracker.handle(TaskEvent::End { id: "a".into(), success: false });
+        tracker.handle(TaskEvent::End { id: "a".into(), success: false });
+
+        let stats = tracker.finish();
+        assert_eq!((stats.in_progress(), stats.completed(), stats.failed()), (0, 1, 1));
+        assert_eq!(stats.cache_hits_percentage(), 50.0);
+    }
+
+    #[test]
+    fn test_running_tasks() {
+        tracker::set_mode(Mode::Detached);
+        let tracker = Tracker::new("build //...");
+        tracker.begin("a", "//foo:a", None);
+        tracker.begin("a.1", "rustc", Some("a"));
+        tracker.begin("b", "//foo:b", None);
+        tracker.end("b", true);
+
+        let running = tracker.running();
+        let running = running.iter().map(|task| (task.name().as_str(), task.depth()));
+        assert_eq!(running.collect::<Vec<_>>(), vec![("//foo:a", 0), ("rustc", 1)]);
+
+        tracker.finish();
+        assert!(tracker.running().is_empty());
+        tracker::set_mode(Mode::default());
+    }
+
+    #[test]
+    fn test_interpolate_color() {
+        assert_eq!(interpolate_color(&RED, &GREEN, 0.0), RED);
+        assert_eq!(interpolate_color(&RED, &GREEN, 0.5), Rgb(128, 128, 0));
+        assert_eq!(interpolate_color(&RED, &GREEN, 42.0), GREEN);
+    }
+}
@@ -3,3 +3,11 @@
 pub mod ui;
 
 pub mod log;
+
+pub mod log_file;
+
+pub mod render;
+
+pub mod report;
+
+pub mod trace;
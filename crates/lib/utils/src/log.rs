@@ -1,7 +1,23 @@
-use cfg::settings::LogLevel;
-use diagnostics::errors::{
-    CliError,
-    LogError,
+use cfg::{
+    config::{
+        Config,
+        ThemeColors,
+        ThemeConfig,
+    },
+    settings::{
+        Color,
+        ErrorFormat,
+        LogLevel,
+        Renderer,
+        Topic,
+    },
+};
+use diagnostics::{
+    errors::{
+        CliError,
+        LogError,
+    },
+    explain,
 };
 use std::fmt::Write;
 use unicode_width::UnicodeWidthChar;
@@ -18,40 +34,296 @@ use miette::{
     SourceSpan,
     SpanContents,
 };
+use once_cell::sync::Lazy;
 use owo_colors::{
     OwoColorize,
     Style,
 };
 use std::{
+    collections::BTreeMap,
     fmt,
-    io::IsTerminal,
+    io::{
+        self,
+        IsTerminal,
+    },
+    path::Path,
+    sync::RwLock,
+    time::Duration,
 };
-use tracing::Level;
-use tracing_subscriber::FmtSubscriber;
-
-pub fn init(verbosity: &LogLevel) -> Result<()> {
-    // Initialize the logging subsystem
-    let subscriber = FmtSubscriber::builder()
-        .with_max_level(match verbosity {
-            LogLevel::Trace => Level::TRACE,
-            LogLevel::Debug => Level::DEBUG,
-            LogLevel::Info => Level::INFO,
-            LogLevel::Warn => Level::WARN,
-            LogLevel::Error => Level::ERROR,
-            LogLevel::Fatal => Level::ERROR,
-        })
-        .without_time()
-        .finish();
+use tracing::{
+    info_span,
+    level_filters::LevelFilter,
+    Level,
+    Span,
+};
+use tracing_indicatif::writer::IndicatifWriter;
+use tracing_subscriber::{
+    filter::filter_fn,
+    fmt::MakeWriter,
+    layer::SubscriberExt,
+    EnvFilter,
+    Layer,
+};
+use tracker::Mode;
+
+use crate::{
+    log_file::{
+        self,
+        LogFile,
+        Rotation,
+    },
+    render::{
+        strip_ansi,
+        JsonFormat,
+        Rendering,
+    },
+    report::{
+        self,
+        JsonReportHandler,
+        SarifReportHandler,
+    },
+    trace::ChromeTraceLayer,
+};
+
+/// How often the progress of tracked tasks is logged when it cannot be redrawn
+/// in place.
+const PROGRESS_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Initialize the logging and error reporting subsystems, rendering their
+/// output with the `renderer` requested (or detected, see [`Rendering`]).
+/// Only the logs of the requested `topics` are shown (see [`directives`]),
+/// while debug-level logs are additionally written to the [`LogFile`] of the
+/// session, regardless of the `verbosity`. When a `trace_file` is given, every
+/// span is written to it as well (see [`ChromeTraceLayer`]). Errors are
+/// reported in the given `error_format`, with the theme of the `config`.
+pub fn init(
+    verbosity: &LogLevel,
+    renderer: Renderer,
+    error_format: ErrorFormat,
+    topics: &[Topic],
+    config: &Config,
+    trace_file: Option<&Path>,
+) -> Result<Rendering> {
+    let rendering = Rendering::detect(renderer);
+
+    // Initialize the error reporting subsystem first, rendering any error
+    // initializing the logging subsystem
+    let handler = ByakuganReportHandler::new_themed(rendering.theme(config.theme()))
+        .with_links(rendering.is_live())
+        .with_ansi(rendering.color())
+        .with_config(config.theme());
+    miette::set_hook(Box::new(move |_| match error_format {
+        ErrorFormat::Human => Box::new(handler.clone()),
+        ErrorFormat::Json => Box::new(JsonReportHandler),
+        ErrorFormat::Sarif => Box::new(SarifReportHandler),
+    }))
+    .into_diagnostic()
+    .map_err(|e| LogError::LogSystemInitialization(e.to_string().into()))?;
+    report::set_format(error_format);
+
+    let trace = trace_file.map(ChromeTraceLayer::create).transpose()?;
+    let log_file = log_file::dir()
+        .ok_or_else(|| LogError::LogFileInitialization("no state directory found".into()))
+        .and_then(|dir| LogFile::open(dir, Rotation::default()));
+
+    // Initialize the logging subsystem, rendering the progress of tracked
+    // tasks below the log lines when it can be redrawn in place
+    let progress = rendering.is_live().then(tracker::layer);
+    let writer = LogWriter::new(
+        progress.as_ref().map(|progress| progress.get_stderr_writer()),
+        rendering.color(),
+    );
+    let fmt = match rendering.renderer() {
+        Renderer::Json => {
+            tracing_subscriber::fmt::layer().event_format(JsonFormat).with_writer(writer).boxed()
+        }
+        _ => tracing_subscriber::fmt::layer()
+            .without_time()
+            .with_ansi(rendering.color())
+            .with_writer(writer)
+            .boxed(),
+    };
+    let filter = EnvFilter::try_new(directives(*verbosity, topics, config.log().levels()))
+        .map_err(|e| LogError::LogSystemInitialization(e.to_string().into()))?;
+    let file = log_file.as_ref().ok().map(|file| {
+        tracing_subscriber::fmt::layer()
+            .with_ansi(false)
+            .with_writer(file.clone())
+            .with_filter(LevelFilter::DEBUG)
+    });
+    let progress =
+        progress.map(|progress| progress.with_filter(filter_fn(tracker::is_progress_span)));
+    let trace = trace.map(|trace| trace.with_filter(filter_fn(|metadata| metadata.is_span())));
+    let subscriber = tracing_subscriber::registry()
+        .with(fmt.with_filter(filter))
+        .with(file)
+        .with(progress)
+        .with(trace);
     tracing::subscriber::set_global_default(subscriber)
         .into_diagnostic()
         .map_err(|e| LogError::LogSystemInitialization(e.to_string().into()))?;
+    if let Some(gradient) = config.theme().colors().gradient() {
+        tracker::style::set_gradient(gradient.map(Color::rgb));
+    }
+    tracker::set_mode(if rendering.is_live() {
+        Mode::Live
+    } else {
+        Mode::Periodic(PROGRESS_INTERVAL)
+    });
+
+    match log_file {
+        Ok(file) => tracing::debug!("Writing logs to {}", file.path().display()),
+        Err(e) => tracing::warn!("{:?}", miette::Report::new(e)),
+    }
 
-    // Initialize the error reporting subsystem
-    miette::set_hook(Box::new(move |_| Box::new(ByakuganReportHandler::new())))
-        .into_diagnostic()
-        .map_err(|e| LogError::LogSystemInitialization(e.to_string().into()))?;
+    Ok(rendering)
+}
+
+/// The `tracing` filter **directives** of the logs shown: those of the
+/// requested `topics` (or of every topic, when none are requested) at the
+/// level configured for their topic or the `verbosity` otherwise, and only
+/// warnings and errors of anything else.
+///
+/// ```rust
+/// use cfg::settings::{
+///     LogLevel,
+///     Topic,
+/// };
+/// use std::collections::BTreeMap;
+///
+/// let levels = BTreeMap::from([(Topic::Query, LogLevel::Trace)]);
+/// let directives = utils::log::directives(LogLevel::Info, &[Topic::Watch, Topic::Build], &levels);
+/// assert_eq!(directives, "warn,[watch]=info,[build]=info");
+/// ```
+pub fn directives(
+    verbosity: LogLevel,
+    topics: &[Topic],
+    levels: &BTreeMap<Topic, LogLevel>,
+) -> String {
+    let name = |level: LogLevel| level_of(level).to_string().to_lowercase();
+    let default = if topics.is_empty() { verbosity } else { verbosity.max(LogLevel::Warn) };
+
+    let mut directives = vec![name(default)];
+    if topics.is_empty() {
+        directives
+            .extend(levels.iter().map(|(topic, level)| format!("[{topic}]={}", name(*level))));
+    }
+    for topic in topics {
+        let level = levels.get(topic).copied().unwrap_or(verbosity);
+        directives.push(format!("[{topic}]={}", name(level)));
+    }
+
+    directives.join(",")
+}
+
+/// The `tracing` level of a [`LogLevel`].
+fn level_of(level: LogLevel) -> Level {
+    match level {
+        LogLevel::Trace => Level::TRACE,
+        LogLevel::Debug => Level::DEBUG,
+        LogLevel::Info => Level::INFO,
+        LogLevel::Warn => Level::WARN,
+        LogLevel::Error => Level::ERROR,
+        LogLevel::Fatal => Level::ERROR,
+    }
+}
+
+/// A **span** attributing the logs emitted within it to a [`Topic`], e.g.
+/// `let _topic = utils::log::topic(Topic::Build).entered();`.
+pub fn topic(topic: Topic) -> Span {
+    match topic {
+        Topic::Watch => info_span!("watch"),
+        Topic::Debounce => info_span!("debounce"),
+        Topic::Query => info_span!("query"),
+        Topic::Build => info_span!("build"),
+        Topic::Test => info_span!("test"),
+        Topic::Run => info_span!("run"),
+        Topic::Cache => info_span!("cache"),
+        Topic::Config => info_span!("config"),
+    }
+}
+
+/// A **redirect** receiving every log line (e.g. to render it within a TUI)
+/// instead of the terminal.
+type Redirect = Box<dyn Fn(&str) + Send + Sync>;
+
+static REDIRECT: Lazy<RwLock<Option<Redirect>>> = Lazy::new(|| RwLock::new(None));
+
+/// Redirect every log line to `redirect` until the returned guard is dropped.
+pub fn redirect(redirect: impl Fn(&str) + Send + Sync + 'static) -> RedirectGuard {
+    *REDIRECT.write().unwrap_or_else(|e| e.into_inner()) = Some(Box::new(redirect));
+    RedirectGuard
+}
+
+/// Guard restoring logging to the terminal when dropped.
+#[derive(Debug)]
+pub struct RedirectGuard;
+
+impl Drop for RedirectGuard {
+    fn drop(&mut self) {
+        *REDIRECT.write().unwrap_or_else(|e| e.into_inner()) = None;
+    }
+}
+
+/// Writer of the logging subsystem, honouring the current [`redirect`] and
+/// otherwise writing to the terminal without interfering with the progress
+/// bars of tracked tasks (if any), or to `stderr`.
+#[derive(Clone)]
+pub struct LogWriter {
+    terminal: Option<IndicatifWriter>,
+    /// Whether ANSI colors are kept (e.g. those of messages colored by their
+    /// emitter), rather than stripped.
+    color:    bool,
+}
+
+impl LogWriter {
+    pub fn new(terminal: Option<IndicatifWriter>, color: bool) -> Self {
+        Self { terminal, color }
+    }
+}
+
+impl<'a> MakeWriter<'a> for LogWriter {
+    type Writer = LogEvent;
+
+    fn make_writer(&'a self) -> Self::Writer {
+        LogEvent { buf: Vec::new(), terminal: self.terminal.clone(), color: self.color }
+    }
+}
 
-    Ok(())
+/// A single formatted log event, written out once complete.
+pub struct LogEvent {
+    buf:      Vec<u8>,
+    terminal: Option<IndicatifWriter>,
+    color:    bool,
+}
+
+impl io::Write for LogEvent {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.buf.extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl Drop for LogEvent {
+    fn drop(&mut self) {
+        if !self.color {
+            self.buf = strip_ansi(&String::from_utf8_lossy(&self.buf)).into_bytes();
+        }
+
+        match (REDIRECT.read().unwrap_or_else(|e| e.into_inner()).as_ref(), &mut self.terminal) {
+            (Some(redirect), _) => String::from_utf8_lossy(&self.buf).lines().for_each(redirect),
+            (None, Some(terminal)) => {
+                let _ = io::Write::write_all(terminal, &self.buf);
+            }
+            (None, None) => {
+                let _ = io::Write::write_all(&mut io::stderr(), &self.buf);
+            }
+        }
+    }
 }
 
 // Most of the below code is taken verbatim from the `miette` crate to work in
@@ -67,6 +339,7 @@ pub struct ByakuganReportHandler {
     pub(crate) context_lines:    usize,
     pub(crate) tab_width:        usize,
     pub(crate) with_cause_chain: bool,
+    pub(crate) ansi:             bool,
 }
 
 impl ByakuganReportHandler {
@@ -81,6 +354,7 @@ impl ByakuganReportHandler {
             context_lines:    1,
             tab_width:        4,
             with_cause_chain: true,
+            ansi:             true,
         }
     }
 
@@ -94,6 +368,7 @@ impl ByakuganReportHandler {
             context_lines: 1,
             tab_width: 4,
             with_cause_chain: true,
+            ansi: true,
         }
     }
 
@@ -128,17 +403,29 @@ impl ByakuganReportHandler {
 
         write!(f, "{error} ")?;
 
-        let url = "https://docs.rs/byakugan";
+        let url = diagnostic
+            .url()
+            .map_or_else(|| "https://docs.rs/byakugan".to_string(), |url| url.to_string());
 
         let code =
             if let Some(code) = diagnostic.code() { format!("{code} ") } else { "".to_string() };
 
-        let link = format!(
-            "\u{1b}]8;;{}\u{1b}\\{}{}\u{1b}]8;;\u{1b}\\",
-            url,
-            code.style(severity_style),
-            "(link)".style(self.theme.styles.link)
-        );
+        let link = match self.links {
+            LinkStyle::Link => format!(
+                "\u{1b}]8;;{}\u{1b}\\{}{}\u{1b}]8;;\u{1b}\\",
+                url,
+                code.style(severity_style),
+                "(link)".style(self.theme.styles.link)
+            ),
+            LinkStyle::Text => {
+                format!(
+                    "{}{}",
+                    code.style(severity_style),
+                    format!("({url})").style(self.theme.styles.link)
+                )
+            }
+            LinkStyle::None => code.trim_end().style(severity_style).to_string(),
+        };
         write!(header, "{link}")?;
         writeln!(f, "{header}")?;
         Ok(())
@@ -206,6 +493,16 @@ impl ByakuganReportHandler {
                 .subsequent_indent("        ");
             writeln!(f, "{}", textwrap::fill(&help.to_string(), opts))?;
         }
+        if let Some(explanation) =
+            diagnostic.code().and_then(|code| explain::lookup(&code.to_string()))
+        {
+            writeln!(
+                f,
+                "  {} for more information about this error, try `{}`",
+                "note:".style(self.theme.styles.help),
+                format!("bkg explain {}", explanation.code).style(self.theme.styles.link)
+            )?;
+        }
         Ok(())
     }
 
@@ -741,6 +1038,13 @@ impl ByakuganReportHandler {
         self
     }
 
+    /// Whether to keep ANSI escape sequences (e.g. those of colored error
+    /// messages) in rendered reports, rather than strip them.
+    pub fn with_ansi(mut self, ansi: bool) -> Self {
+        self.ansi = ansi;
+        self
+    }
+
     /// Include the cause chain of the top-level error in the graphical output,
     /// if available.
     pub fn with_cause_chain(mut self) -> Self {
@@ -791,6 +1095,25 @@ impl ByakuganReportHandler {
         self.context_lines = lines;
         self
     }
+
+    /// Apply the settings of the theme configured by the user (i.e. its link
+    /// style, context lines and width), where set.
+    pub fn with_config(mut self, config: &ThemeConfig) -> Self {
+        if let Some(links) = config.links() {
+            self.links = match links {
+                cfg::settings::LinkStyle::Link => LinkStyle::Link,
+                cfg::settings::LinkStyle::Text => LinkStyle::Text,
+                cfg::settings::LinkStyle::None => LinkStyle::None,
+            };
+        }
+        if let Some(lines) = config.context_lines() {
+            self.context_lines = lines;
+        }
+        if let Some(width) = config.width() {
+            self.termwidth = width;
+        }
+        self
+    }
 }
 
 impl Default for ByakuganReportHandler {
@@ -805,10 +1128,24 @@ impl ReportHandler for ByakuganReportHandler {
             return fmt::Debug::fmt(diagnostic, f);
         }
 
+        if !self.ansi {
+            let report = Rendered(self, diagnostic).to_string();
+            return f.write_str(&strip_ansi(&report));
+        }
+
         self.render_report(f, diagnostic)
     }
 }
 
+/// A [`Diagnostic`] rendered by a [`ByakuganReportHandler`].
+struct Rendered<'a>(&'a ByakuganReportHandler, &'a dyn Diagnostic);
+
+impl fmt::Display for Rendered<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.render_report(f, self.1)
+    }
+}
+
 /**
 Theme used by [`GraphicalReportHandler`](crate::GraphicalReportHandler) to
 render fancy [`Diagnostic`](crate::Diagnostic) reports.
@@ -936,6 +1273,25 @@ impl ThemeStyles {
         }
     }
 
+    /// Paint with the colors of the theme configured by the user, where set.
+    pub fn with_colors(mut self, colors: &ThemeColors) -> Self {
+        let paint = |style: Style, color: Option<Color>| {
+            color.map_or(style, |color| style.color(color.dyn_color()))
+        };
+
+        self.error = paint(self.error, colors.error());
+        self.warning = paint(self.warning, colors.warning());
+        self.advice = paint(self.advice, colors.advice());
+        self.help = paint(self.help, colors.help());
+        self.link = paint(self.link, colors.link());
+        self.linum = paint(self.linum, colors.linum());
+        if let Some(highlights) = colors.highlights().as_ref().filter(|h| !h.is_empty()) {
+            let style = self.highlights.first().copied().unwrap_or_else(style);
+            self.highlights = highlights.iter().map(|color| paint(style, Some(*color))).collect();
+        }
+        self
+    }
+
     /// No styling. Just regular ol' monochrome.
     pub fn none() -> Self {
         Self {
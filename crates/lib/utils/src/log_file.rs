@@ -0,0 +1,217 @@
+use chrono::Local;
+use diagnostics::errors::LogError;
+use getset::CopyGetters;
+use std::{
+    fs::{
+        self,
+        File,
+        OpenOptions,
+    },
+    io::{
+        self,
+        Write,
+    },
+    path::{
+        Path,
+        PathBuf,
+    },
+    sync::{
+        Arc,
+        Mutex,
+    },
+    time::{
+        Duration,
+        Instant,
+    },
+};
+use tracing_subscriber::fmt::MakeWriter;
+use typed_builder::TypedBuilder;
+
+use crate::render::strip_ansi;
+
+/// The size a log file is rotated at.
+pub const MAX_SIZE: u64 = 10 * 1024 * 1024;
+
+/// The age a log file is rotated at, for long-running sessions (e.g. `bkg
+/// watch`).
+pub const MAX_AGE: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// How many log files are kept, across sessions.
+pub const RETENTION: usize = 20;
+
+/// The directory log files are written to, i.e. `byakugan/logs` within the
+/// state directory of the user (i.e. `$XDG_STATE_HOME`), or within their local
+/// data directory when unset.
+pub fn dir() -> Option<PathBuf> {
+    std::env::var_os("XDG_STATE_HOME")
+        .map(PathBuf::from)
+        .filter(|dir| dir.is_absolute())
+        .or_else(dirs_next::data_local_dir)
+        .map(|dir| dir.join("byakugan").join("logs"))
+}
+
+/// When log files are **rotated**, and how many are retained.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, TypedBuilder, CopyGetters)]
+#[getset(get_copy = "pub")]
+pub struct Rotation {
+    /// Rotate once the current file would grow beyond this size (in bytes).
+    #[builder(default = MAX_SIZE)]
+    max_size:  u64,
+    /// Rotate once the current file has been written to for this long.
+    #[builder(default = MAX_AGE)]
+    max_age:   Duration,
+    /// Remove the oldest log files beyond this many.
+    #[builder(default = RETENTION)]
+    retention: usize,
+}
+
+impl Default for Rotation {
+    fn default() -> Self {
+        Self::builder().build()
+    }
+}
+
+/// The **log file** of the current session (e.g.
+/// `byakugan-20231018T173717-4242.log`), rotated to a new file of the session
+/// (e.g. `byakugan-20231018T173717-4242.1.log`) according to its [`Rotation`].
+#[derive(Debug, Clone)]
+pub struct LogFile {
+    inner: Arc<Mutex<Inner>>,
+}
+
+#[derive(Debug)]
+struct Inner {
+    dir:      PathBuf,
+    session:  String,
+    rotation: Rotation,
+    index:    usize,
+    file:     File,
+    path:     PathBuf,
+    size:     u64,
+    opened:   Instant,
+}
+
+impl LogFile {
+    /// Open the log file of a new session within `dir`, removing the oldest
+    /// log files beyond the retention limit.
+    pub fn open(dir: impl Into<PathBuf>, rotation: Rotation) -> Result<Self, LogError> {
+        let dir = dir.into();
+        fs::create_dir_all(&dir).map_err(|e| {
+            LogError::LogFileInitialization(format!("{}: {e}", dir.display()).into())
+        })?;
+
+        let session =
+            format!("byakugan-{}-{}", Local::now().format("%Y%m%dT%H%M%S"), std::process::id());
+        let (file, path) = create(&dir, &session, 0)?;
+        let inner =
+            Inner { dir, session, rotation, index: 0, file, path, size: 0, opened: Instant::now() };
+        inner.prune();
+
+        Ok(Self { inner: Arc::new(Mutex::new(inner)) })
+    }
+
+    /// The path of the file currently written to.
+    pub fn path(&self) -> PathBuf {
+        self.inner.lock().unwrap_or_else(|e| e.into_inner()).path.clone()
+    }
+}
+
+impl Inner {
+    fn write(&mut self, buf: &[u8]) -> io::Result<()> {
+        let too_large = self.size + buf.len() as u64 > self.rotation.max_size;
+        if self.size > 0 && (too_large || self.opened.elapsed() >= self.rotation.max_age) {
+            self.rotate().map_err(|e| io::Error::new(io::ErrorKind::Other, e))?;
+        }
+
+        self.file.write_all(buf)?;
+        self.size += buf.len() as u64;
+        Ok(())
+    }
+
+    fn rotate(&mut self) -> Result<(), LogError> {
+        let (file, path) = create(&self.dir, &self.session, self.index + 1)?;
+        self.index += 1;
+        self.file = file;
+        self.path = path;
+        self.size = 0;
+        self.opened = Instant::now();
+        self.prune();
+        Ok(())
+    }
+
+    /// Remove the least recently written log files beyond the retention limit
+    /// (never the current one).
+    fn prune(&self) {
+        let Ok(entries) = fs::read_dir(&self.dir) else {
+            return;
+        };
+
+        let mut logs = entries
+            .flatten()
+            .filter(|entry| {
+                let name = entry.file_name();
+                let name = name.to_string_lossy();
+                name.starts_with("byakugan-") && name.ends_with(".log")
+            })
+            .filter(|entry| entry.path() != self.path)
+            .filter_map(|entry| Some((entry.metadata().ok()?.modified().ok()?, entry.path())))
+            .collect::<Vec<_>>();
+        logs.sort();
+
+        // Retaining the current file as well
+        let excess = logs.len().saturating_sub(self.rotation.retention.saturating_sub(1));
+        // Not logged on failure, as the file is locked while writing an event
+        for (_, path) in logs.into_iter().take(excess) {
+            let _ = fs::remove_file(path);
+        }
+    }
+}
+
+/// Create the `index`th log file of a session.
+fn create(dir: &Path, session: &str, index: usize) -> Result<(File, PathBuf), LogError> {
+    let path = match index {
+        0 => dir.join(format!("{session}.log")),
+        index => dir.join(format!("{session}.{index}.log")),
+    };
+    let file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&path)
+        .map_err(|e| LogError::LogFileOpen(format!("{}: {e}", path.display()).into()))?;
+
+    Ok((file, path))
+}
+
+impl<'a> MakeWriter<'a> for LogFile {
+    type Writer = LogFileEvent;
+
+    fn make_writer(&'a self) -> Self::Writer {
+        LogFileEvent { buf: Vec::new(), inner: self.inner.clone() }
+    }
+}
+
+/// A single formatted log event, written out (without ANSI escape sequences)
+/// once complete so that it is never split across rotated files.
+pub struct LogFileEvent {
+    buf:   Vec<u8>,
+    inner: Arc<Mutex<Inner>>,
+}
+
+impl io::Write for LogFileEvent {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.buf.extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl Drop for LogFileEvent {
+    fn drop(&mut self) {
+        let event = strip_ansi(&String::from_utf8_lossy(&self.buf));
+        // Failing to write the log file must not interrupt the session
+        let _ = self.inner.lock().unwrap_or_else(|e| e.into_inner()).write(event.as_bytes());
+    }
+}
@@ -0,0 +1,225 @@
+use cfg::{
+    config::ThemeConfig,
+    settings::{
+        Charset,
+        Renderer,
+        ThemePreset,
+    },
+};
+use getset::CopyGetters;
+use serde_json::{
+    Map,
+    Value,
+};
+use std::{
+    fmt,
+    io::{
+        self,
+        IsTerminal,
+    },
+};
+use tracing::{
+    field::{
+        Field,
+        Visit,
+    },
+    Event,
+    Subscriber,
+};
+use tracing_subscriber::{
+    fmt::{
+        format::Writer,
+        FmtContext,
+        FormatEvent,
+        FormatFields,
+    },
+    registry::LookupSpan,
+};
+
+use crate::log::{
+    GraphicalTheme,
+    ThemeCharacters,
+    ThemeStyles,
+};
+
+/// The **rendering** of the output of byakugan, as resolved from the requested
+/// [`Renderer`] and the environment it runs in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, CopyGetters)]
+#[getset(get_copy = "pub")]
+pub struct Rendering {
+    /// The renderer in use (never [`Renderer::Auto`]).
+    renderer: Renderer,
+    /// Whether ANSI colors are emitted.
+    color:    bool,
+}
+
+impl Rendering {
+    /// Resolve the `requested` renderer against the terminal attached to
+    /// `stderr` and the environment.
+    pub fn detect(requested: Renderer) -> Self {
+        Self::detect_with(requested, io::stderr().is_terminal(), |var| std::env::var(var).ok())
+    }
+
+    /// Resolve the `requested` renderer given whether output goes to a
+    /// `terminal` and the `env`ironment variables:
+    ///
+    /// - [`Renderer::Auto`] falls back to [`Renderer::Plain`] outside of a
+    ///   terminal, when `CI` is set or when `TERM=dumb`, and is
+    ///   [`Renderer::Tty`] otherwise.
+    /// - Colors are disabled by a non-empty `NO_COLOR`, by `TERM=dumb` and for
+    ///   [`Renderer::Json`], and otherwise enabled for [`Renderer::Tty`] and
+    ///   for [`Renderer::Plain`] within a terminal.
+    pub fn detect_with(
+        requested: Renderer,
+        terminal: bool,
+        env: impl Fn(&str) -> Option<String>,
+    ) -> Self {
+        let set = |var: &str| env(var).filter(|value| !value.is_empty());
+        let dumb = env("TERM").as_deref() == Some("dumb");
+        let ci = set("CI").filter(|value| value != "false" && value != "0").is_some();
+
+        let renderer = match requested {
+            Renderer::Auto if !terminal || ci || dumb => Renderer::Plain,
+            Renderer::Auto => Renderer::Tty,
+            renderer => renderer,
+        };
+        let color = match renderer {
+            _ if set("NO_COLOR").is_some() || dumb => false,
+            Renderer::Json => false,
+            Renderer::Tty => true,
+            _ => terminal,
+        };
+
+        Self { renderer, color }
+    }
+
+    /// Whether progress is redrawn in place, rather than logged periodically.
+    pub fn is_live(&self) -> bool {
+        self.renderer.is_tty()
+    }
+
+    /// The theme diagnostics are rendered with, i.e. that of the `config`ured
+    /// preset and colors: unicode characters are only drawn by the TTY
+    /// renderer unless requested otherwise, colors only when enabled.
+    pub fn theme(&self, config: &ThemeConfig) -> GraphicalTheme {
+        let characters = match (config.characters(), config.preset()) {
+            (Some(Charset::Unicode), _) => ThemeCharacters::unicode(),
+            (Some(Charset::Emoji), _) => ThemeCharacters::emoji(),
+            (Some(Charset::Ascii), _) => ThemeCharacters::ascii(),
+            (None, ThemePreset::Auto) if self.is_live() => ThemeCharacters::unicode(),
+            (None, ThemePreset::Unicode | ThemePreset::Rgb) => ThemeCharacters::unicode(),
+            (None, ThemePreset::Emoji) => ThemeCharacters::emoji(),
+            (None, _) => ThemeCharacters::ascii(),
+        };
+        let styles = match config.preset() {
+            _ if !self.color => return GraphicalTheme { characters, styles: ThemeStyles::none() },
+            ThemePreset::None => ThemeStyles::none(),
+            ThemePreset::Rgb => ThemeStyles::rgb(),
+            _ => ThemeStyles::ansi(),
+        };
+
+        GraphicalTheme { characters, styles: styles.with_colors(config.colors()) }
+    }
+}
+
+/// Formats every log event as a single line of **JSON**, e.g.
+/// `{"timestamp":"...","level":"INFO","target":"driver","message":"..."}`.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct JsonFormat;
+
+impl<S, N> FormatEvent<S, N> for JsonFormat
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+    N: for<'a> FormatFields<'a> + 'static,
+{
+    fn format_event(
+        &self,
+        ctx: &FmtContext<'_, S, N>,
+        mut writer: Writer<'_>,
+        event: &Event<'_>,
+    ) -> fmt::Result {
+        let metadata = event.metadata();
+        let mut object = Map::new();
+        object.insert("timestamp".into(), chrono::Utc::now().to_rfc3339().into());
+        object.insert("level".into(), metadata.level().to_string().into());
+        object.insert("target".into(), metadata.target().into());
+
+        let mut fields = JsonFields::default();
+        event.record(&mut fields);
+        object.extend(fields.0);
+
+        let spans = ctx
+            .event_scope()
+            .into_iter()
+            .flat_map(|scope| scope.from_root())
+            .map(|span| Value::from(span.name()))
+            .collect::<Vec<_>>();
+        if !spans.is_empty() {
+            object.insert("spans".into(), spans.into());
+        }
+
+        writeln!(writer, "{}", Value::Object(object))
+    }
+}
+
+/// The fields of a log event (or span), with any ANSI escape sequences
+/// stripped.
+#[derive(Debug, Default)]
+pub(crate) struct JsonFields(pub(crate) Map<String, Value>);
+
+impl Visit for JsonFields {
+    fn record_i64(&mut self, field: &Field, value: i64) {
+        self.0.insert(field.name().into(), value.into());
+    }
+
+    fn record_u64(&mut self, field: &Field, value: u64) {
+        self.0.insert(field.name().into(), value.into());
+    }
+
+    fn record_bool(&mut self, field: &Field, value: bool) {
+        self.0.insert(field.name().into(), value.into());
+    }
+
+    fn record_str(&mut self, field: &Field, value: &str) {
+        self.0.insert(field.name().into(), strip_ansi(value).into());
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+        self.0.insert(field.name().into(), strip_ansi(&format!("{value:?}")).into());
+    }
+}
+
+/// Strip ANSI escape sequences (e.g. colors emitted by the build tool or the
+/// logging subsystem) from a line, as well as any carriage returns.
+pub fn strip_ansi(line: &str) -> String {
+    let mut stripped = String::with_capacity(line.len());
+    let mut chars = line.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '\u{1b}' => match chars.next() {
+                // CSI sequence, terminated by a byte in `@`..=`~`
+                Some('[') => {
+                    for c in chars.by_ref() {
+                        if ('@'..='~').contains(&c) {
+                            break;
+                        }
+                    }
+                }
+                // OSC sequence (e.g. hyperlinks), terminated by BEL or ST
+                Some(']') => {
+                    while let Some(c) = chars.next() {
+                        if c == '\u{7}' || (c == '\u{1b}' && chars.next_if_eq(&'\\').is_some()) {
+                            break;
+                        }
+                    }
+                }
+                _ => {}
+            },
+            '\r' => {}
+            c => stripped.push(c),
+        }
+    }
+
+    stripped
+}
@@ -0,0 +1,243 @@
+use cfg::settings::{
+    ErrorFormat,
+    EXE_NAME,
+    EXE_VERSION,
+};
+use miette::{
+    Diagnostic,
+    LabeledSpan,
+    ReportHandler,
+    Severity,
+};
+use once_cell::sync::OnceCell;
+use serde_json::{
+    json,
+    Map,
+    Value,
+};
+use std::fmt;
+
+use crate::render::strip_ansi;
+
+/// The schema of the SARIF logs reported by the [`SarifReportHandler`].
+pub const SARIF_SCHEMA: &str = "https://json.schemastore.org/sarif-2.1.0.json";
+
+/// The format of the reports of the session, as set by [`set_format`].
+static FORMAT: OnceCell<ErrorFormat> = OnceCell::new();
+
+/// Set the format reports are rendered in (once, when the error reporting
+/// subsystem is initialized).
+pub fn set_format(format: ErrorFormat) {
+    let _ = FORMAT.set(format);
+}
+
+/// The format reports are rendered in.
+pub fn format() -> ErrorFormat {
+    FORMAT.get().copied().unwrap_or_default()
+}
+
+/// Renders every report as a single line of **JSON** (see [`json`]).
+#[derive(Debug, Clone, Copy, Default)]
+pub struct JsonReportHandler;
+
+impl ReportHandler for JsonReportHandler {
+    fn debug(&self, diagnostic: &dyn Diagnostic, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if f.alternate() {
+            return fmt::Debug::fmt(diagnostic, f);
+        }
+
+        write!(f, "{}", json(diagnostic))
+    }
+}
+
+/// Renders every report as a **SARIF 2.1.0** log on a single line (see
+/// [`sarif`]).
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SarifReportHandler;
+
+impl ReportHandler for SarifReportHandler {
+    fn debug(&self, diagnostic: &dyn Diagnostic, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if f.alternate() {
+            return fmt::Debug::fmt(diagnostic, f);
+        }
+
+        write!(f, "{}", sarif(&[diagnostic]))
+    }
+}
+
+/// A [`Diagnostic`] as **JSON**: its code, severity, message, labels (with
+/// their location), help, URL, causes and related diagnostics.
+///
+/// ```rust
+/// use diagnostics::errors::ToolchainError;
+///
+/// let json = utils::report::json(&ToolchainError::Buck2NotFound);
+/// assert_eq!(json["code"], "bkg::toolchain::buck2_not_found");
+/// assert_eq!(json["severity"], "error");
+/// assert_eq!(json["message"], "Toolchain Error - buck2 not found");
+/// ```
+pub fn json(diagnostic: &dyn Diagnostic) -> Value {
+    let mut causes = Vec::new();
+    let mut cause = diagnostic.source();
+    while let Some(error) = cause {
+        causes.push(strip_ansi(&error.to_string()));
+        cause = error.source();
+    }
+
+    json!({
+        "code": diagnostic.code().map(|code| code.to_string()),
+        "severity": severity(diagnostic),
+        "message": strip_ansi(&diagnostic.to_string()),
+        "labels": labels(diagnostic).iter().map(Label::json).collect::<Vec<_>>(),
+        "help": diagnostic.help().map(|help| strip_ansi(&help.to_string())),
+        "url": diagnostic.url().map(|url| url.to_string()),
+        "causes": causes,
+        "related": diagnostic
+            .related()
+            .into_iter()
+            .flatten()
+            .map(json)
+            .collect::<Vec<_>>(),
+    })
+}
+
+/// [`Diagnostic`]s (and their related diagnostics) as the results of a
+/// **SARIF 2.1.0** log, with a rule per diagnostic code.
+pub fn sarif(diagnostics: &[&dyn Diagnostic]) -> Value {
+    let mut rules = Map::new();
+    let mut results = Vec::new();
+    for diagnostic in diagnostics {
+        sarif_results(*diagnostic, &mut rules, &mut results);
+    }
+
+    json!({
+        "$schema": SARIF_SCHEMA,
+        "version": "2.1.0",
+        "runs": [{
+            "tool": {
+                "driver": {
+                    "name": EXE_NAME,
+                    "version": EXE_VERSION,
+                    "rules": rules.into_iter().map(|(_, rule)| rule).collect::<Vec<_>>(),
+                },
+            },
+            "results": results,
+        }],
+    })
+}
+
+fn sarif_results(
+    diagnostic: &dyn Diagnostic,
+    rules: &mut Map<String, Value>,
+    results: &mut Vec<Value>,
+) {
+    let code = diagnostic.code().map(|code| code.to_string());
+    if let Some(code) = &code {
+        rules.entry(code.clone()).or_insert_with(|| {
+            without_nulls(json!({
+                "id": code,
+                "helpUri": diagnostic.url().map(|url| url.to_string()),
+                "help": diagnostic.help().map(|help| json!({ "text": strip_ansi(&help.to_string()) })),
+            }))
+        });
+    }
+
+    let level = match severity(diagnostic) {
+        "advice" => "note",
+        severity => severity,
+    };
+    let locations = labels(diagnostic).iter().map(Label::sarif).collect::<Vec<_>>();
+    results.push(without_nulls(json!({
+        "ruleId": code,
+        "level": level,
+        "message": { "text": strip_ansi(&diagnostic.to_string()) },
+        "locations": locations,
+    })));
+
+    for related in diagnostic.related().into_iter().flatten() {
+        sarif_results(related, rules, results);
+    }
+}
+
+/// Remove the `null` members of an object, which SARIF does not allow.
+fn without_nulls(mut value: Value) -> Value {
+    if let Value::Object(object) = &mut value {
+        object.retain(|_, value| !value.is_null());
+    }
+    value
+}
+
+/// The severity of a [`Diagnostic`] (an error, unless specified otherwise).
+fn severity(diagnostic: &dyn Diagnostic) -> &'static str {
+    match diagnostic.severity().unwrap_or(Severity::Error) {
+        Severity::Error => "error",
+        Severity::Warning => "warning",
+        Severity::Advice => "advice",
+    }
+}
+
+/// A label of a [`Diagnostic`], located within its source code.
+struct Label {
+    text:   Option<String>,
+    /// The name of the source code (e.g. the path of a file), if any.
+    source: Option<String>,
+    offset: usize,
+    length: usize,
+    /// The 1-indexed line and column of the label, if its source code could be
+    /// read.
+    line:   Option<usize>,
+    column: Option<usize>,
+}
+
+fn labels(diagnostic: &dyn Diagnostic) -> Vec<Label> {
+    let source_code = diagnostic.source_code();
+
+    diagnostic
+        .labels()
+        .into_iter()
+        .flatten()
+        .map(|label: LabeledSpan| {
+            let contents =
+                source_code.and_then(|source_code| source_code.read_span(label.inner(), 0, 0).ok());
+            Label {
+                text:   label.label().map(str::to_string),
+                source: contents.as_ref().and_then(|contents| contents.name().map(str::to_string)),
+                offset: label.offset(),
+                length: label.len(),
+                line:   contents.as_ref().map(|contents| contents.line() + 1),
+                column: contents.as_ref().map(|contents| contents.column() + 1),
+            }
+        })
+        .collect()
+}
+
+impl Label {
+    fn json(&self) -> Value {
+        json!({
+            "label": self.text,
+            "source": self.source,
+            "offset": self.offset,
+            "length": self.length,
+            "line": self.line,
+            "column": self.column,
+        })
+    }
+
+    fn sarif(&self) -> Value {
+        let mut region = json!({ "charOffset": self.offset, "charLength": self.length });
+        if let (Some(line), Some(column)) = (self.line, self.column) {
+            region["startLine"] = line.into();
+            region["startColumn"] = column.into();
+        }
+
+        let mut location = json!({ "physicalLocation": { "region": region } });
+        if let Some(source) = &self.source {
+            location["physicalLocation"]["artifactLocation"] = json!({ "uri": source });
+        }
+        if let Some(text) = &self.text {
+            location["message"] = json!({ "text": text });
+        }
+
+        location
+    }
+}
@@ -0,0 +1,170 @@
+use diagnostics::errors::LogError;
+use serde_json::{
+    json,
+    Value,
+};
+use std::{
+    collections::BTreeSet,
+    fs::File,
+    io::{
+        self,
+        BufWriter,
+        Write,
+    },
+    path::Path,
+    sync::{
+        atomic::{
+            AtomicU64,
+            Ordering,
+        },
+        Mutex,
+    },
+    thread,
+    time::Instant,
+};
+use tracing::{
+    span::{
+        Attributes,
+        Id,
+        Record,
+    },
+    Subscriber,
+};
+use tracing_subscriber::{
+    layer::Context,
+    registry::LookupSpan,
+    Layer,
+};
+
+use crate::render::JsonFields;
+
+/// The identifier of the next thread to record a span.
+static NEXT_THREAD: AtomicU64 = AtomicU64::new(1);
+
+thread_local! {
+    /// The identifier of the current thread within the trace.
+    static THREAD: u64 = NEXT_THREAD.fetch_add(1, Ordering::Relaxed);
+}
+
+/// A `tracing` **layer** writing every span to a file as a [Chrome trace
+/// event](https://docs.google.com/document/d/1CvAClvFfyA5R-PhYUmn5OOQtYMH4h6I0nSsKchNAySU),
+/// spanning from its creation to its close, such that the file can be loaded
+/// in Perfetto (or `chrome://tracing`).
+///
+/// Events are written as soon as their span closes and the closing bracket of
+/// the trace is omitted (as permitted by the format), so that the trace of a
+/// session that is interrupted remains readable.
+#[derive(Debug)]
+pub struct ChromeTraceLayer {
+    start: Instant,
+    out:   Mutex<Output>,
+}
+
+#[derive(Debug)]
+struct Output {
+    writer:  BufWriter<File>,
+    events:  usize,
+    /// The threads whose name has been written.
+    threads: BTreeSet<u64>,
+}
+
+/// The timing and fields of a span, stored within its extensions.
+struct Timing {
+    /// When the span was created (in microseconds since the trace started).
+    start:  f64,
+    /// The thread the span was created on.
+    thread: u64,
+    args:   JsonFields,
+}
+
+impl ChromeTraceLayer {
+    /// Create (or truncate) the trace file at `path`.
+    pub fn create(path: &Path) -> Result<Self, LogError> {
+        let file = File::create(path)
+            .map_err(|e| LogError::TraceFileCreation(format!("{}: {e}", path.display()).into()))?;
+        let out = Output { writer: BufWriter::new(file), events: 0, threads: BTreeSet::new() };
+
+        Ok(Self { start: Instant::now(), out: Mutex::new(out) })
+    }
+
+    /// The time elapsed since the trace started, in microseconds.
+    fn now(&self) -> f64 {
+        self.start.elapsed().as_secs_f64() * 1e6
+    }
+
+    fn write(&self, event: &Value) {
+        // Failing to write the trace must not interrupt the session
+        let _ = self.out.lock().unwrap_or_else(|e| e.into_inner()).write(event);
+    }
+}
+
+impl Output {
+    fn write(&mut self, event: &Value) -> io::Result<()> {
+        let separator = if self.events == 0 { "[" } else { "," };
+        writeln!(self.writer, "{separator}{event}")?;
+        self.events += 1;
+        self.writer.flush()
+    }
+}
+
+impl<S> Layer<S> for ChromeTraceLayer
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+{
+    fn on_new_span(&self, attrs: &Attributes<'_>, id: &Id, ctx: Context<'_, S>) {
+        let Some(span) = ctx.span(id) else {
+            return;
+        };
+
+        let thread = THREAD.with(|thread| *thread);
+        let mut out = self.out.lock().unwrap_or_else(|e| e.into_inner());
+        if out.threads.insert(thread) {
+            let current = thread::current();
+            let name = current.name().map_or_else(|| format!("thread-{thread}"), Into::into);
+            let _ = out.write(&json!({
+                "name": "thread_name",
+                "ph": "M",
+                "pid": std::process::id(),
+                "tid": thread,
+                "args": { "name": name },
+            }));
+        }
+        drop(out);
+
+        let mut args = JsonFields::default();
+        attrs.record(&mut args);
+        span.extensions_mut().insert(Timing { start: self.now(), thread, args });
+    }
+
+    fn on_record(&self, id: &Id, values: &Record<'_>, ctx: Context<'_, S>) {
+        let Some(span) = ctx.span(id) else {
+            return;
+        };
+
+        let mut extensions = span.extensions_mut();
+        if let Some(timing) = extensions.get_mut::<Timing>() {
+            values.record(&mut timing.args);
+        }
+    }
+
+    fn on_close(&self, id: Id, ctx: Context<'_, S>) {
+        let Some(span) = ctx.span(&id) else {
+            return;
+        };
+
+        let extensions = span.extensions();
+        let Some(timing) = extensions.get::<Timing>() else {
+            return;
+        };
+        self.write(&json!({
+            "name": span.name(),
+            "cat": span.metadata().target(),
+            "ph": "X",
+            "ts": timing.start,
+            "dur": self.now() - timing.start,
+            "pid": std::process::id(),
+            "tid": timing.thread,
+            "args": timing.args.0,
+        }));
+    }
+}
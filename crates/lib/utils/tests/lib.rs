@@ -1,9 +1,331 @@
 #[cfg(test)]
 mod utils_test_suite {
+    use cfg::{
+        config::Config,
+        settings::Renderer,
+    };
+    use diagnostics::{
+        compiler::CompilerDiagnostic,
+        errors::{
+            BuildError,
+            CacheError,
+            CliError,
+            ConfigError,
+            LogError,
+            StorageError,
+            SyntaxError,
+            ToolchainError,
+            WatchError,
+        },
+        explain,
+    };
+    use miette::Diagnostic;
     use pretty_assertions_sorted::assert_eq;
+    use serde_json::Value;
+    use std::{
+        collections::BTreeSet,
+        fmt,
+        fs,
+        io::Write,
+        path::PathBuf,
+    };
+    use tracing_subscriber::{
+        fmt::MakeWriter,
+        layer::SubscriberExt,
+    };
+    use utils::{
+        log::{
+            ByakuganReportHandler,
+            GraphicalTheme,
+            ThemeCharacters,
+        },
+        log_file::{
+            LogFile,
+            Rotation,
+        },
+        render::{
+            strip_ansi,
+            Rendering,
+        },
+        report,
+        trace::ChromeTraceLayer,
+    };
+
+    fn detect(requested: Renderer, terminal: bool, env: &[(&str, &str)]) -> (Renderer, bool) {
+        let rendering = Rendering::detect_with(requested, terminal, |var| {
+            env.iter().find(|(name, _)| *name == var).map(|(_, value)| value.to_string())
+        });
+        (rendering.renderer(), rendering.color())
+    }
 
     #[test]
     fn test_utils() {
         assert_eq!(1, 1);
     }
+
+    #[test]
+    fn test_detect_renderer() {
+        assert_eq!(detect(Renderer::Auto, true, &[]), (Renderer::Tty, true));
+        assert_eq!(detect(Renderer::Auto, false, &[]), (Renderer::Plain, false));
+        assert_eq!(detect(Renderer::Auto, true, &[("CI", "true")]), (Renderer::Plain, true));
+        assert_eq!(detect(Renderer::Auto, true, &[("CI", "false")]), (Renderer::Tty, true));
+        assert_eq!(detect(Renderer::Auto, true, &[("TERM", "dumb")]), (Renderer::Plain, false));
+        assert_eq!(detect(Renderer::Tty, false, &[]), (Renderer::Tty, true));
+        assert_eq!(detect(Renderer::Json, true, &[]), (Renderer::Json, false));
+    }
+
+    #[test]
+    fn test_detect_no_color() {
+        assert_eq!(detect(Renderer::Auto, true, &[("NO_COLOR", "1")]), (Renderer::Tty, false));
+        assert_eq!(detect(Renderer::Auto, true, &[("NO_COLOR", "")]), (Renderer::Tty, true));
+        assert_eq!(detect(Renderer::Plain, true, &[("NO_COLOR", "1")]), (Renderer::Plain, false));
+    }
+
+    #[test]
+    fn test_rendering_is_live() {
+        assert!(!Rendering::detect_with(Renderer::Plain, true, |_| None).is_live());
+        assert!(Rendering::detect_with(Renderer::Tty, false, |_| None).is_live());
+    }
+
+    #[test]
+    fn test_strip_ansi() {
+        assert_eq!(strip_ansi("\u{1b}[32m INFO\u{1b}[0m driver: done"), " INFO driver: done");
+        assert_eq!(strip_ansi("\tlet x = 1;"), "\tlet x = 1;");
+    }
+
+    fn log_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("byakugan-{name}-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        dir
+    }
+
+    fn log(file: &LogFile, line: &str) {
+        write!(file.make_writer(), "{line}").unwrap();
+    }
+
+    #[test]
+    fn test_log_file_rotation() {
+        let dir = log_dir("rotation");
+        let file = LogFile::open(&dir, Rotation::builder().max_size(16).build()).unwrap();
+        let first = file.path();
+
+        log(&file, "\u{1b}[32mDEBUG\u{1b}[0m first\n");
+        log(&file, "DEBUG second\n");
+        log(&file, "DEBUG third\n");
+
+        assert_eq!(fs::read_to_string(&first).unwrap(), "DEBUG first\n");
+        assert!(file.path().to_string_lossy().ends_with(".2.log"));
+        assert_eq!(fs::read_to_string(file.path()).unwrap(), "DEBUG third\n");
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_log_file_retention() {
+        let dir = log_dir("retention");
+        let file =
+            LogFile::open(&dir, Rotation::builder().max_size(1).retention(2).build()).unwrap();
+
+        (0..5).for_each(|i| log(&file, &format!("DEBUG {i}\n")));
+
+        let logs = fs::read_dir(&dir).unwrap().flatten().map(|e| e.path()).collect::<Vec<_>>();
+        assert_eq!(logs.len(), 2);
+        assert!(logs.contains(&file.path()));
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_chrome_trace() {
+        let dir = log_dir("trace");
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("trace.json");
+        let layer = ChromeTraceLayer::create(&path).unwrap();
+
+        tracing::subscriber::with_default(tracing_subscriber::registry().with(layer), || {
+            let cycle =
+                tracing::info_span!("cycle", trigger = "change", outcome = tracing::field::Empty);
+            cycle.in_scope(|| tracing::info_span!("build").in_scope(|| {}));
+            cycle.record("outcome", "success");
+        });
+
+        // The closing bracket is optional, allowing interrupted traces to be read
+        let trace =
+            serde_json::from_str::<Value>(&format!("{}]", fs::read_to_string(&path).unwrap()))
+                .unwrap();
+        let events = trace.as_array().unwrap();
+        let names = events.iter().map(|event| event["name"].as_str().unwrap()).collect::<Vec<_>>();
+        assert_eq!(names, ["thread_name", "build", "cycle"]);
+
+        let (build, cycle) = (&events[1], &events[2]);
+        assert_eq!(cycle["ph"], "X");
+        assert_eq!(cycle["args"]["trigger"], "change");
+        assert_eq!(cycle["args"]["outcome"], "success");
+        assert!(build["ts"].as_f64() >= cycle["ts"].as_f64());
+        assert!(build["dur"].as_f64() <= cycle["dur"].as_f64());
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_sarif_report() {
+        let dir = log_dir("sarif");
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("main.cc"), "int main() {\n  return x;\n}\n").unwrap();
+
+        let diagnostic =
+            CompilerDiagnostic::parse("main.cc:2:10: error: use of undeclared 'x'", &dir);
+        let failed = BuildError::Failed {
+            command:     "buck2 build //:main".into(),
+            status:      "exit status: 1".into(),
+            diagnostics: diagnostic.into_iter().collect(),
+        };
+
+        let sarif = report::sarif(&[&failed]);
+        assert_eq!(sarif["version"], "2.1.0");
+        let results = sarif["runs"][0]["results"].as_array().unwrap();
+        assert_eq!(results.len(), 2);
+        assert_eq!(results[0]["ruleId"], "bkg::build::failed");
+        assert_eq!(
+            results[0]["message"]["text"],
+            "Build Error - Build failed: buck2 build //:main"
+        );
+
+        let location = &results[1]["locations"][0]["physicalLocation"];
+        assert_eq!(results[1]["level"], "error");
+        assert!(location["artifactLocation"]["uri"].as_str().unwrap().ends_with("main.cc"));
+        assert_eq!(location["region"]["startLine"], 2);
+        assert_eq!(location["region"]["startColumn"], 10);
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_configured_theme() {
+        let src = "[theme]\npreset = \"emoji\"\n\n[theme.colors]\nerror = \"#ff0000\"\n";
+        let config = Config::parse(std::path::Path::new("byakugan.toml"), src).unwrap();
+
+        let theme = Rendering::detect_with(Renderer::Plain, true, |_| None).theme(config.theme());
+        assert_eq!(theme.characters, ThemeCharacters::emoji());
+        assert_eq!(theme.styles.error.style("x").to_string(), "\u{1b}[38;2;255;0;0mx\u{1b}[0m");
+
+        let theme = Rendering::detect_with(Renderer::Plain, false, |_| None).theme(config.theme());
+        assert_eq!(theme.styles.error.style("x").to_string(), "x");
+
+        let theme = Rendering::detect_with(Renderer::Plain, true, |_| None)
+            .theme(Config::default().theme());
+        assert_eq!(theme.characters, ThemeCharacters::ascii());
+    }
+
+    struct Rendered<'a>(&'a dyn Diagnostic);
+
+    impl fmt::Display for Rendered<'_> {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            ByakuganReportHandler::new_themed(GraphicalTheme::none()).render_report(f, self.0)
+        }
+    }
+
+    #[test]
+    fn test_error_codes_explained() {
+        let span = (0, 1).into();
+        let errors: Vec<Box<dyn Diagnostic>> = vec![
+            Box::new(CliError::TargetNotFound {
+                command: "bkg build //:a".into(),
+                target: "//:a".into(),
+                did_you_mean: vec![],
+                span,
+            }),
+            Box::new(CliError::TargetsNotFound {
+                command: "bkg build //:a //:b".into(),
+                targets: vec![],
+                did_you_mean: vec![],
+                span,
+            }),
+            Box::new(CliError::MultipleTargetsNotSupported { command: "bkg run".into(), span }),
+            Box::new(CliError::NoBuildSystemDetected { command: "bkg build".into() }),
+            Box::new(CliError::UnknownErrorCode { code: "bkg::cli::a".into() }),
+            Box::new(SyntaxError::InvalidRepoName { cmd: "@".into(), repo: "@".into(), span }),
+            Box::new(SyntaxError::InvalidPkgName { cmd: "/".into(), pkg: "/".into(), span }),
+            Box::new(SyntaxError::InvalidTargetName {
+                label: ":".into(),
+                target: ":".into(),
+                span,
+            }),
+            Box::new(SyntaxError::InvalidLabel { label: ":".into(), span }),
+            Box::new(ToolchainError::Buck2NotFound),
+            Box::new(ToolchainError::BazelNotFound),
+            Box::new(ToolchainError::CargoNotFound),
+            Box::new(ToolchainError::VersionMismatch {
+                tool:      "bazel".into(),
+                pinned:    "6.3.2".into(),
+                installed: "bazel 7.0.0".into(),
+                hint:      "".into(),
+                file:      ".bazelversion".into(),
+                src:       "6.3.2\n".into(),
+                span:      (0, 5).into(),
+            }),
+            Box::new(LogError::LogFileInitialization("".into())),
+            Box::new(LogError::LogSystemInitialization("".into())),
+            Box::new(LogError::LogFileOpen("".into())),
+            Box::new(LogError::TraceFileCreation("".into())),
+            Box::new(CacheError::Write { path: "".into(), reason: "".into() }),
+            Box::new(WatchError::WatchLimit {
+                polled:    0,
+                dirs:      "".into(),
+                max:       "".into(),
+                suggested: 0,
+                interval:  "".into(),
+            }),
+            Box::new(WatchError::WatchFailed { path: "".into(), reason: "".into() }),
+            Box::new(StorageError::Io { path: "".into(), reason: "".into() }),
+            Box::new(StorageError::BadMagic { path: "".into() }),
+            Box::new(StorageError::UnsupportedVersion {
+                path:      "".into(),
+                version:   0,
+                supported: 0,
+            }),
+            Box::new(StorageError::ChecksumMismatch {
+                path:     "".into(),
+                offset:   0,
+                expected: 0,
+                actual:   0,
+            }),
+            Box::new(StorageError::Corrupted { path: "".into(), offset: 0, reason: "".into() }),
+            Box::new(StorageError::BadManifest { path: "".into(), reason: "".into() }),
+            Box::new(StorageError::Locked { path: "".into(), reason: "".into() }),
+            Box::new(StorageError::TornWal {
+                path:    "".into(),
+                offset:  0,
+                dropped: 0,
+                reason:  "".into(),
+            }),
+            Box::new(ConfigError::Read { path: "".into(), reason: "".into() }),
+            Box::new(ConfigError::Invalid {
+                path:   "".into(),
+                src:    "".into(),
+                reason: "".into(),
+                span:   None,
+            }),
+            Box::new(BuildError::Failed {
+                command:     "".into(),
+                status:      "".into(),
+                diagnostics: vec![],
+            }),
+        ];
+
+        // Every error has a stable code with an explanation (and every
+        // explanation the code of an error), hinted at in its footer
+        let mut codes = BTreeSet::new();
+        for error in &errors {
+            let code = error.code().unwrap().to_string();
+            let explanation = explain::lookup(&code).unwrap();
+            assert!(code.starts_with(explain::CODE_PREFIX));
+            assert!(explanation.text.contains("## Example") && explanation.text.contains("## Fix"));
+            assert!(strip_ansi(&Rendered(error.as_ref()).to_string())
+                .contains(&format!("try `bkg explain {code}`")));
+            codes.insert(code);
+        }
+        assert_eq!(codes.len(), explain::EXPLANATIONS.len());
+
+        let rendered =
+            Rendered(&CompilerDiagnostic::parse("error[E0425]: x", ".".as_ref()).unwrap())
+                .to_string();
+        assert!(!rendered.contains("bkg explain"));
+    }
 }
@@ -0,0 +1,159 @@
+use crossterm::{
+    event::{
+        self,
+        Event,
+        KeyCode,
+        KeyEvent,
+        KeyEventKind,
+        KeyModifiers,
+    },
+    terminal,
+    tty::IsTty,
+};
+use derive_more::Display;
+use owo_colors::OwoColorize;
+use std::{
+    sync::mpsc::Sender,
+    thread,
+};
+
+use crate::WatchEvent;
+
+/// An **action** requested by the user via a keybinding while watching (e.g.
+/// pressing `r` to force a rebuild).
+#[derive(Debug, Display, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Action {
+    /// Force a rebuild, even if no file system changes have been observed.
+    #[display(fmt = "rebuild")]
+    Rebuild,
+    /// Run the tests for the watched targets.
+    #[display(fmt = "test")]
+    Test,
+    /// Toggle between running **all** targets and only the targets
+    /// **affected** by the files changed since the last run.
+    #[display(fmt = "toggle all/affected")]
+    ToggleScope,
+    /// Pause (or resume) reacting to file system changes.
+    #[display(fmt = "toggle pause")]
+    TogglePause,
+    /// Toggle filtering test runs down to the tests that failed last time.
+    #[display(fmt = "toggle failing filter")]
+    FilterFailing,
+    /// Clear the screen.
+    #[display(fmt = "clear")]
+    Clear,
+    /// Quit cleanly, killing any child processes spawned while watching.
+    #[display(fmt = "quit")]
+    Quit,
+}
+
+impl Action {
+    /// Map a **key event** to the corresponding [`Action`], if any. `Ctrl-C`
+    /// is mapped to [`Action::Quit`] since raw mode disables the usual
+    /// `SIGINT` handling of the terminal.
+    pub fn from_key(key: &KeyEvent) -> Option<Self> {
+        if key.kind != KeyEventKind::Press {
+            return None;
+        }
+
+        match key.code {
+            KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => Some(Self::Quit),
+            KeyCode::Char('r') => Some(Self::Rebuild),
+            KeyCode::Char('t') => Some(Self::Test),
+            KeyCode::Char('a') => Some(Self::ToggleScope),
+            KeyCode::Char('p') => Some(Self::TogglePause),
+            KeyCode::Char('f') => Some(Self::FilterFailing),
+            KeyCode::Char('c') => Some(Self::Clear),
+            KeyCode::Char('q') | KeyCode::Esc => Some(Self::Quit),
+            _ => None,
+        }
+    }
+}
+
+/// The one-line **help footer** listing the available keybindings.
+pub fn help_footer() -> String {
+    [("r", "rebuild"), ("t", "test"), ("a", "all/affected"), ("p", "pause"), ("f", "failing")]
+        .into_iter()
+        .chain([("c", "clear"), ("q", "quit")])
+        .map(|(key, action)| format!("{} {}", key.bold(), action.dimmed()))
+        .collect::<Vec<_>>()
+        .join(&format!(" {} ", "·".dimmed()))
+}
+
+/// Guard restoring the terminal to **cooked mode** when dropped.
+#[derive(Debug)]
+pub struct RawModeGuard;
+
+impl Drop for RawModeGuard {
+    fn drop(&mut self) {
+        if let Err(e) = terminal::disable_raw_mode() {
+            tracing::warn!("Unable to restore terminal from raw mode: {}", e);
+        }
+    }
+}
+
+/// Enable **interactive controls** by putting the terminal into raw mode and
+/// forwarding key presses as [`WatchEvent::Key`] events over the given
+/// channel. When `terminal` is set (i.e. the TUI is active), every terminal
+/// event is forwarded as a [`WatchEvent::Terminal`] event instead.
+///
+/// Returns `None` (and watching continues non-interactively) when `stdin` is
+/// not a TTY (e.g. in CI or when input is piped) or raw mode is unavailable.
+pub fn enable(tx: Sender<WatchEvent>, terminal: bool) -> Option<RawModeGuard> {
+    if !std::io::stdin().is_tty() {
+        tracing::debug!("stdin is not a TTY, interactive controls are disabled");
+        return None;
+    }
+
+    if let Err(e) = terminal::enable_raw_mode() {
+        tracing::warn!("Unable to enable raw mode, interactive controls are disabled: {}", e);
+        return None;
+    }
+    let guard = RawModeGuard;
+    restore_output_processing();
+
+    thread::spawn(move || loop {
+        match event::read() {
+            Ok(event) if terminal => {
+                if tx.send(WatchEvent::Terminal(event)).is_err() {
+                    break; // The watch loop has exited
+                }
+            }
+            Ok(Event::Key(key)) => {
+                if let Some(action) = Action::from_key(&key) {
+                    if tx.send(WatchEvent::Key(action)).is_err() {
+                        break; // The watch loop has exited
+                    }
+                }
+            }
+            Ok(_) => {}
+            Err(e) => {
+                tracing::warn!("Unable to read terminal input: {}", e);
+                break;
+            }
+        }
+    });
+
+    Some(guard)
+}
+
+/// Raw mode also disables output post-processing, which would turn every `\n`
+/// written by the logging subsystem (or the build tool) into a bare line feed.
+/// Input stays raw, but output is translated as usual.
+#[cfg(unix)]
+fn restore_output_processing() {
+    use std::os::unix::io::AsRawFd;
+
+    let fd = std::io::stdout().as_raw_fd();
+    // SAFETY: `termios` is a plain C struct fully initialized by `tcgetattr`.
+    unsafe {
+        let mut termios: libc::termios = std::mem::zeroed();
+        if libc::tcgetattr(fd, &mut termios) == 0 {
+            termios.c_oflag |= libc::OPOST | libc::ONLCR;
+            libc::tcsetattr(fd, libc::TCSANOW, &termios);
+        }
+    }
+}
+
+#[cfg(not(unix))]
+fn restore_output_processing() {}
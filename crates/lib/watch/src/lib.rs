@@ -1,3 +1,7 @@
+use cfg::{
+    config::WatchConfig,
+    settings::Topic,
+};
 use cli::Command;
 use downcast_rs::Downcast;
 use dyn_clone::DynClone;
@@ -9,8 +13,38 @@ use notify::*;
 // use notify_debouncer_mini::{new_debouncer, notify::*, DebounceEventResult};
 // use smartstring::alias::String;
 use bytes::BytesMut;
+use crossterm::tty::IsTty;
 use notify_debouncer_mini::DebounceEventResult;
-use std::time::Duration;
+use session::Session;
+use std::{
+    sync::{
+        mpsc::RecvTimeoutError,
+        Arc,
+    },
+    time::Duration,
+};
+use tracker::Tracker;
+use tui::{
+    SharedScreen,
+    Stream,
+    Tui,
+};
+
+pub mod keys;
+mod session;
+pub mod tui;
+pub mod watcher;
+
+pub use keys::Action;
+pub use session::{
+    built_artifact,
+    cargo_artifact,
+    cquery_artifact,
+    failed_test_target,
+    BuildTool,
+    Scope,
+};
+pub use watcher::Watchers;
 
 /// A **task** that can be _executed_ and _monitored/manipulated_ by Byakugan.
 pub trait Task: std::fmt::Debug + std::fmt::Display + Send + Sync + DynClone + Downcast {
@@ -47,71 +81,131 @@ pub trait Task: std::fmt::Debug + std::fmt::Display + Send + Sync + DynClone + D
 
 dyn_clone::clone_trait_object!(Task);
 
+/// An **observer** of the builds spawned while watching (e.g. to render their
+/// progress). The output of the build tool is reported line by line from the
+/// threads forwarding it.
+pub trait BuildObserver: Send + Sync {
+    /// A build tool invocation (e.g. `buck2 build <targets>`) is about to
+    /// start, returning any extra arguments to pass to the build tool (e.g. to
+    /// write an event log).
+    fn started(&self, verb: &str, targets: &[String]) -> Vec<String>;
+
+    /// A line of output of the current build tool invocation.
+    fn output(&self, line: &str);
+
+    /// The current build tool invocation finished (or was superseded by a
+    /// newer one).
+    fn finished(&self, success: bool);
+
+    /// The tracker of the current build tool invocation, if its progress is
+    /// tracked (e.g. to render it within the TUI).
+    fn tracker(&self) -> Option<Tracker>;
+}
+
 pub type ModifyEvent = DebounceEventResult;
 
-pub fn watch(command: Command) -> Result<()> {
+/// An **event** processed by the watch loop.
+#[derive(Debug)]
+pub enum WatchEvent {
+    /// A file system event reported by the watcher.
+    Fs(notify::Result<Event>),
+    /// An action requested by the user via a keybinding.
+    Key(Action),
+    /// A terminal event (e.g. a key press or a resize) to be handled by the
+    /// TUI.
+    Terminal(crossterm::event::Event),
+}
+
+/// How often the child process of the current run is checked on while no
+/// events are received.
+const POLL_INTERVAL: Duration = Duration::from_millis(200);
+
+pub fn watch(
+    command: Command,
+    tool: BuildTool,
+    observer: Option<Arc<dyn BuildObserver>>,
+    settings: &WatchConfig,
+) -> Result<()> {
     let (tx, rx) = std::sync::mpsc::channel();
-    // This example is a little bit misleading as you can just create one Config and
-    // use it for all watchers. That way the pollwatcher specific stuff is still
-    // configured, if it should be used.
-    let mut watcher: Box<dyn Watcher> = if RecommendedWatcher::kind() == WatcherKind::PollWatcher {
-        // custom config for PollWatcher kind
-        // you
-        let config = Config::default().with_poll_interval(Duration::from_secs(1));
-        Box::new(PollWatcher::new(tx, config).unwrap())
-    } else {
-        // use default config for everything else
-        Box::new(RecommendedWatcher::new(tx, Config::default()).unwrap())
-    };
 
     // get the current directory
-    let current_dir = std::env::current_dir().unwrap();
-
-    // watch the current directory recursively
-    watcher.watch(&current_dir, RecursiveMode::Recursive).unwrap();
+    let current_dir = std::env::current_dir().into_diagnostic()?;
+
+    // watch the current directory recursively, polling the directories past
+    // the limit of the native watcher (e.g. of inotify watches)
+    let mut watchers = Watchers::new(tx.clone(), settings.poll_interval());
+    watchers.watch(&current_dir)?;
+    if let Some(warning) = watchers.limit_reached() {
+        utils::log::topic(Topic::Watch)
+            .in_scope(|| tracing::warn!("{:?}", miette::Report::new(warning)));
+    }
 
-    tracing::info!("Watching current directory...");
+    utils::log::topic(Topic::Watch).in_scope(|| tracing::info!("Watching current directory..."));
 
     // ensure the validity of the command
     validate_command(&command)?;
 
-    for e in rx {
-        match e {
-            Ok(event) => match event.kind {
-                EventKind::Create(_) | EventKind::Modify(_) | EventKind::Remove(_) => match command
-                {
-                    Command::Build(ref args) => {
-                        tracing::info!("update detected, rebuilding... {:?}", args);
-
-                        // todo, figure out which build system we're during invocation, and then
-                        // execute the appropriate command this should be
-                        // done via loading it into the context, and then using the context
-                        // behind the build command
-
-                        let mut cmd = std::process::Command::new("buck2");
-                        cmd.arg("build");
-                        for arg in args.targets() {
-                            cmd.arg(arg.to_string());
-                        }
-
-                        cmd.stdout(std::process::Stdio::inherit())
-                            .stderr(std::process::Stdio::inherit())
-                            .spawn()
-                            .into_diagnostic()?;
-                    }
-                    Command::Run(_) => {
-                        tracing::info!("update detected, rebuilding and restarting process...");
-                    }
-                    Command::Test(_) => {
-                        tracing::info!("update detected, rebuilding and re-executing tests...");
+    // enable the interactive controls (if attached to a terminal), rendering
+    // to the TUI if requested
+    let wants_tui = matches!(&command, Command::Run(args) if *args.tui());
+    let wants_tui = wants_tui && std::io::stdout().is_tty();
+    let controls = keys::enable(tx, wants_tui);
+    let screen = (wants_tui && controls.is_some()).then(SharedScreen::default);
+    if screen.is_some() {
+        // Progress is rendered within the TUI instead
+        tracker::set_mode(tracker::Mode::Detached);
+    }
+    if matches!(&command, Command::Run(args) if *args.tui()) && screen.is_none() {
+        tracing::warn!("The TUI requires a terminal, falling back to plain output");
+    }
+    let mut tui = screen.clone().map(Tui::enter).transpose()?;
+    let redirect = screen
+        .clone()
+        .map(|screen| utils::log::redirect(move |line| screen.lock().push(Stream::Log, line)));
+
+    let mut session =
+        Session::new(command, tool, current_dir, controls.is_some(), screen, observer);
+    session.print_footer();
+
+    loop {
+        match rx.recv_timeout(POLL_INTERVAL) {
+            Ok(WatchEvent::Fs(Ok(event))) => {
+                utils::log::topic(Topic::Watch).in_scope(|| tracing::trace!("{:?}", event));
+                match event.kind {
+                    EventKind::Create(_) | EventKind::Modify(_) | EventKind::Remove(_) => {
+                        session.on_change(event.paths)
                     }
-                },
-                EventKind::Any | EventKind::Access(_) | EventKind::Other => {}
-            },
-            Err(e) => tracing::error!("watch error: {}", e),
+                    EventKind::Any | EventKind::Access(_) | EventKind::Other => {}
+                }
+            }
+            Ok(WatchEvent::Fs(Err(e))) => {
+                utils::log::topic(Topic::Watch).in_scope(|| tracing::error!("watch error: {}", e))
+            }
+            Ok(WatchEvent::Key(action)) => {
+                if session.handle(action).is_break() {
+                    break;
+                }
+            }
+            Ok(WatchEvent::Terminal(event)) => {
+                let action = tui.as_mut().and_then(|tui| tui.handle(event));
+                if action.map_or(false, |action| session.handle(action).is_break()) {
+                    break;
+                }
+            }
+            Err(RecvTimeoutError::Timeout) => session.poll(),
+            Err(RecvTimeoutError::Disconnected) => break,
+        }
+
+        if let Some(tui) = tui.as_mut() {
+            if let Err(e) = tui.draw() {
+                tracing::warn!("Unable to draw the TUI: {}", e);
+            }
         }
     }
 
+    session.shutdown();
+    drop((redirect, tui));
+
     Ok(())
 }
 
@@ -0,0 +1,761 @@
+use cfg::settings::Topic;
+use cli::Command;
+use crossterm::{
+    cursor::MoveTo,
+    execute,
+    terminal::{
+        Clear,
+        ClearType,
+    },
+};
+use derive_more::Display;
+use diagnostics::{
+    compiler::CompilerDiagnostic,
+    errors::BuildError,
+};
+use miette::{
+    miette,
+    IntoDiagnostic,
+    Result,
+};
+use owo_colors::OwoColorize;
+use parking_lot::Mutex;
+use std::{
+    collections::BTreeSet,
+    io::{
+        BufRead,
+        BufReader,
+        Read,
+    },
+    iter,
+    ops::ControlFlow,
+    path::{
+        Path,
+        PathBuf,
+    },
+    process::{
+        Child,
+        Stdio,
+    },
+    sync::Arc,
+    thread,
+};
+use tracing::Span;
+use utils::render::strip_ansi;
+
+use crate::{
+    keys::{
+        self,
+        Action,
+    },
+    tui::{
+        SharedScreen,
+        Stream,
+    },
+    BuildObserver,
+};
+
+/// Directories containing build outputs or VCS metadata. Changes within them
+/// are produced by the build tool itself and must not trigger a rebuild.
+const IGNORED_DIRS: &[&str] = &["buck-out", "target", ".git", ".hg", ".sl"];
+
+/// Which targets a run is scoped to.
+#[derive(Debug, Display, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum Scope {
+    /// Every target requested on the command line.
+    #[default]
+    #[display(fmt = "all")]
+    All,
+    /// Only the targets (transitively) depending on the files changed since
+    /// the last run.
+    #[display(fmt = "affected")]
+    Affected,
+}
+
+/// The **build tool** spawned to run the watched command, alongside the binary
+/// executing it (e.g. `buck` or `bazelisk`).
+#[derive(Debug, Display, Clone, PartialEq, Eq, Hash)]
+pub enum BuildTool {
+    #[display(fmt = "{}", _0)]
+    Buck2(String),
+    #[display(fmt = "{}", _0)]
+    Bazel(String),
+    #[display(fmt = "cargo")]
+    Cargo,
+}
+
+impl BuildTool {
+    /// The binary to spawn.
+    pub fn program(&self) -> &str {
+        match self {
+            Self::Buck2(program) | Self::Bazel(program) => program,
+            Self::Cargo => "cargo",
+        }
+    }
+
+    /// The arguments selecting the targets of a build of the target being run
+    /// (e.g. `--bin <target>` for Cargo), reporting the output of the build
+    /// when the build tool supports it (see [`built_artifact`]).
+    fn run_args(&self, targets: &[String]) -> Vec<String> {
+        match self {
+            Self::Buck2(_) => iter::once("--show-full-output".to_string())
+                .chain(targets.iter().cloned())
+                .collect(),
+            Self::Bazel(_) => targets.to_vec(),
+            Self::Cargo => {
+                targets.iter().flat_map(|target| ["--bin".to_string(), target.clone()]).collect()
+            }
+        }
+    }
+
+    /// Whether the output of builds of the target being run is reported while
+    /// building, rather than through an
+    /// [`artifact_query`](Self::artifact_query).
+    fn reports_artifact(&self) -> bool {
+        matches!(self, Self::Buck2(_))
+    }
+
+    /// The arguments of a query for the output of the (built) `target`, for
+    /// build tools not reporting it while building.
+    fn artifact_query(&self, target: &str) -> Option<Vec<String>> {
+        let args: &[&str] = match self {
+            Self::Buck2(_) => return None,
+            Self::Bazel(_) => &["cquery", "--output=files"],
+            // A no-op once built, reporting the artifacts as JSON messages
+            Self::Cargo => &["build", "--message-format=json-render-diagnostics", "--bin"],
+        };
+        Some(args.iter().copied().chain([target]).map(str::to_string).collect())
+    }
+
+    /// Extract the output of the target from the output of its
+    /// [`artifact_query`](Self::artifact_query).
+    fn queried_artifact(&self, stdout: &str) -> Option<PathBuf> {
+        match self {
+            Self::Buck2(_) => None,
+            Self::Bazel(_) => cquery_artifact(stdout),
+            Self::Cargo => cargo_artifact(stdout),
+        }
+    }
+
+    /// The arguments of a query for the targets depending on `files` (relative
+    /// to the root of the workspace), if the build tool supports one.
+    fn rdeps_query(&self, files: &[String]) -> Option<Vec<String>> {
+        match self {
+            Self::Buck2(_) => Some(
+                ["uquery", "rdeps(//..., owner(%Ss))"]
+                    .into_iter()
+                    .map(str::to_string)
+                    .chain(files.iter().cloned())
+                    .collect(),
+            ),
+            Self::Bazel(_) => {
+                Some(vec!["query".to_string(), format!("rdeps(//..., set({}))", files.join(" "))])
+            }
+            Self::Cargo => None,
+        }
+    }
+}
+
+/// The **state** of an interactive watch session: the command being watched,
+/// the toggles set via keybindings, the child process of the current run and
+/// (for `run`) the supervised process.
+pub(crate) struct Session {
+    command:      Command,
+    tool:         BuildTool,
+    root:         PathBuf,
+    interactive:  bool,
+    paused:       bool,
+    scope:        Scope,
+    only_failing: bool,
+    /// Files changed since the last run (used to compute affected targets).
+    changed:      BTreeSet<PathBuf>,
+    /// Test targets that failed during the last test run.
+    failing:      Arc<Mutex<BTreeSet<String>>>,
+    /// The output of the last build of the target being run.
+    artifact:     Arc<Mutex<Option<PathBuf>>>,
+    /// The compiler diagnostics found in the output of the current run (only
+    /// collected when reported in a machine-readable format).
+    diagnostics:  Arc<Mutex<Vec<CompilerDiagnostic>>>,
+    /// The child process of the current run, alongside the verb it runs and
+    /// the span it is traced in (until it exits).
+    child:        Option<(&'static str, Child, Span)>,
+    /// The supervised process (i.e. the target being run).
+    process:      Option<Child>,
+    /// The screen of the TUI, if output is rendered to it.
+    screen:       Option<SharedScreen>,
+    /// The observer notified of the progress of builds.
+    observer:     Option<Arc<dyn BuildObserver>>,
+    /// The span of the current cycle, from the change (or action) triggering
+    /// a run until its completion.
+    cycle:        Option<Span>,
+}
+
+impl Session {
+    pub(crate) fn new(
+        command: Command,
+        tool: BuildTool,
+        root: PathBuf,
+        interactive: bool,
+        screen: Option<SharedScreen>,
+        observer: Option<Arc<dyn BuildObserver>>,
+    ) -> Self {
+        Self {
+            command,
+            tool,
+            root,
+            interactive,
+            paused: false,
+            scope: Scope::default(),
+            only_failing: false,
+            changed: BTreeSet::new(),
+            failing: Arc::new(Mutex::new(BTreeSet::new())),
+            artifact: Arc::new(Mutex::new(None)),
+            diagnostics: Arc::new(Mutex::new(Vec::new())),
+            child: None,
+            process: None,
+            screen,
+            observer,
+            cycle: None,
+        }
+    }
+
+    /// React to a set of **changed paths** reported by the file system
+    /// watcher.
+    pub(crate) fn on_change(&mut self, paths: Vec<PathBuf>) {
+        {
+            let _topic = utils::log::topic(Topic::Debounce).entered();
+            let paths = paths.into_iter().filter(|path| !self.is_ignored(path)).collect::<Vec<_>>();
+            if paths.is_empty() {
+                return;
+            }
+            tracing::debug!("{} path(s) changed: {:?}", paths.len(), paths);
+            self.changed.extend(paths);
+
+            if self.paused {
+                tracing::debug!("Watching is paused, deferring {} change(s)", self.changed.len());
+                return;
+            }
+
+            match self.command {
+                Command::Build(ref args) => {
+                    tracing::info!("update detected, rebuilding... {:?}", args);
+                }
+                Command::Run(_) => {
+                    tracing::info!("update detected, rebuilding and restarting process...");
+                }
+                Command::Test(_) => {
+                    tracing::info!("update detected, rebuilding and re-executing tests...");
+                }
+            }
+        }
+        self.begin_cycle("change");
+        self.in_cycle(Self::run);
+    }
+
+    /// Handle an **action** requested via a keybinding, breaking out of the
+    /// watch loop when the user asks to quit.
+    pub(crate) fn handle(&mut self, action: Action) -> ControlFlow<()> {
+        tracing::debug!("Action requested: {}", action);
+
+        match action {
+            Action::Rebuild => {
+                tracing::info!("Forcing a rebuild...");
+                self.begin_cycle("rebuild");
+                self.in_cycle(Self::run);
+            }
+            Action::Test => {
+                self.begin_cycle("test");
+                self.in_cycle(Self::test);
+            }
+            Action::ToggleScope => {
+                self.scope = match self.scope {
+                    Scope::All => Scope::Affected,
+                    Scope::Affected => Scope::All,
+                };
+                tracing::info!("Running {} targets", self.scope.yellow());
+                self.print_footer();
+            }
+            Action::TogglePause => {
+                self.paused = !self.paused;
+                if self.paused {
+                    tracing::info!("Watching {}", "paused".yellow());
+                } else {
+                    tracing::info!("Watching {}", "resumed".green());
+                    if !self.changed.is_empty() {
+                        self.begin_cycle("resume");
+                        self.in_cycle(Self::run);
+                    }
+                }
+                self.print_footer();
+            }
+            Action::FilterFailing => {
+                self.only_failing = !self.only_failing;
+                let failing = self.failing.lock().len();
+                if self.only_failing {
+                    tracing::info!("Filtering test runs to {} failing target(s)", failing);
+                } else {
+                    tracing::info!("Test runs are no longer filtered");
+                }
+                self.print_footer();
+            }
+            Action::Clear => {
+                if let Err(e) = execute!(std::io::stdout(), Clear(ClearType::All), MoveTo(0, 0)) {
+                    tracing::warn!("Unable to clear the screen: {}", e);
+                }
+                self.print_footer();
+            }
+            Action::Quit => {
+                tracing::info!("Quitting...");
+                return ControlFlow::Break(());
+            }
+        }
+
+        ControlFlow::Continue(())
+    }
+
+    /// Check on the child process of the current run, reporting its outcome
+    /// once it has exited (and restarting the supervised process after a
+    /// successful build of the target being run).
+    pub(crate) fn poll(&mut self) {
+        if let Some(process) = self.process.as_mut() {
+            if let Ok(Some(status)) = process.try_wait() {
+                let _topic = utils::log::topic(Topic::Run).entered();
+                tracing::info!("Process exited ({})", status);
+                self.process = None;
+            }
+        }
+
+        let Some((verb, child, span)) = self.child.as_mut() else {
+            return;
+        };
+
+        let topic = span.clone().entered();
+        let status = match child.try_wait() {
+            Ok(Some(status)) if status.success() => {
+                tracing::info!("{}", "Finished successfully".green());
+                Ok(())
+            }
+            Ok(Some(status)) => {
+                tracing::info!("{} ({})", "Failed".red(), status);
+                Err(status.to_string())
+            }
+            Ok(None) => return,
+            Err(e) => {
+                tracing::warn!("Unable to check on child process: {}", e);
+                Err(e.to_string())
+            }
+        };
+        let success = status.is_ok();
+        let verb = *verb;
+
+        // Machine-readable reports of failed builds, for code-scanning tools
+        if let Err(status) = status {
+            if !utils::report::format().is_human() && self.screen.is_none() {
+                let failed = BuildError::Failed {
+                    command:     format!("{} {verb}", self.tool).into(),
+                    status:      status.into(),
+                    diagnostics: std::mem::take(&mut *self.diagnostics.lock()),
+                };
+                eprintln!("{:?}", miette::Report::new(failed));
+            }
+        }
+
+        self.child = None;
+        if let Some(screen) = &self.screen {
+            screen.lock().finish_build(success);
+        }
+        if let Some(observer) = &self.observer {
+            observer.finished(success);
+        }
+        drop(topic);
+        if success && verb == "build" && matches!(self.command, Command::Run(_)) {
+            self.in_cycle(Self::restart);
+        }
+        self.end_cycle(if success { "success" } else { "failure" });
+        self.print_footer();
+    }
+
+    /// Kill any child processes spawned during the session.
+    pub(crate) fn shutdown(&mut self) {
+        self.kill();
+        self.stop();
+        self.end_cycle("cancelled");
+    }
+
+    pub(crate) fn print_footer(&self) {
+        if !self.interactive {
+            return;
+        }
+
+        let mut status = vec![self.scope.to_string()];
+        if self.paused {
+            status.push("paused".to_string());
+        }
+        if self.only_failing {
+            status.push("failing only".to_string());
+        }
+
+        if let Some(screen) = &self.screen {
+            screen.lock().status = status.join(", ");
+            return;
+        }
+        eprintln!("{} {}", format!("[{}]", status.join(", ")).cyan(), keys::help_footer());
+    }
+
+    /// Begin a new **cycle** (see [`Session::in_cycle`]), cancelling the run
+    /// of the previous one if it is still in progress.
+    fn begin_cycle(&mut self, trigger: &'static str) {
+        self.kill();
+        self.end_cycle("superseded");
+        self.cycle = Some(tracing::info_span!(
+            parent: None,
+            "cycle",
+            trigger,
+            outcome = tracing::field::Empty
+        ));
+    }
+
+    /// Run `f` within the span of the current cycle, such that the spans of
+    /// queries and builds are traced as part of it.
+    fn in_cycle(&mut self, f: impl FnOnce(&mut Self)) {
+        let cycle = self.cycle.clone().unwrap_or_else(Span::none);
+        cycle.in_scope(|| f(self));
+    }
+
+    /// End the current cycle (if any), recording its `outcome`.
+    fn end_cycle(&mut self, outcome: &str) {
+        if let Some(cycle) = self.cycle.take() {
+            cycle.record("outcome", outcome);
+        }
+    }
+
+    /// Execute the **primary task** of the watched command.
+    fn run(&mut self) {
+        match self.command {
+            Command::Build(_) | Command::Run(_) => self.build(),
+            Command::Test(_) => self.test(),
+        }
+    }
+
+    fn build(&mut self) {
+        let targets = self.targets();
+        self.spawn("build", &targets, false);
+    }
+
+    fn test(&mut self) {
+        let targets = if self.only_failing && !self.failing.lock().is_empty() {
+            self.failing.lock().iter().cloned().collect()
+        } else {
+            self.targets()
+        };
+        self.spawn("test", &targets, true);
+    }
+
+    /// The targets of the next run, according to the current [`Scope`].
+    fn targets(&self) -> Vec<String> {
+        let requested = match self.command {
+            Command::Build(ref args) => {
+                args.targets().iter().map(|target| target.to_string()).collect()
+            }
+            Command::Test(ref args) => {
+                args.targets().iter().map(|target| target.to_string()).collect()
+            }
+            Command::Run(ref args) => vec![args.target().to_string()],
+        };
+
+        if self.scope == Scope::All || self.changed.is_empty() {
+            return requested;
+        }
+
+        let _topic = utils::log::topic(Topic::Query).entered();
+        match self.affected_targets() {
+            Ok(affected) if !affected.is_empty() => affected,
+            Ok(_) => {
+                tracing::info!("No targets affected by the changed files, running all targets");
+                requested
+            }
+            Err(e) => {
+                tracing::warn!("Unable to determine affected targets, running all targets: {}", e);
+                requested
+            }
+        }
+    }
+
+    /// Query the build tool (e.g. `buck2 uquery`) for the targets depending on
+    /// the files changed since the last run.
+    #[tracing::instrument(skip(self), fields(changed = self.changed.len()))]
+    fn affected_targets(&self) -> Result<Vec<String>> {
+        let files = self
+            .changed
+            .iter()
+            .map(|path| path.strip_prefix(&self.root).unwrap_or(path).display().to_string())
+            .collect::<Vec<_>>();
+
+        let Some(query) = self.tool.rdeps_query(&files) else {
+            return Err(miette!("`{}` does not support querying reverse dependencies", self.tool));
+        };
+        let output = std::process::Command::new(self.tool.program())
+            .args(query)
+            .output()
+            .into_diagnostic()?;
+
+        if !output.status.success() {
+            return Err(miette!("{}", String::from_utf8_lossy(&output.stderr).trim()));
+        }
+
+        let stdout = String::from_utf8(output.stdout).into_diagnostic()?;
+        Ok(stdout.lines().map(|line| line.trim().to_string()).filter(|t| !t.is_empty()).collect())
+    }
+
+    /// Spawn `<build tool> <verb> <targets>`, killing the child of the previous
+    /// run if it is still running. When `capture_failures` is set, the
+    /// output is scanned for failing tests.
+    fn spawn(&mut self, verb: &'static str, targets: &[String], capture_failures: bool) {
+        self.kill();
+        // The span lasts until the child exits, tracing the whole run
+        let span = utils::log::topic(verb_topic(verb));
+        let _topic = span.enter();
+
+        // Builds of the target being run report its output, to (re)start it
+        let run = verb == "build" && matches!(self.command, Command::Run(_));
+
+        let mut cmd = std::process::Command::new(self.tool.program());
+        cmd.arg(verb);
+        if let Some(observer) = &self.observer {
+            cmd.args(observer.started(verb, targets));
+        }
+        if run {
+            cmd.args(self.tool.run_args(targets));
+            *self.artifact.lock() = None;
+        } else {
+            cmd.args(targets);
+        }
+
+        if capture_failures {
+            self.failing.lock().clear();
+        }
+        // Compiler diagnostics are reported in a machine-readable format
+        let report = !utils::report::format().is_human() && self.screen.is_none();
+        self.diagnostics.lock().clear();
+
+        if capture_failures || run || report || self.screen.is_some() {
+            cmd.stdout(Stdio::piped()).stderr(Stdio::piped());
+        } else {
+            cmd.stdout(Stdio::inherit()).stderr(Stdio::inherit());
+        }
+
+        let failing = capture_failures.then(|| self.failing.clone());
+        let artifact = (run && self.tool.reports_artifact()).then(|| self.artifact.clone());
+        let diagnostics = report.then(|| (self.diagnostics.clone(), self.root.clone()));
+        let observer = self.observer.clone();
+        let inspect = move |line: &str| {
+            if let Some(observer) = &observer {
+                observer.output(line);
+            }
+            if let (Some(failing), Some(target)) = (&failing, failed_test_target(line)) {
+                failing.lock().insert(target);
+            }
+            if let (Some(artifact), Some(path)) = (&artifact, built_artifact(line)) {
+                *artifact.lock() = Some(path);
+            }
+            if let Some((diagnostics, root)) = &diagnostics {
+                collect_diagnostic(&mut diagnostics.lock(), &strip_ansi(line), root);
+            }
+        };
+
+        match cmd.spawn() {
+            Ok(mut child) => {
+                if let Some(screen) = &self.screen {
+                    let tracker = self.observer.as_ref().and_then(|observer| observer.tracker());
+                    screen.lock().start_build(tracker);
+                }
+                if let Some(stdout) = child.stdout.take() {
+                    forward(stdout, Stream::Build, false, self.screen.clone(), inspect.clone());
+                }
+                if let Some(stderr) = child.stderr.take() {
+                    forward(stderr, Stream::Build, true, self.screen.clone(), inspect);
+                }
+                self.child = Some((verb, child, span.clone()));
+                self.changed.clear();
+            }
+            Err(e) => {
+                tracing::error!("Unable to spawn `{} {}`: {}", self.tool, verb, e);
+                if let Some(observer) = &self.observer {
+                    observer.finished(false);
+                }
+                self.end_cycle("failure");
+            }
+        }
+    }
+
+    /// (Re)start the **supervised process** from the output of the last
+    /// build, stopping the previous instance first.
+    #[tracing::instrument(skip(self))]
+    fn restart(&mut self) {
+        self.stop();
+        let _topic = utils::log::topic(Topic::Run).entered();
+
+        let artifact = self.artifact.lock().take();
+        let Some(artifact) = artifact.or_else(|| self.query_artifact()) else {
+            tracing::warn!("Unable to determine the output of the build, not starting the process");
+            return;
+        };
+
+        let mut cmd = std::process::Command::new(&artifact);
+        cmd.current_dir(&self.root);
+        if self.interactive {
+            // Key presses are meant for byakugan, not the process
+            cmd.stdin(Stdio::null());
+        }
+        if self.screen.is_some() {
+            cmd.stdout(Stdio::piped()).stderr(Stdio::piped());
+        }
+
+        match cmd.spawn() {
+            Ok(mut process) => {
+                tracing::info!("Started `{}` (pid {})", artifact.display(), process.id());
+                if let Some(stdout) = process.stdout.take() {
+                    forward(stdout, Stream::Process, false, self.screen.clone(), |_| {});
+                }
+                if let Some(stderr) = process.stderr.take() {
+                    forward(stderr, Stream::Process, true, self.screen.clone(), |_| {});
+                }
+                self.process = Some(process);
+            }
+            Err(e) => tracing::error!("Unable to start `{}`: {}", artifact.display(), e),
+        }
+    }
+
+    /// Query the build tool for the output of the target being run, for build
+    /// tools not reporting it while building (e.g. `bazel cquery`).
+    fn query_artifact(&self) -> Option<PathBuf> {
+        let Command::Run(ref args) = self.command else {
+            return None;
+        };
+        let query = self.tool.artifact_query(args.target().as_ref())?;
+
+        let output = std::process::Command::new(self.tool.program())
+            .args(&query)
+            .current_dir(&self.root)
+            .stderr(Stdio::null())
+            .output();
+        match output {
+            Ok(output) if output.status.success() => {
+                let artifact = self.tool.queried_artifact(&String::from_utf8_lossy(&output.stdout));
+                artifact.map(|artifact| self.root.join(artifact))
+            }
+            Ok(output) => {
+                tracing::warn!("`{} {}` failed ({})", self.tool, query.join(" "), output.status);
+                None
+            }
+            Err(e) => {
+                tracing::warn!("Unable to run `{} {}`: {}", self.tool, query.join(" "), e);
+                None
+            }
+        }
+    }
+
+    fn kill(&mut self) {
+        if let Some((_, mut child, span)) = self.child.take() {
+            let _topic = span.entered();
+            tracing::debug!("Killing child process {}", child.id());
+            let _ = child.kill();
+            let _ = child.wait();
+
+            if let Some(observer) = &self.observer {
+                observer.finished(false);
+            }
+        }
+    }
+
+    fn stop(&mut self) {
+        if let Some(mut process) = self.process.take() {
+            let _topic = utils::log::topic(Topic::Run).entered();
+            tracing::debug!("Stopping process {}", process.id());
+            let _ = process.kill();
+            let _ = process.wait();
+        }
+    }
+
+    fn is_ignored(&self, path: &Path) -> bool {
+        path.strip_prefix(&self.root).unwrap_or(path).components().any(|component| {
+            let component = component.as_os_str().to_string_lossy();
+            IGNORED_DIRS.contains(&component.as_ref()) || component.starts_with("bazel-")
+        })
+    }
+}
+
+/// The topic of the logs of a run of the build tool (e.g. `buck2 test`).
+fn verb_topic(verb: &str) -> Topic {
+    match verb {
+        "test" => Topic::Test,
+        _ => Topic::Build,
+    }
+}
+
+/// Forward the output of a child process line by line, either to the given
+/// pane of the TUI or to the terminal, inspecting each line along the way
+/// (e.g. to record failing test targets).
+fn forward(
+    pipe: impl Read + Send + 'static,
+    stream: Stream,
+    stderr: bool,
+    screen: Option<SharedScreen>,
+    inspect: impl Fn(&str) + Send + 'static,
+) {
+    thread::spawn(move || {
+        for line in BufReader::new(pipe).lines().map_while(|line| line.ok()) {
+            inspect(&line);
+
+            match &screen {
+                Some(screen) => screen.lock().push(stream, &line),
+                None if stderr => eprintln!("{line}"),
+                None => println!("{line}"),
+            }
+        }
+    });
+}
+
+/// Collect the compiler diagnostic whose header (or location, for `rustc`) is
+/// on a line of build output.
+fn collect_diagnostic(diagnostics: &mut Vec<CompilerDiagnostic>, line: &str, root: &Path) {
+    if let Some(last) = diagnostics.last_mut().filter(|last| !last.is_located()) {
+        if last.locate_from(line, root) {
+            return;
+        }
+    }
+    diagnostics.extend(CompilerDiagnostic::parse(line, root));
+}
+
+/// Extract the target of a failing test from a line of `buck2 test` output
+/// (e.g. `✗ Fail: root//foo:bar_test - baz::qux (0.1s)`).
+pub fn failed_test_target(line: &str) -> Option<String> {
+    let (_, rest) = line.split_once("Fail:")?;
+    rest.split_whitespace().next().filter(|target| target.contains("//")).map(str::to_string)
+}
+
+/// Extract the output of a target from the output of `bazel cquery
+/// --output=files <target>` (i.e. its first file, e.g. `bazel-out/k8-fastbuild/
+/// bin/foo/bar`).
+pub fn cquery_artifact(stdout: &str) -> Option<PathBuf> {
+    stdout.lines().map(str::trim).find(|line| !line.is_empty()).map(PathBuf::from)
+}
+
+/// Extract the executable built by `cargo build --message-format=json` from
+/// its messages (i.e. the last `compiler-artifact` with an `executable`).
+pub fn cargo_artifact(stdout: &str) -> Option<PathBuf> {
+    stdout
+        .lines()
+        .rev()
+        .filter_map(|line| serde_json::from_str::<serde_json::Value>(line).ok())
+        .filter(|message| message["reason"] == "compiler-artifact")
+        .find_map(|message| message["executable"].as_str().map(PathBuf::from))
+}
+
+/// Extract the output of a built target from a line of `buck2 build
+/// --show-full-output` output (e.g. `root//foo:bar /repo/buck-out/v2/.../bar`).
+pub fn built_artifact(line: &str) -> Option<PathBuf> {
+    let (target, path) = line.trim().split_once(' ')?;
+    let path = path.trim();
+    (target.contains("//") && !path.is_empty()).then(|| PathBuf::from(path))
+}
@@ -0,0 +1,428 @@
+mod pane;
+
+use crossterm::{
+    cursor::{
+        Hide,
+        MoveTo,
+        Show,
+    },
+    event::{
+        Event,
+        KeyCode,
+        KeyEventKind,
+    },
+    execute,
+    queue,
+    style::{
+        Attribute,
+        Color,
+        Print,
+        SetAttribute,
+        SetForegroundColor,
+    },
+    terminal::{
+        self,
+        EnterAlternateScreen,
+        LeaveAlternateScreen,
+    },
+};
+use diagnostics::compiler::is_diagnostic_header;
+use miette::{
+    IntoDiagnostic,
+    Result,
+};
+use parking_lot::Mutex;
+use std::{
+    io::{
+        self,
+        Write,
+    },
+    iter,
+    sync::Arc,
+    time::Duration,
+};
+use tracker::{
+    style::SPINNER,
+    Tracker,
+};
+
+use crate::keys::{
+    self,
+    Action,
+};
+
+pub use self::pane::*;
+
+/// The **source** of a line of output, used to route it to a pane.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Stream {
+    /// Output of the build tool.
+    Build,
+    /// Output (stdout and stderr) of the supervised process.
+    Process,
+    /// Log messages emitted by byakugan itself.
+    Log,
+}
+
+/// The **contents** of the split-pane TUI, shared between the watch loop and
+/// the threads forwarding the output of child processes.
+#[derive(Debug, Clone)]
+pub struct Screen {
+    /// The tracker of the current (or last) build.
+    pub progress:    Option<Tracker>,
+    /// Whether the last build succeeded and how long it took, once finished.
+    pub outcome:     Option<(bool, Duration)>,
+    pub build:       Pane,
+    pub output:      Pane,
+    pub diagnostics: Pane,
+    /// The status of the watch session (e.g. `affected, paused`).
+    pub status:      String,
+}
+
+pub type SharedScreen = Arc<Mutex<Screen>>;
+
+impl Default for Screen {
+    fn default() -> Self {
+        Self {
+            progress:    None,
+            outcome:     None,
+            build:       Pane::new("Build"),
+            output:      Pane::new("Output"),
+            diagnostics: Pane::new("Diagnostics"),
+            status:      String::new(),
+        }
+    }
+}
+
+impl Screen {
+    /// Route a line of output to the appropriate pane, extracting any
+    /// compiler diagnostics from the output of the build tool.
+    pub fn push(&mut self, stream: Stream, line: &str) {
+        match stream {
+            Stream::Build => {
+                let stripped = strip_ansi(line);
+                if is_diagnostic_header(&stripped) {
+                    self.diagnostics.push(stripped.trim());
+                } else if let Some(location) = stripped.trim_start().strip_prefix("--> ") {
+                    self.diagnostics.append_to_last(&format!(" ({location})"));
+                }
+                self.build.push(line);
+            }
+            Stream::Process => self.output.push(line),
+            Stream::Log => self.build.push(line),
+        }
+    }
+
+    /// Reset the progress and diagnostics for a new build, tracked by
+    /// `tracker` (if any).
+    pub fn start_build(&mut self, tracker: Option<Tracker>) {
+        self.progress = tracker;
+        self.outcome = None;
+        self.diagnostics.clear();
+    }
+
+    pub fn finish_build(&mut self, success: bool) {
+        self.outcome = self.progress.as_ref().map(|tracker| (success, tracker.elapsed()));
+    }
+
+    /// Render the progress of the current build as lines: its command, the
+    /// statistics of its tasks and the tasks still running, using the spinner
+    /// frame for the given tick.
+    pub fn render_progress(&self, tick: usize) -> Vec<String> {
+        let Some(tracker) = &self.progress else {
+            return Vec::new();
+        };
+        let spinner = SPINNER[tick % SPINNER.len()];
+        let command = tracker.command();
+
+        let header = match self.outcome {
+            None => format!("{spinner} {command}  {:.1}s", tracker.elapsed().as_secs_f64()),
+            Some((true, elapsed)) => format!("✔ {command}  built in {:.1}s", elapsed.as_secs_f64()),
+            Some((false, elapsed)) => {
+                format!("✘ {command}  failed after {:.1}s", elapsed.as_secs_f64())
+            }
+        };
+        let tasks = tracker.running().into_iter().map(|task| {
+            let indent = "  ".repeat(task.depth());
+            format!("{indent}{spinner} {}  {:.1}s", task.name(), task.elapsed().as_secs_f64())
+        });
+
+        iter::once(header).chain(iter::once(tracker.stats().to_string())).chain(tasks).collect()
+    }
+}
+
+/// The pane with **focus** (i.e. receiving scroll and search input).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+enum Focus {
+    #[default]
+    Build,
+    Output,
+    Diagnostics,
+}
+
+impl Focus {
+    fn next(self) -> Self {
+        match self {
+            Self::Build => Self::Output,
+            Self::Output => Self::Diagnostics,
+            Self::Diagnostics => Self::Build,
+        }
+    }
+
+    fn prev(self) -> Self {
+        self.next().next()
+    }
+}
+
+/// A **full-screen, split-pane TUI** showing the build progress, the output of
+/// the supervised process and the latest diagnostics, each with scrollback
+/// and search. The terminal is restored when the TUI is dropped.
+#[derive(Debug)]
+pub struct Tui {
+    screen: SharedScreen,
+    focus:  Focus,
+    /// The search query being typed (after pressing `/`).
+    input:  Option<String>,
+    /// The last submitted search query.
+    query:  Option<String>,
+    status: String,
+    tick:   usize,
+}
+
+impl Tui {
+    /// Switch to the alternate screen and start rendering the given screen.
+    pub fn enter(screen: SharedScreen) -> Result<Self> {
+        execute!(io::stdout(), EnterAlternateScreen, Hide).into_diagnostic()?;
+        Ok(Self {
+            screen,
+            focus: Focus::default(),
+            input: None,
+            query: None,
+            status: String::new(),
+            tick: 0,
+        })
+    }
+
+    /// Handle a terminal event, returning the watch [`Action`] requested (if
+    /// any) once TUI-specific keybindings have been accounted for.
+    pub fn handle(&mut self, event: Event) -> Option<Action> {
+        let Event::Key(key) = event else {
+            return None;
+        };
+        if key.kind != KeyEventKind::Press {
+            return None;
+        }
+
+        if let Some(input) = self.input.as_mut() {
+            match key.code {
+                KeyCode::Char(c) => input.push(c),
+                KeyCode::Backspace => {
+                    input.pop();
+                }
+                KeyCode::Enter => {
+                    self.query = self.input.take().filter(|query| !query.is_empty());
+                    self.search(true);
+                }
+                KeyCode::Esc => self.input = None,
+                _ => {}
+            }
+            return None;
+        }
+
+        let page = terminal::size().map_or(10, |(_, height)| usize::from(height / 2));
+        match key.code {
+            KeyCode::Tab => self.focus = self.focus.next(),
+            KeyCode::BackTab => self.focus = self.focus.prev(),
+            KeyCode::Up | KeyCode::Char('k') => self.with_focused(|pane| pane.scroll_up(1)),
+            KeyCode::Down | KeyCode::Char('j') => self.with_focused(|pane| pane.scroll_down(1)),
+            KeyCode::PageUp => self.with_focused(|pane| pane.scroll_up(page)),
+            KeyCode::PageDown => self.with_focused(|pane| pane.scroll_down(page)),
+            KeyCode::Home | KeyCode::Char('g') => self.with_focused(Pane::scroll_to_top),
+            KeyCode::End | KeyCode::Char('G') => self.with_focused(Pane::scroll_to_bottom),
+            KeyCode::Char('/') => self.input = Some(String::new()),
+            KeyCode::Char('n') => self.search(true),
+            KeyCode::Char('N') => self.search(false),
+            KeyCode::Esc if self.query.is_some() => {
+                self.query = None;
+                self.status.clear();
+                self.with_focused(Pane::clear_search);
+            }
+            _ => {
+                return match Action::from_key(&key) {
+                    // Clearing the screen clears the focused pane instead.
+                    Some(Action::Clear) => {
+                        self.with_focused(Pane::clear);
+                        None
+                    }
+                    action => action,
+                };
+            }
+        }
+
+        None
+    }
+
+    /// Render a frame.
+    pub fn draw(&mut self) -> io::Result<()> {
+        self.tick += 1;
+
+        let (width, height) = terminal::size()?;
+        let (width, height) = (usize::from(width), usize::from(height));
+        if width < 20 || height < 8 {
+            return Ok(());
+        }
+
+        let mut out = io::stdout().lock();
+        let screen = self.screen.lock();
+        let body = height - 1;
+        let left = width / 2;
+        let progress_height = body * 3 / 5;
+
+        // Build progress: the running tasks pinned above the build output
+        let progress = screen.render_progress(self.tick);
+        self.draw_pane(
+            &mut out,
+            (0, 0, left, progress_height),
+            &screen.build,
+            Focus::Build,
+            progress,
+        )?;
+        self.draw_pane(
+            &mut out,
+            (0, progress_height, left, body - progress_height),
+            &screen.diagnostics,
+            Focus::Diagnostics,
+            Vec::new(),
+        )?;
+        self.draw_pane(
+            &mut out,
+            (left, 0, width - left, body),
+            &screen.output,
+            Focus::Output,
+            Vec::new(),
+        )?;
+
+        // Help bar
+        let help = match &self.input {
+            Some(input) => format!("/{input}█"),
+            None => format!(
+                "[{}] Tab focus · ↑↓ scroll · / search · n/N next/prev · {} {}",
+                screen.status,
+                strip_ansi(&keys::help_footer()),
+                self.status
+            ),
+        };
+        queue!(
+            out,
+            MoveTo(0, (height - 1) as u16),
+            SetAttribute(Attribute::Reverse),
+            Print(fit(&help, width)),
+            SetAttribute(Attribute::Reset)
+        )?;
+
+        out.flush()
+    }
+
+    /// Draw a bordered pane at `(x, y, width, height)`, with `pinned` rows
+    /// shown above the (scrollable) contents of the pane.
+    fn draw_pane(
+        &self,
+        out: &mut impl Write,
+        (x, y, width, height): (usize, usize, usize, usize),
+        pane: &Pane,
+        focus: Focus,
+        pinned: Vec<String>,
+    ) -> io::Result<()> {
+        let inner = width.saturating_sub(2);
+        let border = if focus == self.focus { Color::Cyan } else { Color::DarkGrey };
+        let title = format!(" {} ", pane.title());
+        let top = format!("┌{}┐", fit(&format!("{title:─<inner$}"), inner));
+
+        queue!(out, SetForegroundColor(border), MoveTo(x as u16, y as u16), Print(top))?;
+        for row in 1..height.saturating_sub(1) {
+            queue!(
+                out,
+                MoveTo(x as u16, (y + row) as u16),
+                Print("│"),
+                MoveTo((x + width - 1) as u16, (y + row) as u16),
+                Print("│")
+            )?;
+        }
+        let bottom = format!("└{}┘", "─".repeat(inner));
+        queue!(
+            out,
+            MoveTo(x as u16, (y + height - 1) as u16),
+            Print(bottom),
+            SetForegroundColor(Color::Reset)
+        )?;
+
+        let rows = height.saturating_sub(2);
+        let pinned = pinned.into_iter().take(rows).collect::<Vec<_>>();
+        let visible = pane.visible(rows - pinned.len()).collect::<Vec<_>>();
+        let blank = rows - pinned.len() - visible.len();
+
+        for (row, line) in pinned.iter().enumerate() {
+            queue!(out, MoveTo((x + 1) as u16, (y + 1 + row) as u16), Print(fit(line, inner)))?;
+        }
+        for row in 0..blank {
+            let row = y + 1 + pinned.len() + row;
+            queue!(out, MoveTo((x + 1) as u16, row as u16), Print(" ".repeat(inner)))?;
+        }
+        for (row, (index, line)) in visible.into_iter().enumerate() {
+            let row = y + 1 + pinned.len() + blank + row;
+            let is_match = self.query.as_deref().map_or(false, |query| line.contains(query));
+            let attribute = match pane.cursor() {
+                Some(cursor) if cursor == index && focus == self.focus => Attribute::Reverse,
+                _ if is_match => Attribute::Bold,
+                _ => Attribute::Reset,
+            };
+            queue!(
+                out,
+                MoveTo((x + 1) as u16, row as u16),
+                SetAttribute(attribute),
+                Print(fit(line, inner)),
+                SetAttribute(Attribute::Reset)
+            )?;
+        }
+
+        Ok(())
+    }
+
+    /// Apply `f` to the focused pane.
+    fn with_focused<T>(&self, f: impl FnOnce(&mut Pane) -> T) -> T {
+        let mut screen = self.screen.lock();
+        f(match self.focus {
+            Focus::Build => &mut screen.build,
+            Focus::Output => &mut screen.output,
+            Focus::Diagnostics => &mut screen.diagnostics,
+        })
+    }
+
+    fn search(&mut self, forward: bool) {
+        let Some(query) = self.query.clone() else {
+            return;
+        };
+        let found = self.with_focused(|pane| {
+            if forward {
+                pane.search_next(&query)
+            } else {
+                pane.search_prev(&query)
+            }
+        });
+        self.status = if found { format!("/{query}") } else { format!("/{query}: no match") };
+    }
+}
+
+impl Drop for Tui {
+    fn drop(&mut self) {
+        let _ = execute!(io::stdout(), Show, LeaveAlternateScreen);
+    }
+}
+
+/// Truncate or pad a line to exactly `width` characters.
+fn fit(line: &str, width: usize) -> String {
+    let mut fitted = line.chars().take(width).collect::<String>();
+    let len = fitted.chars().count();
+    fitted.extend(std::iter::repeat(' ').take(width - len));
+    fitted
+}
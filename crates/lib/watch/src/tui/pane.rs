@@ -0,0 +1,132 @@
+use std::collections::VecDeque;
+
+pub use utils::render::strip_ansi;
+
+/// The maximum number of lines of **scrollback** retained by each pane.
+pub const SCROLLBACK: usize = 10_000;
+
+/// A scrollable, searchable **pane** of lines (e.g. the stdout/stderr of the
+/// supervised process).
+#[derive(Debug, Clone)]
+pub struct Pane {
+    title:  &'static str,
+    lines:  VecDeque<String>,
+    /// How many lines the view is scrolled up from the bottom (`0` follows
+    /// new output).
+    scroll: usize,
+    /// The line of the currently selected search match.
+    cursor: Option<usize>,
+}
+
+impl Pane {
+    pub fn new(title: &'static str) -> Self {
+        Self { title, lines: VecDeque::new(), scroll: 0, cursor: None }
+    }
+
+    pub fn title(&self) -> &'static str {
+        self.title
+    }
+
+    pub fn lines(&self) -> &VecDeque<String> {
+        &self.lines
+    }
+
+    /// Append a line, stripping any ANSI escape sequences (and expanding tabs)
+    /// so that the line width can be computed when rendering.
+    pub fn push(&mut self, line: &str) {
+        if self.lines.len() == SCROLLBACK {
+            self.lines.pop_front();
+            self.cursor = self.cursor.and_then(|cursor| cursor.checked_sub(1));
+        }
+        self.lines.push_back(displayed(line));
+
+        // Keep the view steady while the user is scrolled up.
+        if self.scroll > 0 {
+            self.scroll = (self.scroll + 1).min(self.lines.len().saturating_sub(1));
+        }
+    }
+
+    /// Append `text` to the last line (e.g. the location of a diagnostic
+    /// reported on the line following its header).
+    pub fn append_to_last(&mut self, text: &str) {
+        if let Some(last) = self.lines.back_mut() {
+            last.push_str(&displayed(text));
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.lines.clear();
+        self.scroll = 0;
+        self.cursor = None;
+    }
+
+    pub fn scroll_up(&mut self, lines: usize) {
+        self.scroll = (self.scroll + lines).min(self.lines.len().saturating_sub(1));
+    }
+
+    pub fn scroll_down(&mut self, lines: usize) {
+        self.scroll = self.scroll.saturating_sub(lines);
+    }
+
+    pub fn scroll_to_top(&mut self) {
+        self.scroll = self.lines.len().saturating_sub(1);
+    }
+
+    pub fn scroll_to_bottom(&mut self) {
+        self.scroll = 0;
+    }
+
+    /// The lines visible in a view of the given `height`, alongside their
+    /// index within the scrollback.
+    pub fn visible(&self, height: usize) -> impl Iterator<Item = (usize, &String)> {
+        let end = self.lines.len().saturating_sub(self.scroll);
+        let start = end.saturating_sub(height);
+        self.lines.range(start..end).enumerate().map(move |(i, line)| (start + i, line))
+    }
+
+    pub fn cursor(&self) -> Option<usize> {
+        self.cursor
+    }
+
+    /// Select the next line (searching backwards from the current match, i.e.
+    /// towards older output) containing `query`, scrolling it into view.
+    /// Returns `false` if there is no match.
+    pub fn search_next(&mut self, query: &str) -> bool {
+        let from = self.cursor.unwrap_or(self.lines.len());
+        let found = (0..from)
+            .rev()
+            .chain((from..self.lines.len()).rev())
+            .find(|&i| self.lines[i].contains(query));
+        self.select(found)
+    }
+
+    /// Select the previous match (towards newer output).
+    pub fn search_prev(&mut self, query: &str) -> bool {
+        let from = self.cursor.map_or(0, |cursor| cursor + 1);
+        let found = (from..self.lines.len())
+            .chain(0..from.min(self.lines.len()))
+            .find(|&i| self.lines[i].contains(query));
+        self.select(found)
+    }
+
+    pub fn clear_search(&mut self) {
+        self.cursor = None;
+    }
+
+    fn select(&mut self, line: Option<usize>) -> bool {
+        match line {
+            Some(line) => {
+                self.cursor = Some(line);
+                self.scroll = self.lines.len() - line - 1;
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+/// The text of a line as displayed within a pane, without ANSI escape
+/// sequences and with tabs expanded to four spaces.
+fn displayed(line: &str) -> String {
+    strip_ansi(line).replace('\t', "    ")
+}
@@ -0,0 +1,241 @@
+use diagnostics::errors::WatchError;
+use notify::{
+    Config,
+    ErrorKind,
+    EventHandler,
+    PollWatcher,
+    RecommendedWatcher,
+    RecursiveMode,
+    Watcher,
+    WatcherKind,
+};
+use std::{
+    fs,
+    io,
+    path::{
+        Path,
+        PathBuf,
+    },
+    sync::mpsc::Sender,
+    time::Duration,
+};
+
+use crate::WatchEvent;
+
+/// The per-user limit of inotify watches.
+pub const MAX_USER_WATCHES: &str = "/proc/sys/fs/inotify/max_user_watches";
+
+/// The number of polled directories listed by the warning reporting them.
+const LISTED_DIRS: usize = 3;
+
+/// The **watchers** of the files of the workspace: the native watcher of the
+/// platform (e.g. inotify) for as many directories as it allows, and a
+/// [`PollWatcher`] for the subtrees past its limit.
+///
+/// Once the limit is reached (e.g. `fs.inotify.max_user_watches`), the watches
+/// of the subtree being registered are released and its subdirectories are
+/// registered one by one instead, such that only the subtrees that could not
+/// be registered at all are polled.
+pub struct Watchers {
+    /// The sender of the watch loop, which events are reported to.
+    tx:       Sender<WatchEvent>,
+    /// The native watcher, unless it could not be created.
+    native:   Option<Box<dyn Watcher>>,
+    /// The watcher polling the subtrees the native watcher could not watch,
+    /// created once needed.
+    poll:     Option<PollWatcher>,
+    /// How often the polled subtrees are checked for changes.
+    interval: Duration,
+    /// The root of the watched workspace.
+    root:     PathBuf,
+    /// The polled subtrees.
+    polled:   Vec<PathBuf>,
+}
+
+impl Watchers {
+    /// The watchers reporting to `tx`, polling every `interval`.
+    pub fn new(tx: Sender<WatchEvent>, interval: Duration) -> Self {
+        let kind = RecommendedWatcher::kind();
+        let native = if kind == WatcherKind::PollWatcher {
+            PollWatcher::new(handler(&tx), poll_config(interval))
+                .map(|watcher| Box::new(watcher) as Box<dyn Watcher>)
+        } else {
+            RecommendedWatcher::new(handler(&tx), Config::default())
+                .map(|watcher| Box::new(watcher) as Box<dyn Watcher>)
+        };
+        let native = native
+            .map_err(|e| {
+                tracing::warn!("Unable to create a {kind:?} watcher, polling instead: {e}")
+            })
+            .ok();
+
+        Self { tx, native, poll: None, interval, root: PathBuf::new(), polled: vec![] }
+    }
+
+    /// The watchers using the given `native` watcher (which reports its events
+    /// itself), falling back to polling past its limit.
+    pub fn with_native(
+        native: Box<dyn Watcher>,
+        tx: Sender<WatchEvent>,
+        interval: Duration,
+    ) -> Self {
+        Self {
+            tx,
+            native: Some(native),
+            poll: None,
+            interval,
+            root: PathBuf::new(),
+            polled: vec![],
+        }
+    }
+
+    /// Watch the workspace at `root` recursively.
+    pub fn watch(&mut self, root: &Path) -> Result<(), WatchError> {
+        self.root = root.to_path_buf();
+        self.register(root)
+    }
+
+    /// The subtrees polled instead of being watched natively.
+    pub fn polled(&self) -> &[PathBuf] {
+        &self.polled
+    }
+
+    /// The warning to report when the limit of the native watcher was reached
+    /// (i.e. some subtrees are polled), with how to raise it.
+    pub fn limit_reached(&self) -> Option<WatchError> {
+        if self.native.is_none() || self.polled.is_empty() {
+            return None;
+        }
+
+        let max = fs::read_to_string(MAX_USER_WATCHES)
+            .ok()
+            .and_then(|max| max.trim().parse::<usize>().ok());
+        let mut dirs = self
+            .polled
+            .iter()
+            .take(LISTED_DIRS)
+            .map(|dir| match dir.strip_prefix(&self.root) {
+                Ok(dir) if dir.as_os_str().is_empty() => ".".to_string(),
+                Ok(dir) => dir.display().to_string(),
+                Err(_) => dir.display().to_string(),
+            })
+            .collect::<Vec<_>>()
+            .join(", ");
+        if self.polled.len() > LISTED_DIRS {
+            dirs.push_str(&format!(" and {} more", self.polled.len() - LISTED_DIRS));
+        }
+
+        Some(WatchError::WatchLimit {
+            polled:    self.polled.len(),
+            dirs:      dirs.into(),
+            max:       max
+                .map_or_else(|| "a limited number of".into(), |max| max.to_string().into()),
+            suggested: suggested_limit(max),
+            interval:  humantime::format_duration(self.interval).to_string().into(),
+        })
+    }
+
+    /// Register the subtree at `dir` with the native watcher, or as many of
+    /// its subtrees as it allows, polling the rest.
+    fn register(&mut self, dir: &Path) -> Result<(), WatchError> {
+        let Some(native) = self.native.as_mut() else {
+            return self.poll(dir);
+        };
+
+        match native.watch(dir, RecursiveMode::Recursive) {
+            Ok(()) => return Ok(()),
+            Err(e) if is_watch_limit(&e) => {
+                tracing::debug!("Reached the watch limit within {}", dir.display());
+            }
+            Err(e) => return Err(watch_failed(dir, e)),
+        }
+
+        // Release the watches registered within the subtree before the limit
+        // was reached. Directories created later within `dir` itself are not
+        // watched, as it is no longer watched recursively.
+        let _ = native.unwatch(dir);
+        if native.watch(dir, RecursiveMode::NonRecursive).is_err() {
+            return self.poll(dir);
+        }
+
+        for subdir in subdirectories(dir).map_err(|e| watch_failed(dir, e))? {
+            self.register(&subdir)?;
+        }
+
+        Ok(())
+    }
+
+    /// Poll the subtree at `dir`.
+    fn poll(&mut self, dir: &Path) -> Result<(), WatchError> {
+        let poll = match &mut self.poll {
+            Some(poll) => poll,
+            None => self.poll.insert(
+                PollWatcher::new(handler(&self.tx), poll_config(self.interval))
+                    .map_err(|e| watch_failed(dir, e))?,
+            ),
+        };
+
+        tracing::debug!("Polling {} every {:?}", dir.display(), self.interval);
+        poll.watch(dir, RecursiveMode::Recursive).map_err(|e| watch_failed(dir, e))?;
+        self.polled.push(dir.to_path_buf());
+
+        Ok(())
+    }
+}
+
+/// The limit of inotify watches suggested to raise the current one (if known)
+/// to.
+///
+/// ```rust
+/// use watch::watcher::suggested_limit;
+///
+/// assert_eq!(suggested_limit(None), 524_288);
+/// assert_eq!(suggested_limit(Some(8_192)), 524_288);
+/// assert_eq!(suggested_limit(Some(524_288)), 1_048_576);
+/// ```
+pub fn suggested_limit(max: Option<usize>) -> usize {
+    max.map_or(0, |max| (max * 2).next_power_of_two()).max(524_288)
+}
+
+/// Whether a watcher failed for reaching the limit of watches (i.e. `ENOSPC`
+/// for inotify).
+fn is_watch_limit(error: &notify::Error) -> bool {
+    match &error.kind {
+        ErrorKind::MaxFilesWatch => true,
+        ErrorKind::Io(e) => e.raw_os_error() == Some(libc::ENOSPC),
+        _ => false,
+    }
+}
+
+/// The configuration of the polling watcher: files are compared by their
+/// modification time only, as hashing their contents would read every file of
+/// the polled subtrees (e.g. as large as `buck-out`) on every poll.
+fn poll_config(interval: Duration) -> Config {
+    Config::default().with_poll_interval(interval)
+}
+
+fn handler(tx: &Sender<WatchEvent>) -> impl EventHandler {
+    let tx = tx.clone();
+    move |event| {
+        let _ = tx.send(WatchEvent::Fs(event));
+    }
+}
+
+/// The subdirectories of `dir`, sorted (symbolic links are not followed).
+fn subdirectories(dir: &Path) -> io::Result<Vec<PathBuf>> {
+    let mut subdirs = fs::read_dir(dir)?
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.file_type().map_or(false, |file_type| file_type.is_dir()))
+        .map(|entry| entry.path())
+        .collect::<Vec<_>>();
+    subdirs.sort();
+
+    Ok(subdirs)
+}
+
+fn watch_failed(dir: &Path, reason: impl ToString) -> WatchError {
+    WatchError::WatchFailed {
+        path:   dir.display().to_string().into(),
+        reason: reason.to_string().into(),
+    }
+}
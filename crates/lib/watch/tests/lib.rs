@@ -1,7 +1,276 @@
 #[cfg(test)]
 mod watch_test_suite {
+    use crossterm::event::{
+        KeyCode,
+        KeyEvent,
+        KeyModifiers,
+    };
+    use diagnostics::errors::WatchError;
+    use notify::{
+        Config,
+        ErrorKind,
+        EventHandler,
+        RecursiveMode,
+        Watcher,
+        WatcherKind,
+    };
+    use std::{
+        collections::BTreeSet,
+        fs,
+        path::{
+            Path,
+            PathBuf,
+        },
+        sync::{
+            mpsc,
+            Arc,
+            Mutex,
+        },
+        time::{
+            Duration,
+            Instant,
+        },
+    };
+    use tracker::Tracker;
+    use watch::{
+        built_artifact,
+        cargo_artifact,
+        cquery_artifact,
+        failed_test_target,
+        tui::{
+            strip_ansi,
+            Pane,
+            Screen,
+            Stream,
+            SCROLLBACK,
+        },
+        Action,
+        WatchEvent,
+        Watchers,
+    };
+
     #[test]
     fn test_watch() {
         assert_eq!(1, 1);
     }
+
+    #[test]
+    fn test_key_actions() {
+        let key = |code| KeyEvent::new(code, KeyModifiers::NONE);
+
+        assert_eq!(Action::from_key(&key(KeyCode::Char('r'))), Some(Action::Rebuild));
+        assert_eq!(Action::from_key(&key(KeyCode::Char('c'))), Some(Action::Clear));
+        assert_eq!(Action::from_key(&key(KeyCode::Char('x'))), None);
+        assert_eq!(
+            Action::from_key(&KeyEvent::new(KeyCode::Char('c'), KeyModifiers::CONTROL)),
+            Some(Action::Quit)
+        );
+    }
+
+    #[test]
+    fn test_failed_test_target() {
+        assert_eq!(
+            failed_test_target("✗ Fail: root//foo:bar_test - baz::qux (0.1s)"),
+            Some("root//foo:bar_test".to_string())
+        );
+        assert_eq!(failed_test_target("✓ Pass: root//foo:bar_test (0.1s)"), None);
+    }
+
+    #[test]
+    fn test_built_artifact() {
+        assert_eq!(
+            built_artifact("root//foo:bar /repo/buck-out/v2/gen/root/foo/bar"),
+            Some(PathBuf::from("/repo/buck-out/v2/gen/root/foo/bar"))
+        );
+        assert_eq!(built_artifact("BUILD SUCCEEDED"), None);
+    }
+
+    #[test]
+    fn test_cquery_artifact() {
+        assert_eq!(
+            cquery_artifact(
+                "\nbazel-out/k8-fastbuild/bin/foo/bar\nbazel-out/k8-fastbuild/bin/foo/bar.\
+                 runfiles\n"
+            ),
+            Some(PathBuf::from("bazel-out/k8-fastbuild/bin/foo/bar"))
+        );
+        assert_eq!(cquery_artifact(""), None);
+    }
+
+    #[test]
+    fn test_cargo_artifact() {
+        let messages = [
+            r#"{"reason":"compiler-artifact","target":{"name":"dep"},"executable":null}"#,
+            r#"{"reason":"compiler-artifact","target":{"name":"bar"},"executable":"/repo/target/debug/bar"}"#,
+            r#"{"reason":"build-finished","success":true}"#,
+        ];
+        assert_eq!(
+            cargo_artifact(&messages.join("\n")),
+            Some(PathBuf::from("/repo/target/debug/bar"))
+        );
+        assert_eq!(cargo_artifact(messages[0]), None);
+    }
+
+    #[test]
+    fn test_strip_ansi() {
+        assert_eq!(strip_ansi("\u{1b}[1;31merror\u{1b}[0m: oops\r"), "error: oops");
+        assert_eq!(strip_ansi("\u{1b}]8;;https://x.y\u{7}link\u{1b}]8;;\u{7}"), "link");
+    }
+
+    #[test]
+    fn test_pane_search() {
+        let mut pane = Pane::new("Output");
+        for line in ["listening on :8080", "GET /", "GET /health", "POST /login"] {
+            pane.push(line);
+        }
+
+        assert!(pane.search_next("GET"));
+        assert_eq!(pane.cursor(), Some(2));
+        assert!(pane.search_next("GET"));
+        assert_eq!(pane.cursor(), Some(1));
+        assert!(pane.search_prev("GET"));
+        assert_eq!(pane.cursor(), Some(2));
+        assert!(!pane.search_next("DELETE"));
+
+        let visible = pane.visible(2).map(|(_, line)| line.as_str()).collect::<Vec<_>>();
+        assert_eq!(visible, ["GET /", "GET /health"]);
+
+        pane.push("\tat main.rs:1");
+        assert_eq!(pane.lines().back().unwrap(), "    at main.rs:1");
+    }
+
+    #[test]
+    fn test_pane_scrollback() {
+        let mut pane = Pane::new("Output");
+        for line in 0..SCROLLBACK {
+            pane.push(&line.to_string());
+        }
+        pane.scroll_to_top();
+        for line in SCROLLBACK..SCROLLBACK + 10 {
+            pane.push(&line.to_string());
+        }
+
+        assert_eq!(pane.lines().len(), SCROLLBACK);
+        let visible = pane.visible(2).map(|(_, line)| line.as_str()).collect::<Vec<_>>();
+        assert_eq!(visible, ["10"]);
+    }
+
+    #[test]
+    fn test_diagnostics() {
+        let mut screen = Screen::default();
+        screen.push(Stream::Build, "error: unused variable: `x`");
+        screen.push(Stream::Build, "  --> src/main.rs:2:9");
+        screen.push(Stream::Build, "errors are fine");
+        screen.push(Stream::Process, "listening on :8080");
+        assert_eq!(screen.diagnostics.lines()[0], "error: unused variable: `x` (src/main.rs:2:9)");
+        assert_eq!(screen.diagnostics.lines().len(), 1);
+        assert_eq!(screen.build.lines().len(), 3);
+        assert_eq!(screen.output.lines().len(), 1);
+    }
+
+    #[test]
+    fn test_build_progress() {
+        let tracker = Tracker::new("build //foo:bar");
+        tracker.begin("a", "//foo:bar (rustc)", None);
+
+        let mut screen = Screen::default();
+        screen.start_build(Some(tracker.clone()));
+        let progress = screen.render_progress(0);
+        assert!(progress[0].starts_with("◐ build //foo:bar  "));
+        assert_eq!(progress[1], "In progress 1, completed 0, failed 0, cache hits 0");
+        assert!(progress[2].starts_with("◐ //foo:bar (rustc)  "));
+
+        tracker.end("a", true);
+        screen.finish_build(true);
+        let progress = screen.render_progress(1);
+        assert!(progress[0].starts_with("✔ build //foo:bar  built in "));
+        assert_eq!(progress.len(), 2);
+    }
+
+    /// A watcher allowing a limited number of directory watches, as inotify.
+    struct LimitedWatcher {
+        max:     usize,
+        watches: Arc<Mutex<BTreeSet<PathBuf>>>,
+    }
+
+    impl Watcher for LimitedWatcher {
+        fn new<F: EventHandler>(_: F, _: Config) -> notify::Result<Self> {
+            unimplemented!()
+        }
+
+        fn watch(&mut self, path: &Path, mode: RecursiveMode) -> notify::Result<()> {
+            let mut dirs = vec![path.to_path_buf()];
+            if mode == RecursiveMode::Recursive {
+                dirs = walk(path);
+            }
+
+            let mut watches = self.watches.lock().unwrap();
+            for dir in dirs {
+                if watches.len() == self.max {
+                    return Err(notify::Error::new(ErrorKind::MaxFilesWatch));
+                }
+                watches.insert(dir);
+            }
+
+            Ok(())
+        }
+
+        fn unwatch(&mut self, path: &Path) -> notify::Result<()> {
+            self.watches.lock().unwrap().retain(|dir| !dir.starts_with(path));
+            Ok(())
+        }
+
+        fn kind() -> WatcherKind {
+            WatcherKind::Inotify
+        }
+    }
+
+    fn walk(dir: &Path) -> Vec<PathBuf> {
+        let mut subdirs = fs::read_dir(dir)
+            .unwrap()
+            .map(|entry| entry.unwrap().path())
+            .filter(|path| path.is_dir())
+            .collect::<Vec<_>>();
+        subdirs.sort();
+
+        std::iter::once(dir.to_path_buf()).chain(subdirs.iter().flat_map(|dir| walk(dir))).collect()
+    }
+
+    #[test]
+    fn test_watch_limit_fallback() {
+        let root = std::env::temp_dir().join(format!("bkg-watch-limit-{}", std::process::id()));
+        for dir in ["a/a1", "a/a2", "b/b1/deep", "c"] {
+            fs::create_dir_all(root.join(dir)).unwrap();
+        }
+
+        // 8 directories, only 5 of which can be watched
+        let watches = Arc::new(Mutex::new(BTreeSet::new()));
+        let native = LimitedWatcher { max: 5, watches: watches.clone() };
+        let (tx, rx) = mpsc::channel();
+        let mut watchers = Watchers::with_native(Box::new(native), tx, Duration::from_millis(50));
+        watchers.watch(&root).unwrap();
+
+        let watched = ["", "a", "a/a1", "a/a2", "b"].map(|dir| root.join(dir));
+        assert_eq!(*watches.lock().unwrap(), BTreeSet::from(watched));
+        assert_eq!(watchers.polled(), [root.join("b/b1"), root.join("c")]);
+        match watchers.limit_reached() {
+            Some(WatchError::WatchLimit { polled, dirs, .. }) => {
+                assert_eq!(polled, 2);
+                assert_eq!(dirs, "b/b1, c");
+            }
+            warning => panic!("expected a watch limit warning, got {warning:?}"),
+        }
+
+        // Changes to the polled directories are still noticed
+        let file = root.join("c").join("BUCK");
+        fs::write(&file, "").unwrap();
+        let deadline = Instant::now() + Duration::from_secs(10);
+        let noticed = std::iter::from_fn(|| {
+            rx.recv_timeout(deadline.saturating_duration_since(Instant::now())).ok()
+        })
+        .any(|event| matches!(event, WatchEvent::Fs(Ok(event)) if event.paths.contains(&file)));
+        assert!(noticed);
+
+        fs::remove_dir_all(&root).unwrap();
+    }
 }